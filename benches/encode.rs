@@ -0,0 +1,123 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use datamatrix::{DataMatrixBuilder, Effort};
+
+/// Counts the allocations to compare the encode functions.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// Deterministic pseudo random bytes, like compressed data.
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state >> 24) as u8
+        })
+        .collect()
+}
+
+fn encode_binary(c: &mut Criterion) {
+    let data = random_bytes(1500);
+    let mut group = c.benchmark_group("encode_binary");
+    for effort in [Effort::Exhaustive, Effort::Balanced] {
+        let builder = DataMatrixBuilder::new().with_effort(effort);
+        group.bench_function(format!("{:?}", effort), |b| {
+            b.iter(|| builder.clone().encode(black_box(&data)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn encode_batch(c: &mut Criterion) {
+    let data: Vec<Vec<u8>> = (0..10_000)
+        .map(|i| format!("LOT {:06}", i).into_bytes())
+        .collect();
+    // the shortest path search allocates for each candidate, so leave it out here
+    let builder = DataMatrixBuilder::new().with_effort(Effort::Fast);
+    let mut codewords = Vec::new();
+    let encode = count_allocations(|| {
+        for data in &data {
+            black_box(builder.clone().encode(data).unwrap());
+        }
+    });
+    let encode_into = count_allocations(|| {
+        for data in &data {
+            black_box(builder.encode_into(data, &mut codewords).unwrap());
+        }
+    });
+    println!(
+        "allocations for {} encodes: {} with encode, {} with encode_into",
+        data.len(),
+        encode,
+        encode_into
+    );
+
+    let mut group = c.benchmark_group("encode_batch");
+    group.sample_size(10);
+    group.bench_function("encode", |b| {
+        b.iter(|| {
+            for data in &data {
+                black_box(builder.clone().encode(data).unwrap());
+            }
+        })
+    });
+    group.bench_function("encode_into", |b| {
+        b.iter(|| {
+            for data in &data {
+                black_box(builder.encode_into(data, &mut codewords).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn encode_serial(c: &mut Criterion) {
+    let builder = DataMatrixBuilder::new();
+    let serials = builder.clone().serial_encoder(b"LOT 2024 SN", 6).unwrap();
+    let mut group = c.benchmark_group("encode_serial");
+    group.bench_function("builder", |b| {
+        let mut number = 0;
+        b.iter(|| {
+            number = (number + 1) % 1_000_000;
+            let data = format!("LOT 2024 SN{:06}", number);
+            builder.clone().encode(black_box(data.as_bytes())).unwrap()
+        })
+    });
+    group.bench_function("serial_encoder", |b| {
+        let mut number = 0;
+        b.iter(|| {
+            number = (number + 1) % 1_000_000;
+            serials.encode(black_box(number)).unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, encode_binary, encode_batch, encode_serial);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use datamatrix::{errorcode, DataMatrix, SymbolList, SymbolSize};
+
+fn encode_error(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode_error");
+    for size in [
+        SymbolSize::Square10,
+        SymbolSize::Square52,
+        SymbolSize::Square104,
+        SymbolSize::Square144,
+    ] {
+        let data: Vec<u8> = (0..size.num_data_codewords())
+            .map(|i| (i * 37 % 256) as u8)
+            .collect();
+        group.bench_function(format!("{:?}", size), |b| {
+            b.iter(|| errorcode::encode_error(black_box(&data), size))
+        });
+    }
+    group.finish();
+}
+
+fn encode_144(c: &mut Criterion) {
+    let data = b"0123456789".repeat(300);
+    c.bench_function("encode 144x144", |b| {
+        b.iter(|| DataMatrix::encode(black_box(&data), SymbolList::from(SymbolSize::Square144)))
+    });
+}
+
+criterion_group!(benches, encode_error, encode_144);
+criterion_main!(benches);
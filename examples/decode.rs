@@ -1,6 +1,6 @@
 use std::io::{self, Read};
 
-use datamatrix::{placement::MatrixMap, DataMatrix};
+use datamatrix::{placement::MatrixMap, DataMatrix, DecodingError};
 
 fn main() {
     // First read a bitmap image as ASCII encoded 0s and 1s from stdin.
@@ -33,7 +33,18 @@ fn main() {
         .collect::<Vec<_>>();
 
     let (matrix_map, size) = MatrixMap::try_from_bits(&pixels, width).unwrap();
-    let data = DataMatrix::decode(&pixels, width).unwrap();
+    let data = match DataMatrix::decode(&pixels, width) {
+        Ok(data) => data,
+        Err(DecodingError::ErrorCorrection(err)) => {
+            eprintln!("Too many errors in the symbol: {:?}", err);
+            std::process::exit(1);
+        }
+        // the error types are non-exhaustive, new variants may be added
+        Err(err) => {
+            eprintln!("Decoding failed: {:?}", err);
+            std::process::exit(1);
+        }
+    };
     println!("{}", matrix_map.bitmap().unicode());
     println!("Size: {:?}", size);
     println!("Content: {:?}", std::str::from_utf8(&data).unwrap());
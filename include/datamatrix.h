@@ -0,0 +1,62 @@
+/* C interface of the datamatrix crate, build it with the feature `ffi`. */
+#ifndef DATAMATRIX_H
+#define DATAMATRIX_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Success. */
+#define DMTX_OK 0
+/* A required pointer was null. */
+#define DMTX_NULL_POINTER 1
+/* The data does not fit into the symbol, or can not be encoded with the options. */
+#define DMTX_TOO_MUCH_DATA 2
+/* No symbol has the requested number of rows and columns. */
+#define DMTX_INVALID_SIZE 3
+/* The encoder failed otherwise. */
+#define DMTX_ENCODING_FAILED 4
+
+/* Options for dmtx_encode. */
+typedef struct {
+    /* Number of rows of the symbol, or zero to pick the smallest one which fits. */
+    uint32_t rows;
+    /* Number of columns of the symbol, zero if rows is zero. */
+    uint32_t columns;
+    /* Use the macro codewords where possible. */
+    bool use_macros;
+    /* Encode GS1 data with FNC1 for the group separators. */
+    bool gs1;
+} DmtxOptions;
+
+/* Bitmap of an encoded symbol, free it with dmtx_bitmap_free. */
+typedef struct {
+    /* The modules row by row, 1 for dark and 0 for light. */
+    uint8_t *modules;
+    /* Number of modules per row. */
+    size_t width;
+    /* Number of rows. */
+    size_t height;
+} DmtxBitmap;
+
+/* Get the default options, any symbol size of the default list and macros enabled. */
+DmtxOptions dmtx_options_default(void);
+
+/* Encode len bytes at data and write the bitmap to out, options may be null. */
+int dmtx_encode(const uint8_t *data, size_t len, const DmtxOptions *options, DmtxBitmap *out);
+
+/* Free the modules of a bitmap returned by dmtx_encode and reset it. */
+void dmtx_bitmap_free(DmtxBitmap *bitmap);
+
+/* Get a static, NUL terminated description of an error code. */
+const char *dmtx_error_message(int code);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* DATAMATRIX_H */
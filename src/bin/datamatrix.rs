@@ -0,0 +1,281 @@
+//! Command line interface for encoding and decoding, requires the feature `cli`.
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+use datamatrix::{detection, placement::SvgOptions, DataMatrix, DataMatrixBuilder, SymbolList};
+
+const USAGE: &str = "\
+Usage:
+    datamatrix encode [TEXT] [OPTIONS]
+    datamatrix decode IMAGE
+
+Encode TEXT, or the standard input if it is missing, and print the symbol.
+Decode a single symbol in an image file and print its content.
+
+Options for encode:
+    --size SIZE        min (default), square, rect, or ROWSxCOLUMNS like 16x16
+    --out FORMAT       unicode (default), ascii, svg, or png
+    --file PATH        write to PATH instead of the standard output
+    --scale N          pixels per module for png, SVG units for svg (default 4)
+    --quiet-zone N     light modules on each side (default 1)
+    --gs1              encode GS1 data, group separators become FNC1
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Unicode,
+    Ascii,
+    Svg,
+    Png,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct EncodeArgs {
+    text: Option<String>,
+    symbol_list: SymbolList,
+    format: Format,
+    file: Option<String>,
+    scale: u32,
+    quiet_zone: u32,
+    gs1: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    Encode(EncodeArgs),
+    Decode(String),
+    Help,
+}
+
+fn parse_size(size: &str) -> Result<SymbolList, String> {
+    match size {
+        "min" => return Ok(SymbolList::default()),
+        "square" => return Ok(SymbolList::default().enforce_square()),
+        "rect" => return Ok(SymbolList::default().enforce_rectangular()),
+        _ => (),
+    }
+    let dimensions = size
+        .split_once('x')
+        .and_then(|(rows, cols)| Some((rows.parse().ok()?, cols.parse().ok()?)));
+    SymbolList::with_extended_rectangles()
+        .iter()
+        .find(|symbol| {
+            let ((width, height), _) = symbol.module_dimensions();
+            dimensions == Some((height, width))
+        })
+        .map(SymbolList::from)
+        .ok_or_else(|| format!("unknown symbol size '{}'", size))
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+    let command = match args.next().as_deref() {
+        None | Some("-h" | "--help" | "help") => return Ok(Command::Help),
+        Some("decode") => {
+            let path = args.next().ok_or("missing IMAGE for decode")?;
+            if let Some(arg) = args.next() {
+                return Err(format!("unexpected argument '{}'", arg));
+            }
+            return Ok(Command::Decode(path));
+        }
+        Some("encode") => EncodeArgs {
+            text: None,
+            symbol_list: SymbolList::default(),
+            format: Format::Unicode,
+            file: None,
+            scale: 4,
+            quiet_zone: 1,
+            gs1: false,
+        },
+        Some(other) => return Err(format!("unknown command '{}'", other)),
+    };
+    let mut encode = command;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("missing value for {}", name));
+        match arg.as_str() {
+            "--size" => encode.symbol_list = parse_size(&value("--size")?)?,
+            "--out" => {
+                encode.format = match value("--out")?.as_str() {
+                    "unicode" => Format::Unicode,
+                    "ascii" => Format::Ascii,
+                    "svg" => Format::Svg,
+                    "png" => Format::Png,
+                    other => return Err(format!("unknown output format '{}'", other)),
+                }
+            }
+            "--file" => encode.file = Some(value("--file")?),
+            "--scale" => {
+                encode.scale = value("--scale")?
+                    .parse()
+                    .ok()
+                    .filter(|scale| *scale > 0)
+                    .ok_or("the scale must be a positive number")?
+            }
+            "--quiet-zone" => {
+                encode.quiet_zone = value("--quiet-zone")?
+                    .parse()
+                    .map_err(|_| "the quiet zone must be a number")?
+            }
+            "--gs1" => encode.gs1 = true,
+            "-h" | "--help" => return Ok(Command::Help),
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'", arg)),
+            _ if encode.text.is_none() => encode.text = Some(arg),
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+    Ok(Command::Encode(encode))
+}
+
+fn encode(args: EncodeArgs) -> Result<(), String> {
+    let text = match args.text {
+        Some(text) => text,
+        None => {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|err| format!("can not read the standard input: {}", err))?;
+            text
+        }
+    };
+    let code = DataMatrixBuilder::new()
+        .with_symbol_list(args.symbol_list)
+        .with_gs1(args.gs1)
+        .encode_str(&text)
+        .map_err(|err| format!("encoding failed: {}", err))?;
+    let bitmap = code.bitmap();
+    let quiet_zone = args.quiet_zone as usize;
+    let output = match args.format {
+        Format::Unicode => bitmap.to_unicode_string(quiet_zone).into_bytes(),
+        Format::Ascii => bitmap.to_ascii_string(quiet_zone).into_bytes(),
+        Format::Svg => {
+            let options = SvgOptions::new()
+                .with_module_size(args.scale as f32)
+                .with_quiet_zone(quiet_zone);
+            bitmap.to_svg(&options).into_bytes()
+        }
+        Format::Png => {
+            let mut png = io::Cursor::new(Vec::new());
+            bitmap
+                .to_image(args.scale, args.quiet_zone)
+                .write_to(&mut png, image::ImageFormat::Png)
+                .map_err(|err| format!("can not write PNG: {}", err))?;
+            png.into_inner()
+        }
+    };
+    match args.file {
+        Some(path) => std::fs::write(&path, output)
+            .map_err(|err| format!("can not write '{}': {}", path, err)),
+        None => io::stdout()
+            .write_all(&output)
+            .map_err(|err| format!("can not write the output: {}", err)),
+    }
+}
+
+fn decode(path: &str) -> Result<(), String> {
+    let image = image::open(path)
+        .map_err(|err| format!("can not read '{}': {}", path, err))?
+        .into_luma8();
+    let bitmap = detection::detect(image.as_raw(), image.width() as usize)
+        .map_err(|err| format!("no symbol found: {:?}", err))?;
+    let data =
+        DataMatrix::decode_bitmap(&bitmap).map_err(|err| format!("decoding failed: {:?}", err))?;
+    let mut stdout = io::stdout();
+    stdout
+        .write_all(&data)
+        .and_then(|_| stdout.write_all(b"\n"))
+        .map_err(|err| format!("can not write the output: {}", err))
+}
+
+fn main() -> ExitCode {
+    let result = match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Help) => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        Ok(Command::Encode(args)) => encode(args),
+        Ok(Command::Decode(path)) => decode(&path),
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+fn args(line: &str) -> impl Iterator<Item = String> + '_ {
+    line.split_whitespace().map(String::from)
+}
+
+#[test]
+fn test_parse_encode() {
+    let Ok(Command::Encode(encode)) = parse_args(args("encode HELLO --size 12x26 --out svg"))
+    else {
+        panic!("not an encode command");
+    };
+    assert_eq!(encode.text.as_deref(), Some("HELLO"));
+    assert_eq!(encode.format, Format::Svg);
+    assert_eq!(
+        encode.symbol_list,
+        SymbolList::from(datamatrix::SymbolSize::Rect12x26)
+    );
+
+    let Ok(Command::Encode(encode)) = parse_args(args(
+        "encode --gs1 --scale 2 --quiet-zone 0 --file a.png --out png",
+    )) else {
+        panic!("not an encode command");
+    };
+    assert_eq!(encode.text, None);
+    assert!(encode.gs1);
+    assert_eq!((encode.scale, encode.quiet_zone), (2, 0));
+    assert_eq!(encode.file.as_deref(), Some("a.png"));
+    assert_eq!(
+        parse_size("square"),
+        Ok(SymbolList::default().enforce_square())
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(parse_args(args("")), Ok(Command::Help));
+    assert_eq!(
+        parse_args(args("decode code.png")),
+        Ok(Command::Decode("code.png".into()))
+    );
+    for line in [
+        "frobnicate",
+        "decode",
+        "decode a.png b.png",
+        "encode A B",
+        "encode A --size 11x11",
+        "encode A --out gif",
+        "encode A --scale 0",
+        "encode A --size",
+        "encode A --verbose",
+    ] {
+        assert!(parse_args(args(line)).is_err(), "{}", line);
+    }
+}
+
+#[test]
+fn test_png_roundtrip() {
+    let path = std::env::temp_dir().join("datamatrix-cli-test.png");
+    let path = path.to_str().unwrap().to_owned();
+    let Ok(Command::Encode(args)) = parse_args(
+        ["encode", "Hello, CLI!", "--out", "png", "--file", &path]
+            .into_iter()
+            .map(String::from),
+    ) else {
+        panic!("not an encode command");
+    };
+    encode(args).unwrap();
+    let image = image::open(&path).unwrap().into_luma8();
+    let bitmap = detection::detect(image.as_raw(), image.width() as usize).unwrap();
+    assert_eq!(DataMatrix::decode_bitmap(&bitmap).unwrap(), b"Hello, CLI!");
+    std::fs::remove_file(&path).unwrap();
+}
@@ -12,11 +12,19 @@
 use alloc::{string::String, vec::Vec};
 use flagset::FlagSet;
 
-pub use crate::decodation::{decode_data, decode_str, DataDecodingError};
-use crate::encodation::{planner::optimize, GenericDataEncoder};
-pub use crate::encodation::{DataEncodingError, EncodationType};
+pub use crate::decodation::{
+    decode_data, decode_str, decode_str_structured, decode_structured, DataDecodingError,
+};
+use crate::encodation::{planner::optimize, Effort, GenericDataEncoder};
+pub use crate::encodation::{
+    Constraint, DataEncodingError, EncodationType, NoRandomization, Randomize253, Randomizer,
+};
 
+#[cfg(feature = "heapless")]
+use super::symbol_size::Symbols;
 use super::{SymbolList, SymbolSize};
+#[cfg(feature = "heapless")]
+use crate::encodation::SliceEncoder;
 
 #[cfg(test)]
 use pretty_assertions::assert_eq;
@@ -29,14 +37,258 @@ pub fn encode_data(
     enabled_modes: impl Into<FlagSet<EncodationType>>,
     use_macros: bool,
 ) -> Result<(Vec<u8>, SymbolSize), DataEncodingError> {
-    let mut encoder = GenericDataEncoder::with_size(data, symbol_list, enabled_modes.into());
-    if use_macros {
+    let mut codewords = Vec::new();
+    let EncodedData { size, .. } = encode_data_into(
+        data,
+        symbol_list,
+        None,
+        false,
+        eci,
+        enabled_modes.into(),
+        use_macros,
+        false,
+        false,
+        false,
+        Effort::Exhaustive,
+        None,
+        0.0,
+        None,
+        &Randomize253,
+        &mut codewords,
+    )?;
+    Ok((codewords, size))
+}
+
+/// Information about an encoding returned by [encode_data_into].
+pub(crate) struct EncodedData {
+    pub size: SymbolSize,
+    /// Number of codewords before the padding.
+    pub num_unpadded: usize,
+    /// Start position in the input and mode of every segment.
+    pub segments: Vec<(usize, EncodationType)>,
+}
+
+/// Like [encode_data], but write the codewords into `out`, reusing its allocation.
+///
+/// If `plan` is given it is followed instead of computing an optimal plan.
+/// The `structured_append` header is written first, then the reader programming
+/// codeword if `reader_programming` is set, followed by the ECI.
+/// With `gs1` an FNC1 is written after the ECI, macros are not used then.
+/// The `effort` limits the modes used for planning, the first character is
+/// encoded with `start_mode` if given.
+/// With `prefer_text` ties between C40 and Text are decided in favor of Text.
+/// The fraction `headroom` of the symbol's data codewords is left unused.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn encode_data_into(
+    data: &[u8],
+    symbol_list: &SymbolList,
+    structured_append: Option<[u8; 3]>,
+    reader_programming: bool,
+    eci: Option<u32>,
+    enabled_modes: FlagSet<EncodationType>,
+    use_macros: bool,
+    gs1: bool,
+    strict: bool,
+    prefer_text: bool,
+    effort: Effort,
+    start_mode: Option<EncodationType>,
+    headroom: f32,
+    plan: Option<&[(usize, EncodationType)]>,
+    randomizer: &dyn Randomizer,
+    out: &mut Vec<u8>,
+) -> Result<EncodedData, DataEncodingError> {
+    if eci.is_some_and(|eci| eci > 999999) {
+        return Err(DataEncodingError::InvalidOptions(
+            "the ECI must be at most 999999",
+        ));
+    }
+    check_headroom(headroom)?;
+    let mut encoder = GenericDataEncoder::with_size(data, symbol_list, enabled_modes);
+    encoder.set_strict(strict);
+    encoder.set_prefer_text(prefer_text);
+    encoder.set_effort(effort);
+    encoder.set_start_mode(start_mode);
+    encoder.set_headroom(headroom);
+    encoder.set_randomizer(randomizer);
+    if let Some(plan) = plan {
+        encoder.set_plan(plan.to_vec());
+    }
+    encoder.use_buffer(core::mem::take(out));
+    if let Some(header) = structured_append {
+        encoder.write_structured_append(header);
+    }
+    if reader_programming {
+        encoder.write_reader_programming();
+    } else if structured_append.is_none() && use_macros && !gs1 {
         encoder.use_macro_if_possible();
     }
     if let Some(eci) = eci {
         encoder.write_eci(eci);
     }
-    encoder.codewords()
+    if gs1 {
+        encoder.write_fnc1();
+    }
+    let num_header = encoder.num_written_codewords();
+    let (codewords, size) = match encoder.codewords() {
+        Err(DataEncodingError::TooMuchOrIllegalData) if plan.is_none() && start_mode.is_none() => {
+            return Err(encoding_failure(
+                data,
+                symbol_list,
+                enabled_modes,
+                effort,
+                headroom,
+                num_header,
+            ))
+        }
+        result => result?,
+    };
+    *out = codewords;
+    Ok(EncodedData {
+        size,
+        num_unpadded: encoder.num_unpadded_codewords(),
+        segments: encoder.take_segments(),
+    })
+}
+
+/// Replace a NaN `headroom` by another invalid value, so the builder can implement `Eq`.
+pub(crate) fn headroom_or_invalid(headroom: f32) -> f32 {
+    if headroom.is_nan() {
+        -1.0
+    } else {
+        headroom
+    }
+}
+
+/// Check that `headroom` is in the range `0.0..1.0`.
+pub(crate) fn check_headroom(headroom: f32) -> Result<(), DataEncodingError> {
+    if (0.0..1.0).contains(&headroom) {
+        Ok(())
+    } else {
+        Err(DataEncodingError::InvalidOptions(
+            "the headroom must be in the range 0.0..1.0",
+        ))
+    }
+}
+
+/// A part of this length always fits into 144x144, even with Base256.
+const PART_LEN: usize = 1000;
+
+/// Find out why the planned encoding of `data` failed.
+///
+/// Either a byte can not be encoded by the modes, or the data is too long. For data
+/// which does not fit into any symbol, the needed codewords are estimated by
+/// encoding parts which fit.
+fn encoding_failure(
+    data: &[u8],
+    symbol_list: &SymbolList,
+    enabled_modes: FlagSet<EncodationType>,
+    effort: Effort,
+    headroom: f32,
+    num_header: usize,
+) -> DataEncodingError {
+    if let Err(invalid) = validate_input(data, enabled_modes) {
+        let (position, byte) = invalid[0];
+        return DataEncodingError::IllegalCharacter {
+            position,
+            character: byte as char,
+        };
+    }
+    let largest = symbol_list
+        .iter()
+        .map(|s| s.num_data_codewords())
+        .max()
+        .unwrap_or(0);
+    let available = (largest as f32 * (1.0 - headroom)) as usize;
+    let all_sizes = SymbolList::with_extended_rectangles();
+    let estimate = |modes| {
+        let mut needed = num_header;
+        for part in data.chunks(PART_LEN) {
+            let mut encoder = GenericDataEncoder::with_size(part, &all_sizes, modes);
+            encoder.set_effort(effort);
+            encoder.codewords().ok()?;
+            needed += encoder.num_unpadded_codewords();
+        }
+        Some(needed)
+    };
+    let Some(needed) = estimate(enabled_modes) else {
+        return DataEncodingError::TooMuchOrIllegalData;
+    };
+    // the parts are encoded a bit differently, it did not fit after all
+    let needed = needed.max(available + 1);
+    let constraint = if enabled_modes != EncodationType::all()
+        && estimate(EncodationType::all()).is_some_and(|needed| needed <= available)
+    {
+        Constraint::EncodationTypes
+    } else if needed <= largest {
+        Constraint::Headroom
+    } else if needed <= SymbolSize::Square144.num_data_codewords() {
+        Constraint::SymbolList
+    } else {
+        Constraint::Capacity
+    };
+    DataEncodingError::TooMuchData {
+        needed,
+        available,
+        constraint,
+    }
+}
+
+/// Like [encoding_failure], but without allocation, for data which does not fit into `symbols`.
+#[cfg(feature = "heapless")]
+pub(crate) fn encoding_failure_without_alloc(
+    data: &[u8],
+    symbols: Symbols,
+    enabled_modes: FlagSet<EncodationType>,
+    headroom: f32,
+) -> DataEncodingError {
+    let illegal = |ch: &u8| (EncodationType::encodable_by(*ch) & enabled_modes).is_empty();
+    if let Some(position) = data.iter().position(illegal) {
+        return DataEncodingError::IllegalCharacter {
+            position,
+            character: data[position] as char,
+        };
+    }
+    let largest = match symbols {
+        Symbols::List(list, max_codewords) => list
+            .iter()
+            .map(|s| s.num_data_codewords())
+            .filter(|len| *len <= max_codewords)
+            .max()
+            .unwrap_or(0),
+        Symbols::Size(size) => size.num_data_codewords(),
+    };
+    let available = (largest as f32 * (1.0 - headroom)) as usize;
+    let estimate = |modes| {
+        let mut needed = 0;
+        let mut out = [0; 1558];
+        for part in data.chunks(PART_LEN) {
+            let size = Symbols::Size(SymbolSize::Square144);
+            let mut encoder = SliceEncoder::new(part, size, modes, &mut out);
+            encoder.encode().ok()?;
+            needed += encoder.num_unpadded_codewords();
+        }
+        Some(needed)
+    };
+    let Some(needed) = estimate(enabled_modes) else {
+        return DataEncodingError::TooMuchOrIllegalData;
+    };
+    let needed = needed.max(available + 1);
+    let constraint = if enabled_modes != EncodationType::all()
+        && estimate(EncodationType::all()).is_some_and(|needed| needed <= available)
+    {
+        Constraint::EncodationTypes
+    } else if needed <= largest {
+        Constraint::Headroom
+    } else if needed <= SymbolSize::Square144.num_data_codewords() {
+        Constraint::SymbolList
+    } else {
+        Constraint::Capacity
+    };
+    DataEncodingError::TooMuchData {
+        needed,
+        available,
+        constraint,
+    }
 }
 
 /// Compute a plan for when to switch encodation types during data encoding.
@@ -70,10 +322,45 @@ pub fn encodation_plan(
         0,
         EncodationType::Ascii,
         symbol_list,
+        0.0,
         enabled_modes.into(),
+        false,
+        false,
+        None,
     )
 }
 
+/// Find all bytes in `data` which none of the `enabled_modes` can encode.
+///
+/// Returns the positions and values of these bytes, so they can be reported
+/// at once instead of failing on the first one. Passing this check does not
+/// mean the data fits into a symbol.
+///
+/// # Example
+///
+/// ```rust
+/// # use datamatrix::{data::validate_input, EncodationType};
+/// let modes = EncodationType::X12 | EncodationType::Edifact;
+/// assert_eq!(validate_input(b"AB-x", modes), Err(vec![(3, b'x')]));
+/// ```
+pub fn validate_input(
+    data: &[u8],
+    enabled_modes: impl Into<FlagSet<EncodationType>>,
+) -> Result<(), Vec<(usize, u8)>> {
+    let enabled_modes = enabled_modes.into();
+    let invalid: Vec<(usize, u8)> = data
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, ch)| !enabled_modes.into_iter().any(|mode| mode.can_encode(*ch)))
+        .collect();
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(invalid)
+    }
+}
+
 /// Try to convert an UTF-8 encoded string to Latin 1.
 pub fn utf8_to_latin1(s: &str) -> Option<Vec<u8>> {
     let mut out = Vec::with_capacity(s.len());
@@ -183,6 +470,107 @@ pub fn utf8_to_latin1(s: &str) -> Option<Vec<u8>> {
     Some(out)
 }
 
+/// How [DataMatrixBuilder::encode_str](crate::DataMatrixBuilder::encode_str) handles text which is not Latin 1.
+///
+/// See [DataMatrixBuilder::with_charset_policy](crate::DataMatrixBuilder::with_charset_policy).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CharsetPolicy {
+    /// Encode the UTF-8 bytes after an UTF-8 ECI.
+    #[default]
+    Eci,
+    /// Fail with [DataEncodingError::IllegalCharacter] for the first other character.
+    Error,
+    /// Replace the other characters with [utf8_to_latin1_lossy].
+    Transliterate,
+}
+
+/// Convert an UTF-8 encoded string to Latin 1, approximating other characters.
+///
+/// Letters with diacritics are replaced by the base letter, e.g., "ł" by "l",
+/// and typographic punctuation by ASCII, e.g., "“" by a double quote. Characters
+/// without an approximation become "?".
+///
+/// # Example
+///
+/// ```rust
+/// # use datamatrix::data::utf8_to_latin1_lossy;
+/// assert_eq!(utf8_to_latin1_lossy("Łódź – 5 €"), b"L\xf3dz - 5 EUR");
+/// assert_eq!(utf8_to_latin1_lossy("日本"), b"??");
+/// ```
+pub fn utf8_to_latin1_lossy(s: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(s.len());
+    let mut buf = [0; 4];
+    for ch in s.chars() {
+        if ch.is_ascii() {
+            out.push(ch as u8);
+        } else if let Some(latin1) = utf8_to_latin1(ch.encode_utf8(&mut buf)) {
+            out.extend_from_slice(&latin1);
+        } else {
+            out.extend_from_slice(approximate_latin1(ch).as_bytes());
+        }
+    }
+    out
+}
+
+fn approximate_latin1(ch: char) -> &'static str {
+    match ch {
+        'Ā' | 'Ă' | 'Ą' => "A",
+        'ā' | 'ă' | 'ą' => "a",
+        'Ć' | 'Ĉ' | 'Ċ' | 'Č' => "C",
+        'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'Ď' | 'Đ' => "D",
+        'ď' | 'đ' => "d",
+        'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => "G",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'Ĥ' | 'Ħ' => "H",
+        'ĥ' | 'ħ' => "h",
+        'Ĩ' | 'Ī' | 'Ĭ' | 'Į' | 'İ' => "I",
+        'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'Ĳ' => "IJ",
+        'ĳ' => "ij",
+        'Ĵ' => "J",
+        'ĵ' => "j",
+        'Ķ' => "K",
+        'ķ' | 'ĸ' => "k",
+        'Ĺ' | 'Ļ' | 'Ľ' | 'Ŀ' | 'Ł' => "L",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'Ń' | 'Ņ' | 'Ň' | 'Ŋ' => "N",
+        'ń' | 'ņ' | 'ň' | 'ŉ' | 'ŋ' => "n",
+        'Ō' | 'Ŏ' | 'Ő' => "O",
+        'ō' | 'ŏ' | 'ő' => "o",
+        'Œ' => "OE",
+        'œ' => "oe",
+        'Ŕ' | 'Ŗ' | 'Ř' => "R",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'Ś' | 'Ŝ' | 'Ş' | 'Š' | 'Ș' => "S",
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' | 'ſ' => "s",
+        'Ţ' | 'Ť' | 'Ŧ' | 'Ț' => "T",
+        'ţ' | 'ť' | 'ŧ' | 'ț' => "t",
+        'Ũ' | 'Ū' | 'Ŭ' | 'Ů' | 'Ű' | 'Ų' => "U",
+        'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'Ŵ' => "W",
+        'ŵ' => "w",
+        'Ŷ' | 'Ÿ' => "Y",
+        'ŷ' => "y",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ź' | 'ż' | 'ž' => "z",
+        '‘' | '’' | '‚' | '‛' | '′' => "'",
+        '“' | '”' | '„' | '‟' | '″' => "\"",
+        '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' => "-",
+        '\u{2000}'..='\u{200a}' | '\u{202f}' | '\u{205f}' => " ",
+        '‹' => "<",
+        '›' => ">",
+        '•' => "*",
+        '…' => "...",
+        '€' => "EUR",
+        '™' => "TM",
+        _ => "?",
+    }
+}
+
 /// Try to convert a Latin 1 encoded string to an UTF-8 string.
 ///
 /// Fails if the input is contains invalid latin 1 characters.
@@ -299,6 +687,39 @@ pub(crate) fn latin1_to_utf8_mut(latin1: &[u8], out: &mut String) -> Option<()>
     Some(())
 }
 
+#[test]
+fn test_validate_input() {
+    assert_eq!(validate_input(b"", EncodationType::X12), Ok(()));
+    assert_eq!(validate_input(b"ABC\xe4\r", EncodationType::all()), Ok(()));
+    assert_eq!(
+        validate_input(b"a12\x1fB.\xe4\r", EncodationType::Edifact),
+        Err(alloc::vec![(0, b'a'), (3, 0x1f), (6, 0xe4), (7, b'\r')])
+    );
+    assert_eq!(
+        validate_input(
+            b"a12\x1fB.\xe4\r",
+            EncodationType::Edifact | EncodationType::X12
+        ),
+        Err(alloc::vec![(0, b'a'), (3, 0x1f), (6, 0xe4)])
+    );
+    assert_eq!(
+        validate_input(b"\x00\xff", FlagSet::default()),
+        Err(alloc::vec![(0, 0), (1, 0xff)])
+    );
+}
+
+#[test]
+fn test_utf8_to_latin1_lossy() {
+    assert_eq!(utf8_to_latin1_lossy(""), b"");
+    assert_eq!(utf8_to_latin1_lossy("a\tb\r\n"), b"a\tb\r\n");
+    assert_eq!(utf8_to_latin1_lossy("Grüße"), b"Gr\xfc\xdfe");
+    assert_eq!(
+        utf8_to_latin1_lossy("Dvořák, „Œuvre“…"),
+        b"Dvor\xe1k, \"OEuvre\"..."
+    );
+    assert_eq!(utf8_to_latin1_lossy("😀x"), b"?x");
+}
+
 #[test]
 fn test_macro() {
     use crate::encodation::{ascii::PAD, MACRO05, MACRO06};
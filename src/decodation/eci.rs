@@ -39,7 +39,11 @@ fn convert_chunk(bytes: &[u8], eci: u32, out: &mut String) -> Result<(), DataDec
 }
 
 #[cfg(feature = "extended_eci")]
-fn convert_chunk_extended(bytes: &[u8], eci: u32, out: &mut str) -> Result<(), DataDecodingError> {
+fn convert_chunk_extended(
+    bytes: &[u8],
+    eci: u32,
+    out: &mut String,
+) -> Result<(), DataDecodingError> {
     use encoding_rs::*;
 
     let encoder = match eci {
@@ -80,7 +84,7 @@ fn convert_chunk_extended(bytes: &[u8], eci: u32, out: &mut str) -> Result<(), D
 fn convert_chunk_extended(
     _bytes: &[u8],
     eci: u32,
-    _out: &mut str,
+    _out: &mut String,
 ) -> Result<(), DataDecodingError> {
     match eci {
         0..=13 | 15..=18 | 20..=30 => Err(DataDecodingError::NotImplemented(
@@ -142,3 +146,13 @@ fn decode_iso_8859_9(bytes: &[u8], out: &mut String) -> Result<(), DataDecodingE
     }
     Ok(())
 }
+
+#[test]
+#[cfg(feature = "extended_eci")]
+fn test_extended_eci() {
+    assert_eq!(convert(b"\x82\xa0", &[(0, 20)]), Ok("あ".into()));
+    assert_eq!(
+        convert(b"A\x82", &[(1, 20)]),
+        Err(DataDecodingError::CharsetError)
+    );
+}
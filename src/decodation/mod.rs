@@ -3,7 +3,7 @@
 //! It performs the inverse of the `encodation` module.
 use super::encodation::{
     ascii, edifact, EncodationType, MACRO05, MACRO05_HEAD, MACRO06, MACRO06_HEAD, MACRO_TRAIL,
-    UNLATCH,
+    STRUCT_APPEND, UNLATCH,
 };
 use alloc::{string::String, vec::Vec};
 
@@ -17,11 +17,14 @@ mod tests;
 use pretty_assertions::assert_eq;
 
 mod eci;
+mod structured_append;
 
 pub(crate) use eci::ECI_UTF8;
+pub use structured_append::{decode_str_structured, decode_structured};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// Error when decoding the data part.
+#[non_exhaustive]
 pub enum DataDecodingError {
     UnexpectedCharacter(&'static str, u8),
     NotImplemented(&'static str),
@@ -29,8 +32,27 @@ pub enum DataDecodingError {
     CharsetError,
     /// An ECI code is not supported in raw data decoding
     ECICode,
+    /// The symbols do not form a complete structured append sequence.
+    StructuredAppend(&'static str),
 }
 
+impl core::fmt::Display for DataDecodingError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnexpectedCharacter(ctx, ch) => {
+                write!(f, "unexpected character {} ({})", ch, ctx)
+            }
+            Self::NotImplemented(what) => write!(f, "{} is not implemented", what),
+            Self::UnexpectedEnd => write!(f, "unexpected end of data"),
+            Self::CharsetError => write!(f, "data could not be converted to the charset"),
+            Self::ECICode => write!(f, "ECI codes are not supported in raw data decoding"),
+            Self::StructuredAppend(msg) => write!(f, "invalid structured append: {}", msg),
+        }
+    }
+}
+
+impl core::error::Error for DataDecodingError {}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 struct Reader<'a>(&'a [u8], usize);
 
@@ -77,6 +99,7 @@ struct DecodedParts {
     eci_spans: Vec<(usize, u32)>,
     #[allow(unused)]
     fnc1: bool,
+    structured_append: Option<structured_append::Header>,
 }
 
 fn decode_parts(data: &[u8], raw: bool) -> Result<DecodedParts, DataDecodingError> {
@@ -85,6 +108,14 @@ fn decode_parts(data: &[u8], raw: bool) -> Result<DecodedParts, DataDecodingErro
     let mut out = Vec::with_capacity(data.len());
     let mut ecis = Vec::new();
 
+    let structured_append = if data.peek(0) == Some(STRUCT_APPEND) {
+        let _ = data.eat().unwrap();
+        let header = [data.eat()?, data.eat()?, data.eat()?];
+        Some(structured_append::Header::new(header)?)
+    } else {
+        None
+    };
+
     let add_macro_trail = match data.peek(0) {
         Some(MACRO05) => {
             out.extend_from_slice(MACRO05_HEAD);
@@ -133,6 +164,7 @@ fn decode_parts(data: &[u8], raw: bool) -> Result<DecodedParts, DataDecodingErro
         output: out,
         eci_spans: ecis,
         fnc1,
+        structured_append,
     })
 }
 
@@ -230,9 +262,17 @@ fn decode_ascii<'a>(
             ascii::LATCH_C40 => return Ok((data, EncodationType::C40)),
             ascii::LATCH_BASE256 => return Ok((data, EncodationType::Base256)),
             ascii::FNC1 => {
-                out.push(29);
+                // in the first position, possibly after an ECI, it marks GS1 data
+                if !out.is_empty() {
+                    out.push(29);
+                }
+            }
+            STRUCT_APPEND => {
+                return Err(DataDecodingError::UnexpectedCharacter(
+                    "structured append not at the start",
+                    ch,
+                ))
             }
-            233 => return Err(DataDecodingError::NotImplemented("Structured Append")),
             234 => return Err(DataDecodingError::NotImplemented("Reader Programming")),
             ascii::UPPER_SHIFT => {
                 upper_shift = true;
@@ -467,7 +507,8 @@ fn decode_c40_like<'a>(
                             out.push(text);
                         }
                     }
-                    27 => return Err(DataDecodingError::NotImplemented("FNC1 in C40/Text")),
+                    // a separator, it can not be in the first position
+                    27 => out.push(29),
                     30 => upper_shift = true,
                     _ => {
                         return Err(DataDecodingError::UnexpectedCharacter(
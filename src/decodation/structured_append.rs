@@ -0,0 +1,232 @@
+//! Reassembly of data split over several symbols with structured append.
+use alloc::{string::String, vec::Vec};
+
+use super::{decode_parts, eci, DataDecodingError, DecodedParts};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// The structured append header of a symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Header {
+    /// Position of the symbol in the sequence, starting at 0.
+    position: usize,
+    /// Number of symbols in the sequence.
+    count: usize,
+    file_id: (u8, u8),
+}
+
+impl Header {
+    /// Parse the three codewords after the structured append codeword.
+    pub(super) fn new(header: [u8; 3]) -> Result<Self, DataDecodingError> {
+        let [indicator, id1, id2] = header;
+        let position = (indicator >> 4) as usize;
+        let count = 17 - (indicator & 0x0f) as usize;
+        if count > 16 || position >= count {
+            return Err(DataDecodingError::UnexpectedCharacter(
+                "invalid structured append sequence indicator",
+                indicator,
+            ));
+        }
+        Ok(Self {
+            position,
+            count,
+            file_id: (id1, id2),
+        })
+    }
+}
+
+/// Decode each symbol and join the parts in the order of the sequence.
+fn decode_sequence(symbols: &[&[u8]], raw: bool) -> Result<DecodedParts, DataDecodingError> {
+    let mut parts = symbols
+        .iter()
+        .map(|data| decode_parts(data, raw))
+        .collect::<Result<Vec<_>, _>>()?;
+    // the encoder does not add a header if the data fits into one symbol
+    if let [part] = parts.as_slice() {
+        if part.structured_append.is_none() {
+            return Ok(parts.pop().unwrap());
+        }
+    }
+    let Some(first) = parts.first().and_then(|part| part.structured_append) else {
+        return Err(DataDecodingError::StructuredAppend(
+            "the first symbol has no header",
+        ));
+    };
+    let mut headers = Vec::with_capacity(parts.len());
+    for part in &parts {
+        match part.structured_append {
+            Some(header) if header.count == first.count && header.file_id == first.file_id => {
+                headers.push(header)
+            }
+            Some(_) => {
+                return Err(DataDecodingError::StructuredAppend(
+                    "the symbols are from different sequences",
+                ))
+            }
+            None => {
+                return Err(DataDecodingError::StructuredAppend(
+                    "a symbol has no header",
+                ))
+            }
+        }
+    }
+    if parts.len() != first.count {
+        return Err(DataDecodingError::StructuredAppend(
+            "the number of symbols does not match the sequence",
+        ));
+    }
+    let mut order: Vec<usize> = (0..parts.len()).collect();
+    order.sort_by_key(|i| headers[*i].position);
+    if order
+        .iter()
+        .enumerate()
+        .any(|(pos, i)| headers[*i].position != pos)
+    {
+        return Err(DataDecodingError::StructuredAppend(
+            "a symbol of the sequence is missing",
+        ));
+    }
+
+    let mut joined = DecodedParts {
+        output: Vec::new(),
+        eci_spans: Vec::new(),
+        fnc1: parts[order[0]].fnc1,
+        structured_append: None,
+    };
+    for i in order {
+        let part = &mut parts[i];
+        let offset = joined.output.len();
+        // each symbol starts with the default character set
+        let starts_with_eci = part.eci_spans.first().is_some_and(|(pos, _)| *pos == 0);
+        if !joined.eci_spans.is_empty() && !starts_with_eci {
+            joined.eci_spans.push((offset, 0));
+        }
+        joined
+            .eci_spans
+            .extend(part.eci_spans.iter().map(|(pos, eci)| (pos + offset, *eci)));
+        joined.output.append(&mut part.output);
+    }
+    Ok(joined)
+}
+
+/// Decode the data codewords of a structured append sequence.
+///
+/// The `symbols` are the data codewords of each symbol, in any order. All
+/// symbols of the sequence must be given, a single symbol without structured
+/// append header is decoded like with [decode_data](super::decode_data).
+pub fn decode_structured(symbols: &[&[u8]]) -> Result<Vec<u8>, DataDecodingError> {
+    let parts = decode_sequence(symbols, true)?;
+    if !parts.eci_spans.is_empty() {
+        Err(DataDecodingError::ECICode)
+    } else {
+        Ok(parts.output)
+    }
+}
+
+/// Decode the data codewords of a structured append sequence as a string.
+///
+/// This works like [decode_structured], the ECIs of each symbol are
+/// handled like in [decode_str](super::decode_str).
+pub fn decode_str_structured(symbols: &[&[u8]]) -> Result<String, DataDecodingError> {
+    let parts = decode_sequence(symbols, false)?;
+    eci::convert(&parts.output, &parts.eci_spans)
+}
+
+#[test]
+fn test_header() {
+    assert_eq!(
+        Header::new([0b0010_1010, 1, 2]),
+        Ok(Header {
+            position: 2,
+            count: 7,
+            file_id: (1, 2)
+        })
+    );
+    assert_eq!(Header::new([0b1111_0001, 1, 2]).unwrap().count, 16);
+    // count 17, and position 2 of 2
+    for indicator in [0b0000_0000, 0b0010_1111] {
+        assert!(matches!(
+            Header::new([indicator, 1, 2]),
+            Err(DataDecodingError::UnexpectedCharacter(_, _))
+        ));
+    }
+}
+
+#[test]
+fn test_decode_structured() {
+    use crate::{DataMatrixBuilder, SymbolSize};
+
+    let data: Vec<u8> = (0..100).map(|i| b'A' + (i % 26) as u8).collect();
+    let builder = DataMatrixBuilder::new().with_symbol_list(SymbolSize::Square16);
+    let symbols = builder.clone().encode_structured(&data).unwrap();
+    let mut codewords: Vec<&[u8]> = symbols.iter().map(|s| s.data_codewords()).collect();
+    assert_eq!(decode_structured(&codewords), Ok(data.clone()));
+    codewords.reverse();
+    assert_eq!(decode_structured(&codewords), Ok(data.clone()));
+
+    // one symbol alone is only a part
+    let first = super::decode_data(symbols[0].data_codewords()).unwrap();
+    assert!(first.len() < data.len() && data.starts_with(&first));
+
+    let missing = decode_structured(&codewords[1..]);
+    assert!(matches!(
+        missing,
+        Err(DataDecodingError::StructuredAppend(_))
+    ));
+    let mut duplicate = codewords.clone();
+    duplicate[1] = duplicate[0];
+    let duplicate = decode_structured(&duplicate);
+    assert!(matches!(
+        duplicate,
+        Err(DataDecodingError::StructuredAppend(_))
+    ));
+
+    let other = builder
+        .with_file_id((3, 4))
+        .encode_structured(&data)
+        .unwrap();
+    let mut mixed = codewords.clone();
+    mixed[0] = other[0].data_codewords();
+    let mixed = decode_structured(&mixed);
+    assert!(matches!(mixed, Err(DataDecodingError::StructuredAppend(_))));
+
+    let plain = DataMatrixBuilder::new().encode(b"Hello").unwrap();
+    assert_eq!(
+        decode_structured(&[plain.data_codewords()]),
+        Ok(b"Hello".to_vec())
+    );
+    let with_plain = decode_structured(&[codewords[0], plain.data_codewords()]);
+    assert!(matches!(
+        with_plain,
+        Err(DataDecodingError::StructuredAppend(_))
+    ));
+    assert!(decode_structured(&[]).is_err());
+}
+
+#[test]
+fn test_decode_str_structured() {
+    use crate::{DataMatrixBuilder, SymbolSize};
+
+    for text in [
+        "Ελληνικό κείμενο σε δύο σύμβολα",
+        "Grüße aus der ganzen Welt, Grüße!",
+    ] {
+        let symbols = DataMatrixBuilder::new()
+            .with_symbol_list(SymbolSize::Square22)
+            .encode_str_structured(text)
+            .unwrap();
+        assert!(symbols.len() > 1);
+        let codewords: Vec<&[u8]> = symbols.iter().map(|s| s.data_codewords()).collect();
+        assert_eq!(decode_str_structured(&codewords).unwrap(), text);
+    }
+    let symbols = DataMatrixBuilder::new()
+        .with_symbol_list(SymbolSize::Square22)
+        .encode_str_structured("Ελληνικό κείμενο σε δύο σύμβολα")
+        .unwrap();
+    let codewords: Vec<&[u8]> = symbols.iter().map(|s| s.data_codewords()).collect();
+    assert_eq!(
+        decode_structured(&codewords),
+        Err(DataDecodingError::ECICode)
+    );
+}
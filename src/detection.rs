@@ -0,0 +1,463 @@
+//! Detection of a Data Matrix in a grayscale image.
+//!
+//! This is a basic detector for images which contain a single symbol, seen mostly
+//! from the front, e.g., a scan or a camera capture of a label. The symbol may be rotated.
+//!
+//! The image is binarized with a global threshold (Otsu's method), then the biggest
+//! connected dark area is taken as the symbol. Three of its corners are on the L shaped
+//! finder pattern, the fourth corner is estimated from them. Each symbol size and
+//! orientation is sampled with a projective mapping of the corners. The corners of the
+//! best candidates are then moved until the finder and timing patterns match, which
+//! also corrects a slight perspective distortion.
+//!
+//! ```rust
+//! # use datamatrix::{detection, DataMatrix, SymbolList};
+//! let code = DataMatrix::encode(b"Hello, World!", SymbolList::default()).unwrap();
+//! // render with 4 pixels per module and a quiet zone of 2 modules
+//! let bitmap = code.bitmap();
+//! let width = (bitmap.width() + 4) * 4;
+//! let mut image = vec![255; width * (bitmap.height() + 4) * 4];
+//! for (x, y) in bitmap.pixels() {
+//!     for i in 0..16 {
+//!         image[((y + 2) * 4 + i / 4) * width + (x + 2) * 4 + i % 4] = 0;
+//!     }
+//! }
+//! let sampled = detection::detect(&image, width).unwrap();
+//! assert_eq!(DataMatrix::decode_bitmap(&sampled).unwrap(), b"Hello, World!");
+//! ```
+use alloc::{vec, vec::Vec};
+
+use crate::placement::{Bitmap, ModuleRole};
+use crate::symbol_size::{SymbolList, SymbolSize};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Maximal fraction of finder and timing modules which may be sampled wrong.
+const MAX_PATTERN_ERRORS: f32 = 0.1;
+
+/// Number of candidates for the size and orientation whose corners are refined.
+const REFINED_CANDIDATES: usize = 4;
+
+/// Errors when detecting a Data Matrix in an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DetectionError {
+    /// The width is zero or does not evenly divide the number of pixels.
+    ImageSize,
+    /// There is no dark area big enough for a symbol.
+    NoSymbol,
+    /// No symbol size matches the finder and timing patterns.
+    NoMatch,
+}
+
+impl core::fmt::Display for DetectionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::ImageSize => write!(f, "number of pixels does not fit the width"),
+            Self::NoSymbol => write!(f, "no symbol found in the image"),
+            Self::NoMatch => write!(f, "finder and timing patterns do not match any size"),
+        }
+    }
+}
+
+impl core::error::Error for DetectionError {}
+
+/// Find a Data Matrix in a grayscale image and sample its modules.
+///
+/// The `image` contains one byte per pixel in row-major order, `width` pixels per
+/// row, where 0 is black. The symbol must be dark on a light background with a
+/// quiet zone. The result can be decoded with [DataMatrix::decode_bitmap](crate::DataMatrix::decode_bitmap).
+pub fn detect(image: &[u8], width: usize) -> Result<Bitmap<bool>, DetectionError> {
+    if width == 0 || image.is_empty() || !image.len().is_multiple_of(width) {
+        return Err(DetectionError::ImageSize);
+    }
+    let dark = binarize(image);
+    let area = biggest_component(&dark, width);
+    // the smallest symbol has 8 modules on the short side
+    if area.len() < 8 * 8 / 2 {
+        return Err(DetectionError::NoSymbol);
+    }
+    let corners = corners(&area);
+
+    // rank all orientations and sizes, then refine the corners of the best ones
+    let mut candidates = Vec::with_capacity(4 * 30);
+    for light in 0..4 {
+        let quad = with_light_corner(&corners, light);
+        for size in SymbolList::all() {
+            let errors = pattern_errors(&dark, width, &Quad::new(quad), size);
+            candidates.push((errors, size, quad));
+        }
+    }
+    candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let best = candidates
+        .into_iter()
+        .take(REFINED_CANDIDATES)
+        .map(|(_, size, quad)| {
+            let (errors, quad) = refine(&dark, width, size, quad);
+            (errors, size, Quad::new(quad))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+    match best {
+        Some((errors, size, quad)) if errors <= MAX_PATTERN_ERRORS => {
+            let ((w, h), _) = size.module_dimensions();
+            let bits = (0..h).flat_map(|row| {
+                let quad = &quad;
+                let dark = &dark;
+                (0..w).map(move |col| quad.sample(dark, width, size, row, col))
+            });
+            Ok(Bitmap::new(bits, w))
+        }
+        _ => Err(DetectionError::NoMatch),
+    }
+}
+
+/// Binarize with Otsu's threshold, `true` is dark.
+fn binarize(image: &[u8]) -> Vec<bool> {
+    let mut histogram = [0usize; 256];
+    for px in image {
+        histogram[*px as usize] += 1;
+    }
+    let total = image.len() as f32;
+    let sum: f32 = histogram
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (i * n) as f32)
+        .sum();
+    let (mut count_low, mut sum_low) = (0.0, 0.0);
+    let (mut best, mut threshold) = (-1.0, 0);
+    for (i, n) in histogram.iter().enumerate() {
+        count_low += *n as f32;
+        sum_low += (i * n) as f32;
+        let count_high = total - count_low;
+        if count_low == 0.0 || count_high == 0.0 {
+            continue;
+        }
+        let diff = sum_low / count_low - (sum - sum_low) / count_high;
+        let variance = count_low * count_high * diff * diff;
+        if variance > best {
+            best = variance;
+            threshold = i;
+        }
+    }
+    image.iter().map(|px| (*px as usize) <= threshold).collect()
+}
+
+/// Get the coordinates `(x, y)` of the biggest 8-connected area of dark pixels.
+fn biggest_component(dark: &[bool], width: usize) -> Vec<(usize, usize)> {
+    let height = dark.len() / width;
+    let mut seen = vec![false; dark.len()];
+    let mut best = Vec::new();
+    let mut stack = Vec::new();
+    for start in 0..dark.len() {
+        if !dark[start] || seen[start] {
+            continue;
+        }
+        let mut area = Vec::new();
+        seen[start] = true;
+        stack.push(start);
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % width, i / width);
+            area.push((x, y));
+            for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                    let j = ny * width + nx;
+                    if dark[j] && !seen[j] {
+                        seen[j] = true;
+                        stack.push(j);
+                    }
+                }
+            }
+        }
+        if area.len() > best.len() {
+            best = area;
+        }
+    }
+    best
+}
+
+/// Find four corners of the area in clockwise order.
+///
+/// One of them is the light corner of the symbol which is only approximated.
+fn corners(area: &[(usize, usize)]) -> [(f32, f32); 4] {
+    let points = || area.iter().map(|(x, y)| (*x as f32, *y as f32));
+    let farthest = |key: &dyn Fn((f32, f32)) -> f32| {
+        points().max_by(|a, b| key(*a).total_cmp(&key(*b))).unwrap()
+    };
+    let n = area.len() as f32;
+    let center = points().fold((0.0, 0.0), |acc, p| (acc.0 + p.0 / n, acc.1 + p.1 / n));
+    let dist = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0) * (a.0 - b.0) + (a.1 - b.1) * (a.1 - b.1);
+    // a corner and the opposite one, then the corners farthest from the diagonal
+    let first = farthest(&|p| dist(p, center));
+    let third = farthest(&|p| dist(p, first));
+    let side = |p: (f32, f32)| {
+        (third.0 - first.0) * (p.1 - first.1) - (third.1 - first.1) * (p.0 - first.0)
+    };
+    // clockwise with the y axis pointing down
+    [first, farthest(&|p| -side(p)), third, farthest(&side)]
+}
+
+/// Get the corners top left, top right, bottom right and bottom left, assuming
+/// the corner `light` of the clockwise `corners` is the top right one.
+///
+/// The top right corner is light, so it is estimated from the others.
+fn with_light_corner(corners: &[(f32, f32); 4], light: usize) -> [(f32, f32); 4] {
+    let corner = |i: usize| corners[(light + i) % 4];
+    let (br, bl, tl) = (corner(1), corner(2), corner(3));
+    let tr = (br.0 + tl.0 - bl.0, br.1 + tl.1 - bl.1);
+    [tl, tr, br, bl]
+}
+
+/// Move the corners as long as fewer finder and timing modules are sampled wrong.
+///
+/// Returns the fraction of wrong modules and the corners.
+fn refine(
+    dark: &[bool],
+    width: usize,
+    size: SymbolSize,
+    mut corners: [(f32, f32); 4],
+) -> (f32, [(f32, f32); 4]) {
+    let ((w, _), _) = size.module_dimensions();
+    let (tl, tr) = (corners[0], corners[1]);
+    // roughly the module size, the exact length needs a square root
+    let module = (tr.0 - tl.0).abs().max((tr.1 - tl.1).abs()) / w as f32;
+    let mut errors = pattern_errors(dark, width, &Quad::new(corners), size);
+    for step in [module / 2.0, module / 4.0, module / 8.0] {
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for i in 0..4 {
+                for (dx, dy) in [(-1.0, 0.0), (1.0, 0.0), (0.0, -1.0), (0.0, 1.0)] {
+                    let mut moved = corners;
+                    moved[i].0 += dx * step;
+                    moved[i].1 += dy * step;
+                    let moved_errors = pattern_errors(dark, width, &Quad::new(moved), size);
+                    if moved_errors < errors {
+                        (errors, corners) = (moved_errors, moved);
+                        improved = true;
+                    }
+                }
+            }
+        }
+    }
+    (errors, corners)
+}
+
+/// Projective mapping from the unit square to the symbol in the image.
+///
+/// The corners of the square are the top left, top right, bottom right and bottom left
+/// corners of the symbol.
+#[derive(Debug, Clone)]
+struct Quad {
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32,
+    f: f32,
+    g: f32,
+    h: f32,
+}
+
+impl Quad {
+    fn new(p: [(f32, f32); 4]) -> Self {
+        let [(x0, y0), (x1, y1), (x2, y2), (x3, y3)] = p;
+        let (dx1, dx2, dx3) = (x1 - x2, x3 - x2, x0 - x1 + x2 - x3);
+        let (dy1, dy2, dy3) = (y1 - y2, y3 - y2, y0 - y1 + y2 - y3);
+        let det = dx1 * dy2 - dx2 * dy1;
+        let (g, h) = if det.abs() < 1e-6 {
+            (0.0, 0.0)
+        } else {
+            ((dx3 * dy2 - dx2 * dy3) / det, (dx1 * dy3 - dx3 * dy1) / det)
+        };
+        Self {
+            a: x1 - x0 + g * x1,
+            b: x3 - x0 + h * x3,
+            c: x0,
+            d: y1 - y0 + g * y1,
+            e: y3 - y0 + h * y3,
+            f: y0,
+            g,
+            h,
+        }
+    }
+
+    /// Map `(u, v)` of the unit square to the image.
+    fn map(&self, u: f32, v: f32) -> (f32, f32) {
+        let w = self.g * u + self.h * v + 1.0;
+        (
+            (self.a * u + self.b * v + self.c) / w,
+            (self.d * u + self.e * v + self.f) / w,
+        )
+    }
+
+    /// Check if the module at `row` and `col` of a symbol with `size` is dark.
+    fn sample(
+        &self,
+        dark: &[bool],
+        width: usize,
+        size: SymbolSize,
+        row: usize,
+        col: usize,
+    ) -> bool {
+        let ((w, h), _) = size.module_dimensions();
+        // the corners are the centers of the outer pixels, which is close enough
+        let (x, y) = self.map((col as f32 + 0.5) / w as f32, (row as f32 + 0.5) / h as f32);
+        let (x, y) = ((x + 0.5) as isize, (y + 0.5) as isize);
+        let height = dark.len() / width;
+        if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+            return false;
+        }
+        dark[y as usize * width + x as usize]
+    }
+}
+
+/// Get the fraction of finder and timing modules which are sampled wrong.
+fn pattern_errors(dark: &[bool], width: usize, quad: &Quad, size: SymbolSize) -> f32 {
+    let ((w, h), _) = size.module_dimensions();
+    let (mut total, mut errors) = (0, 0);
+    for row in 0..h {
+        for col in 0..w {
+            let expected = match crate::placement::module_role(size, row, col) {
+                ModuleRole::Finder => true,
+                ModuleRole::Timing => (row + col) % 2 == 0,
+                _ => continue,
+            };
+            total += 1;
+            if quad.sample(dark, width, size, row, col) != expected {
+                errors += 1;
+            }
+        }
+    }
+    errors as f32 / total as f32
+}
+
+/// Render the symbol in a grayscale image with `scale` pixels per module.
+///
+/// The symbol is rotated by the angle with `cos` and `sin` around the center
+/// of the image of `width` times `height` pixels.
+#[cfg(test)]
+fn render(
+    bitmap: &Bitmap<bool>,
+    scale: f32,
+    cos: f32,
+    sin: f32,
+    width: usize,
+    height: usize,
+) -> Vec<u8> {
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+    let (bw, bh) = (bitmap.width() as f32, bitmap.height() as f32);
+    let mut image = vec![230; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+            // rotate back into the symbol
+            let col = (cos * dx + sin * dy) / scale + bw / 2.0;
+            let row = (-sin * dx + cos * dy) / scale + bh / 2.0;
+            if col >= 0.0 && row >= 0.0 && col < bw && row < bh {
+                let i = row as usize * bitmap.width() + col as usize;
+                if bitmap.bits()[i] {
+                    image[y * width + x] = 30;
+                }
+            }
+        }
+    }
+    image
+}
+
+#[test]
+fn test_detect_rotated() {
+    use crate::DataMatrix;
+
+    let data = b"Detected in an image";
+    // cos and sin of 0, 10, 30, 45, 90, 180 and 270 degrees
+    let angles = [
+        (1.0, 0.0),
+        (0.984_807_7, 0.173_648_18),
+        (0.866_025_4, 0.5),
+        (
+            core::f32::consts::FRAC_1_SQRT_2,
+            core::f32::consts::FRAC_1_SQRT_2,
+        ),
+        (0.0, 1.0),
+        (-1.0, 0.0),
+        (0.0, -1.0),
+    ];
+    for size in [
+        SymbolSize::Square22,
+        SymbolSize::Rect12x36,
+        SymbolSize::Square52,
+    ] {
+        let code = DataMatrix::encode(data, size).unwrap();
+        let bitmap = code.bitmap();
+        for (cos, sin) in angles {
+            for scale in [3.0, 5.5] {
+                let side = (bitmap.width() as f32 * scale * 1.6) as usize;
+                let image = render(&bitmap, scale, cos, sin, side, side);
+                let sampled = detect(&image, side)
+                    .unwrap_or_else(|err| panic!("{:?} {:?} {} {}", err, size, cos, scale));
+                assert_eq!(
+                    DataMatrix::decode_bitmap(&sampled).as_deref(),
+                    Ok(&data[..]),
+                    "{:?} {} {} {}",
+                    size,
+                    cos,
+                    sin,
+                    scale
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_detect_noise() {
+    use crate::DataMatrix;
+
+    let code = DataMatrix::encode(b"noisy", SymbolSize::Square16).unwrap();
+    let bitmap = code.bitmap();
+    let mut image = render(&bitmap, 6.0, 0.984_807_7, -0.173_648_18, 150, 130);
+    // uneven brightness and some dark specks in the background
+    let mut rnd = crate::test::random_bytes();
+    for (i, px) in image.iter_mut().enumerate() {
+        *px = px.saturating_add((i % 150) as u8 / 8) - rnd() % 16;
+    }
+    for i in [5, 160, 1234, 19000] {
+        image[i] = 0;
+    }
+    let sampled = detect(&image, 150).unwrap();
+    assert_eq!(
+        DataMatrix::decode_bitmap(&sampled).as_deref(),
+        Ok(&b"noisy"[..])
+    );
+}
+
+#[test]
+fn test_detect_errors() {
+    assert_eq!(detect(&[], 0).err(), Some(DetectionError::ImageSize));
+    assert_eq!(detect(&[0; 10], 3).err(), Some(DetectionError::ImageSize));
+    assert_eq!(
+        detect(&[255; 100], 10).err(),
+        Some(DetectionError::NoSymbol)
+    );
+    // a big dark square has no timing pattern
+    let mut image = vec![255; 100 * 100];
+    for y in 20..80 {
+        image[y * 100 + 20..y * 100 + 80].fill(0);
+    }
+    assert_eq!(detect(&image, 100).err(), Some(DetectionError::NoMatch));
+}
+
+#[test]
+fn test_quad() {
+    let quad = Quad::new([(1.0, 2.0), (5.0, 2.0), (6.0, 8.0), (0.0, 8.0)]);
+    for ((u, v), expected) in [
+        ((0.0, 0.0), (1.0, 2.0)),
+        ((1.0, 0.0), (5.0, 2.0)),
+        ((1.0, 1.0), (6.0, 8.0)),
+        ((0.0, 1.0), (0.0, 8.0)),
+    ] {
+        let (x, y) = quad.map(u, v);
+        assert!((x - expected.0).abs() < 1e-4 && (y - expected.1).abs() < 1e-4);
+    }
+}
@@ -11,6 +11,9 @@ pub(crate) const PAD: u8 = 129;
 
 pub(crate) const UPPER_SHIFT: u8 = 235;
 
+/// The group separator, the field separator of GS1 data.
+pub(crate) const GS: u8 = 29;
+
 pub(super) fn two_digits_coming(rest: &[u8]) -> bool {
     match rest {
         [a, b, ..] => a.is_ascii_digit() && b.is_ascii_digit(),
@@ -33,6 +36,7 @@ pub(super) fn encode<T: EncodingContext>(ctx: &mut T) -> Result<(), DataEncoding
         }
         match ctx.eat() {
             None => return Ok(()),
+            Some(GS) if ctx.is_gs1() => ctx.push(FNC1),
             Some(ch @ 0..=127) => ctx.push(ch + 1),
             Some(ch @ 128..=255) => {
                 ctx.push(UPPER_SHIFT);
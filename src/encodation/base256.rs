@@ -14,6 +14,9 @@ fn randomize_255_state(ch: u8, pos: usize) -> u8 {
     }
 }
 
+/// Maximal number of bytes in a segment.
+const MAX_LEN: usize = 1555;
+
 /// Write the length "header" of this encodation.
 fn write_length<T: EncodingContext>(ctx: &mut T, start: usize) -> Result<(), DataEncodingError> {
     let space_left = ctx
@@ -24,13 +27,13 @@ fn write_length<T: EncodingContext>(ctx: &mut T, start: usize) -> Result<(), Dat
         let data_count = data_written - 1;
         if data_count <= 249 {
             ctx.replace(start, data_count as u8);
-        } else if data_count <= 1555 {
+        } else if data_count <= MAX_LEN {
             ctx.replace(start, ((data_count / 250) + 249) as u8);
             ctx.insert(start + 1, (data_count % 250) as u8);
             data_written += 1;
         } else {
             // if we get here the planner has a bug
-            panic!("base256 data too long, this is an encoding bug");
+            return Err(DataEncodingError::InternalError("base256 data too long"));
         }
     }
     for i in 0..data_written {
@@ -40,6 +43,20 @@ fn write_length<T: EncodingContext>(ctx: &mut T, start: usize) -> Result<(), Dat
     Ok(())
 }
 
+/// Number of codewords written by [encode] for non-empty `data`, see
+/// [EncodationType::estimate_codewords](super::EncodationType::estimate_codewords).
+///
+/// Data longer than the maximal length of a segment is split into several segments.
+pub(super) fn estimate_codewords(data: &[u8]) -> usize {
+    data.chunks(MAX_LEN)
+        .map(|segment| match segment.len() {
+            // latch, length and the bytes
+            n @ 0..=249 => 1 + 1 + n,
+            n => 1 + 2 + n,
+        })
+        .sum()
+}
+
 pub(super) fn encode<T: EncodingContext>(ctx: &mut T) -> Result<(), DataEncodingError> {
     let start = ctx.codewords().len();
     ctx.push(0);
@@ -12,6 +12,7 @@ const SHIFT1: u8 = 0;
 const SHIFT2: u8 = 1;
 const SHIFT3: u8 = 2;
 const UPPER_SHIFT: u8 = 30;
+const FNC1: u8 = 27;
 
 #[inline(always)]
 pub(super) fn low_ascii_to_c40_symbols(ctx: &mut ArrayVec<u8, 6>, ch: u8) {
@@ -74,7 +75,11 @@ pub(super) fn handle_end<T>(
 where
     T: EncodingContext,
 {
-    assert!(buf.len() <= 2);
+    if buf.len() > 2 {
+        return Err(DataEncodingError::InternalError(
+            "more than two C40 values at the end",
+        ));
+    }
 
     // this method is called after a requested mode switch if and only if
     // there are characters left
@@ -98,12 +103,10 @@ where
                 return Ok(());
             }
             // case d), implicit unlatch, then ascii
-            (1, 1) => {
-                if ascii::encoding_size(&[last_ch]) == 1 {
-                    ctx.set_ascii_until_end();
-                    ctx.backup(1);
-                    return Ok(());
-                }
+            (1, 1) if !ctx.is_strict() && ascii::encoding_size(&[last_ch]) == 1 => {
+                ctx.set_ascii_until_end();
+                ctx.backup(1);
+                return Ok(());
             }
             _ => (),
         }
@@ -130,7 +133,7 @@ where
                 .symbol_size_left(1)
                 .ok_or(DataEncodingError::TooMuchOrIllegalData)?;
             ctx.set_ascii_until_end();
-            if space_left >= 1 {
+            if space_left >= 1 || ctx.is_strict() {
                 ctx.push(super::UNLATCH);
             }
             return Ok(());
@@ -169,7 +172,12 @@ where
             break;
         }
         // encode the character into buf
-        to_vals(&mut buf, ch, &low_ascii_write);
+        if ch == ascii::GS && ctx.is_gs1() {
+            buf.push(SHIFT2);
+            buf.push(FNC1);
+        } else {
+            to_vals(&mut buf, ch, &low_ascii_write);
+        }
         last_ch = ch;
         while buf.len() >= 3 {
             write_three_values(ctx, buf[0], buf[1], buf[2]);
@@ -202,6 +210,22 @@ pub(super) fn encode<T: EncodingContext>(ctx: &mut T) -> Result<(), DataEncoding
     encode_generic(ctx, low_ascii_to_c40_symbols)
 }
 
+/// Number of codewords written by [encode_generic] for non-empty `data`, see
+/// [EncodationType::estimate_codewords](super::EncodationType::estimate_codewords).
+pub(super) fn estimate_codewords<F: Fn(u8) -> u8>(data: &[u8], val_size: F) -> usize {
+    let (head, two_digits) = match data {
+        [head @ .., a, b] if a.is_ascii_digit() && b.is_ascii_digit() => (head, true),
+        _ => (data, false),
+    };
+    let head_vals: usize = head.iter().map(|ch| val_size(*ch) as usize).sum();
+    if two_digits && head_vals.is_multiple_of(3) {
+        // latch, triples, UNLATCH, and the two digits in ASCII
+        return 1 + (head_vals / 3) * 2 + 2;
+    }
+    let vals: usize = data.iter().map(|ch| val_size(*ch) as usize).sum();
+    1 + vals.div_ceil(3) * 2 + 1
+}
+
 #[cfg(test)]
 fn vals(data: &[u8]) -> Vec<u8> {
     let mut vals = Vec::new();
@@ -266,3 +290,16 @@ fn test_shift_upper() {
     // third is 1, 30, 3
     assert_eq!(vals, vec![1, 30, 0, 0, 1, 30, 2, 31, 1, 30, 3]);
 }
+
+#[test]
+fn test_handle_end_too_many_values() {
+    use super::tests::TestEncodingContext;
+    let mut enc = TestEncodingContext::new(vec![], 10, -1);
+    let buf: ArrayVec<u8, 6> = [14, 15, 16].into_iter().collect();
+    assert_eq!(
+        handle_end(&mut enc, b'C', buf),
+        Err(DataEncodingError::InternalError(
+            "more than two C40 values at the end"
+        ))
+    );
+}
@@ -16,9 +16,12 @@ pub(crate) fn is_encodable(ch: u8) -> bool {
 }
 
 /// Encode 1 to 4 characters using EDIFACT and write it to the context.
+///
+/// Only the lower six bits of each character are used.
 fn write4<T: EncodingContext>(ctx: &mut T, s: &ArrayVec<u8, 4>) {
+    let s0 = s[0] & 0b11_1111;
     let s1 = s.get(1).copied().unwrap_or(0) & 0b11_1111;
-    ctx.push((s[0] << 2) | (s1 >> 4));
+    ctx.push((s0 << 2) | (s1 >> 4));
 
     if s.len() >= 2 {
         let s2 = s.get(2).copied().unwrap_or(0) & 0b11_1111;
@@ -35,9 +38,10 @@ fn handle_end<T: EncodingContext>(
     ctx: &mut T,
     mut symbols: ArrayVec<u8, 4>,
 ) -> Result<(), DataEncodingError> {
-    // check case "encoding with <= 2 ASCII, no UNLATCH"
+    // check case "encoding with <= 2 ASCII, no UNLATCH", five or more
+    // characters need at least three ASCII codewords
     let rest_chars = symbols.len() + ctx.characters_left();
-    if rest_chars <= 4 {
+    if rest_chars <= 4 && !ctx.is_strict() {
         // The standard allows ASCII encoding without UNLATCH if there
         // are <= 2 words of space left in the symbol and
         // we can encode the rest with ASCII in this space.
@@ -58,6 +62,9 @@ fn handle_end<T: EncodingContext>(
             }
         }
     }
+    // Decoders read a group with only one or two codewords left in the symbol
+    // as ASCII, so in strict mode an UNLATCH needs three codewords from here on.
+    let group_start = ctx.codewords().len();
     if symbols.is_empty() {
         if !ctx.has_more_characters() {
             // eod
@@ -66,41 +73,80 @@ fn handle_end<T: EncodingContext>(
                 .ok_or(DataEncodingError::TooMuchOrIllegalData)?;
             // padding case
             if space_left > 0 {
-                // the other case is caught in the "special end of data rule" above
-                assert!(space_left > 2);
+                if ctx.is_strict() {
+                    ctx.require_codewords(group_start + 3);
+                } else if space_left <= 2 {
+                    // the other case is caught in the "special end of data rule" above
+                    return Err(DataEncodingError::InternalError(
+                        "EDIFACT end of data rule not applied",
+                    ));
+                }
+                // at the start of a group, so the UNLATCH takes the upper six bits
+                // and the lower two bits are zero, like the padding in write4
                 ctx.push(UNLATCH << 2);
                 ctx.set_ascii_until_end();
             }
         } else {
             // mode switch
+            if ctx.is_strict() {
+                ctx.require_codewords(group_start + 3);
+            }
             ctx.push(UNLATCH << 2);
         }
     } else {
-        assert!(symbols.len() <= 3);
+        if symbols.len() > 3 {
+            return Err(DataEncodingError::InternalError(
+                "more than three EDIFACT values at the end",
+            ));
+        }
+        if !symbols.iter().copied().all(is_encodable) {
+            return Err(DataEncodingError::TooMuchOrIllegalData);
+        }
         if !ctx.has_more_characters() {
             // eod, maybe add UNLATCH for padding if space allows
             let space_left = ctx
                 .symbol_size_left(symbols.len())
                 .ok_or(DataEncodingError::TooMuchOrIllegalData)?
                 > 0;
-            if space_left || symbols.len() == 3 {
+            if space_left || symbols.len() == 3 || ctx.is_strict() {
                 symbols.push(UNLATCH);
                 ctx.set_ascii_until_end();
             }
         } else {
             symbols.push(UNLATCH);
         }
+        if ctx.is_strict() {
+            ctx.require_codewords(group_start + 3);
+        }
         write4(ctx, &symbols);
     }
     Ok(())
 }
 
+/// Number of codewords written by [encode] for non-empty `data`, see
+/// [EncodationType::estimate_codewords](super::EncodationType::estimate_codewords).
+pub(super) fn estimate_codewords(data: &[u8]) -> Option<usize> {
+    if !data.iter().copied().all(is_encodable) {
+        return None;
+    }
+    // the rest is written together with UNLATCH
+    let rest = match data.len() % 4 {
+        0 => 1,
+        1 => 2,
+        _ => 3,
+    };
+    Some(1 + (data.len() / 4) * 3 + rest)
+}
+
 pub(super) fn encode<T: EncodingContext>(ctx: &mut T) -> Result<(), DataEncodingError> {
     let mut symbols = ArrayVec::<u8, 4>::new();
     while let Some(ch) = ctx.eat() {
         symbols.push(ch);
 
         if symbols.len() == 4 {
+            if !symbols.iter().copied().all(is_encodable) {
+                return Err(DataEncodingError::TooMuchOrIllegalData);
+            }
             write4(ctx, &symbols);
             symbols.clear();
             if ctx.maybe_switch_mode()? {
@@ -157,3 +203,133 @@ fn test_write4_one() {
     write4(&mut enc, &s);
     assert_eq!(enc.codewords, vec![0b10_01_00_00]);
 }
+
+#[test]
+fn test_write4_masks_upper_bits() {
+    use super::tests::TestEncodingContext;
+    for chars in [b"ABCD", b"^@^@", b"Z1?A"] {
+        let mut enc = TestEncodingContext::new(vec![], 3, -1);
+        write4(&mut enc, &(*chars).into());
+        let mut enc_masked = TestEncodingContext::new(vec![], 3, -1);
+        write4(&mut enc_masked, &chars.map(|ch| ch & 0b11_1111).into());
+        assert_eq!(enc.codewords, enc_masked.codewords);
+    }
+    // garbage in the upper bits must not leak into the next value
+    let mut enc = TestEncodingContext::new(vec![], 3, -1);
+    write4(&mut enc, &[0b11_00_00_01, 0b11_00_00_10, 0, 0].into());
+    assert_eq!(enc.codewords, vec![0b00_00_01_00, 0b00_10_00_00, 0]);
+}
+
+#[test]
+fn test_handle_end_too_many_values() {
+    use super::tests::TestEncodingContext;
+    let mut enc = TestEncodingContext::new(vec![], 10, -1);
+    assert_eq!(
+        handle_end(&mut enc, (*b"ABCD").into()),
+        Err(DataEncodingError::InternalError(
+            "more than three EDIFACT values at the end"
+        ))
+    );
+}
+
+#[test]
+fn test_end_of_data_exhaustive() {
+    use crate::{DataMatrixBuilder, EncodationType, SymbolSize};
+
+    // length of EDIFACT from the start for `data` in `size` following ISO 16022, 5.2.8.2
+    let reference = |data: &[u8], size: SymbolSize| {
+        let capacity = size.num_data_codewords();
+        let (full, rest) = data.split_at(data.len() / 4 * 4);
+        let base = 1 + full.len() / 4 * 3;
+        let ascii_size = ascii::encoding_size(rest);
+        let space = capacity.saturating_sub(base);
+        if ascii_size <= space && space <= 2 {
+            // ASCII without UNLATCH, also for padding
+            base + ascii_size
+        } else {
+            // the rest is written together with UNLATCH
+            base + match rest.len() {
+                0 => 1,
+                1 => 2,
+                _ => 3,
+            }
+        }
+    };
+
+    let sizes = crate::SymbolList::all()
+        .iter()
+        .filter(|s| s.num_data_codewords() <= 30)
+        .collect::<alloc::vec::Vec<_>>();
+    for size in sizes {
+        let capacity = size.num_data_codewords();
+        for len in 1..=capacity * 4 / 3 + 4usize {
+            for tail in [&b"XYZ"[..], b"12", b"1"] {
+                let mut data = vec![b'A'; len.saturating_sub(tail.len())];
+                data.extend_from_slice(&tail[..tail.len().min(len)]);
+                let builder = DataMatrixBuilder::new()
+                    .with_symbol_list(size)
+                    .with_encodation_plan(vec![
+                        (data.len(), EncodationType::Edifact),
+                        (0, EncodationType::Edifact),
+                    ]);
+                let expected = reference(&data, size);
+                match builder.clone().encode_with_report(&data) {
+                    Ok((_, report)) => {
+                        let written = report.num_encoded_codewords();
+                        assert_eq!(written, expected, "{:?} {:?}", size, data);
+                        let code = builder.encode(&data).unwrap();
+                        assert_eq!(crate::data::decode_data(code.data_codewords()), Ok(data));
+                    }
+                    Err(err) => assert!(
+                        expected > capacity,
+                        "{:?} {:?} {:?} {}",
+                        err,
+                        size,
+                        data,
+                        expected
+                    ),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_unlatch_in_group() {
+    use super::tests::TestEncodingContext;
+
+    // "ABC" for the phases 1 to 3 of the last group
+    const A: u8 = b'A' & 0b11_1111;
+    const B: u8 = b'B' & 0b11_1111;
+    const C: u8 = b'C' & 0b11_1111;
+    let expected: [&[u8]; 4] = [
+        &[UNLATCH << 2],
+        &[(A << 2) | (UNLATCH >> 4), UNLATCH << 4],
+        &[(A << 2) | (B >> 4), (B << 4) | (UNLATCH >> 2), UNLATCH << 6],
+        &[(A << 2) | (B >> 4), (B << 4) | (C >> 2), (C << 6) | UNLATCH],
+    ];
+    assert_eq!(expected[0], [0b0111_1100]);
+    assert_eq!(expected[1], [0b0000_0101, 0b1111_0000]);
+    assert_eq!(expected[2], [0b0000_0100, 0b0010_0111, 0b1100_0000]);
+    assert_eq!(expected[3], [0b0000_0100, 0b0010_0000, 0b1101_1111]);
+
+    for (phase, expected) in expected.iter().enumerate() {
+        let mut data = b"WXYZ".to_vec();
+        data.extend_from_slice(&b"ABC"[..phase]);
+        let group = [0b0101_1101, 0b1000_0110, 0b0101_1010];
+
+        // end of data with space for padding
+        let mut enc = TestEncodingContext::new(data.clone(), 10, -1);
+        encode(&mut enc).unwrap();
+        assert_eq!(enc.codewords[..3], group);
+        assert_eq!(&enc.codewords[3..], *expected, "phase {}", phase);
+
+        // mode switch with more data
+        let len = data.len();
+        data.extend_from_slice(b"abcdef");
+        let mut enc = TestEncodingContext::new(data, 10, len as isize);
+        encode(&mut enc).unwrap();
+        assert_eq!(&enc.codewords[3..], *expected, "phase {}", phase);
+        assert_eq!(enc.rest(), b"abcdef");
+    }
+}
@@ -1,6 +1,7 @@
+use alloc::vec::Vec;
 use flagset::{flags, FlagSet};
 
-use super::{ascii, base256, c40, edifact, text, x12, DataEncodingError, GenericDataEncoder};
+use super::{ascii, base256, c40, edifact, text, x12, DataEncodingError, EncodingContext};
 
 flags! {
     /// List of data encodation types
@@ -26,11 +27,13 @@ impl EncodationType {
         match self {
             // Order is chosen based on my personal
             // estimate of which modes are more complicated.
+            // C40 is put before X12 so that ties are decided
+            // like in other encoders (zxing).
             Self::Ascii => 0,
             Self::Base256 => 1,
             Self::Edifact => 2,
-            Self::X12 => 3,
-            Self::C40 => 4,
+            Self::C40 => 3,
+            Self::X12 => 4,
             Self::Text => 5,
         }
     }
@@ -40,9 +43,66 @@ impl EncodationType {
         FlagSet::full()
     }
 
-    pub(super) fn encode<'a, 'b: 'a>(
+    /// Check if the byte `ch` can be represented in this mode.
+    ///
+    /// ASCII, C40, Text, and Base256 can encode every byte, potentially with a shift.
+    /// X12 and EDIFACT only support a subset of ASCII.
+    pub fn can_encode(&self, ch: u8) -> bool {
+        match self {
+            Self::Ascii | Self::C40 | Self::Text | Self::Base256 => true,
+            Self::X12 => x12::is_native_x12(ch),
+            Self::Edifact => edifact::is_encodable(ch),
+        }
+    }
+
+    /// Get the set of all modes which can represent the byte `ch`.
+    ///
+    /// ```rust
+    /// # use datamatrix::EncodationType;
+    /// assert!(!EncodationType::encodable_by(b'a').contains(EncodationType::X12));
+    /// ```
+    pub fn encodable_by(ch: u8) -> FlagSet<Self> {
+        Self::all()
+            .into_iter()
+            .filter(|mode| mode.can_encode(ch))
+            .fold(FlagSet::default(), |set, mode| set | mode)
+    }
+
+    /// Estimate the number of codewords needed to encode `data` fully in this mode.
+    ///
+    /// The count includes the latch from ASCII and the final UNLATCH (or the
+    /// ASCII encoded rest for X12) as written by the encoder when the
+    /// symbol is not yet full after the data, i.e., when padding follows.
+    /// The special end of data rules, which can save a codeword if the
+    /// data exactly fills a symbol, are not taken into account. The capacity of
+    /// the symbols is not checked, Base256 data longer than 1555 bytes is counted
+    /// as several segments. Nothing is allocated.
+    ///
+    /// Returns `None` if the data can not be encoded with this mode.
+    ///
+    /// ```rust
+    /// # use datamatrix::EncodationType;
+    /// // latch, two triples and UNLATCH
+    /// assert_eq!(EncodationType::C40.estimate_codewords(b"ABCDEF"), Some(6));
+    /// assert_eq!(EncodationType::X12.estimate_codewords(b"abc"), None);
+    /// ```
+    pub fn estimate_codewords(&self, data: &[u8]) -> Option<usize> {
+        if data.is_empty() {
+            return Some(0);
+        }
+        match self {
+            Self::Ascii => Some(ascii::encoding_size(data)),
+            Self::C40 => Some(c40::estimate_codewords(data, c40::val_size)),
+            Self::Text => Some(c40::estimate_codewords(data, text::val_size)),
+            Self::X12 => x12::estimate_codewords(data),
+            Self::Edifact => edifact::estimate_codewords(data),
+            Self::Base256 => Some(base256::estimate_codewords(data)),
+        }
+    }
+
+    pub(super) fn encode<T: EncodingContext>(
         &self,
-        encoder: &'a mut GenericDataEncoder<'b>,
+        encoder: &mut T,
     ) -> Result<(), DataEncodingError> {
         match self {
             Self::Ascii => ascii::encode(encoder),
@@ -59,14 +119,40 @@ impl EncodationType {
     }
 
     /// Get the LATCH codeword to switch to this mode from ASCII.
-    pub(super) fn latch_from_ascii(&self) -> u8 {
+    pub(super) fn latch_from_ascii(&self) -> Result<u8, DataEncodingError> {
         match self {
-            Self::Ascii => panic!("can not switch from ascii to ascii"),
-            Self::C40 => ascii::LATCH_C40,
-            Self::Text => ascii::LATCH_TEXT,
-            Self::X12 => ascii::LATCH_X12,
-            Self::Edifact => ascii::LATCH_EDIFACT,
-            Self::Base256 => ascii::LATCH_BASE256,
+            Self::Ascii => Err(DataEncodingError::InternalError(
+                "can not switch from ASCII to ASCII",
+            )),
+            Self::C40 => Ok(ascii::LATCH_C40),
+            Self::Text => Ok(ascii::LATCH_TEXT),
+            Self::X12 => Ok(ascii::LATCH_X12),
+            Self::Edifact => Ok(ascii::LATCH_EDIFACT),
+            Self::Base256 => Ok(ascii::LATCH_BASE256),
         }
     }
 }
+
+/// Choice of the encodation modes, see [DataMatrixBuilder::with_encodation](crate::DataMatrixBuilder::with_encodation).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Encodation {
+    /// Let the planner find a minimal encodation using all modes.
+    #[default]
+    Auto,
+    /// Use only this mode.
+    ///
+    /// ASCII may still be used at the end of data where the specification allows it.
+    Only(EncodationType),
+    /// Encode every character with this mode.
+    ///
+    /// Unlike with [Encodation::Only] no ASCII is added at the end, the encoding fails with
+    /// [DataEncodingError::TooMuchOrIllegalData] if this is not possible, or with
+    /// [DataEncodingError::IllegalCharacter] if the mode can not encode a character. For example,
+    /// C40 often needs ASCII for a single character at the end of data, so `"ABCD"`
+    /// fails while `"ABCDE"` works.
+    Exclusive(EncodationType),
+    /// Encode the first character with this mode and let the planner decide the rest.
+    StartWith(EncodationType),
+    /// Follow a plan, see [DataMatrixBuilder::with_encodation_plan](crate::DataMatrixBuilder::with_encodation_plan).
+    Plan(Vec<(usize, EncodationType)>),
+}
@@ -14,11 +14,16 @@ mod x12;
 mod encodation_type;
 // mod look_ahead;
 pub(crate) mod planner;
+mod slice;
 
 #[cfg(test)]
 mod tests;
+#[cfg(test)]
+mod vectors;
 
-pub use encodation_type::EncodationType;
+pub use encodation_type::{Encodation, EncodationType};
+pub use planner::Effort;
+pub(crate) use slice::SliceEncoder;
 
 pub(crate) const MACRO05: u8 = 236;
 pub(crate) const MACRO06: u8 = 237;
@@ -26,9 +31,8 @@ pub(crate) const MACRO05_HEAD: &[u8] = b"[)>\x1E05\x1D";
 pub(crate) const MACRO06_HEAD: &[u8] = b"[)>\x1E06\x1D";
 pub(crate) const MACRO_TRAIL: &[u8] = b"\x1E\x04";
 
-// The following is not implemented
-// const STRUCT_APPEND: u8 = 233;
-// const READER_PROGRAMMING: u8 = 234;
+pub(crate) const STRUCT_APPEND: u8 = 233;
+pub(crate) const READER_PROGRAMMING: u8 = 234;
 
 pub(crate) const UNLATCH: u8 = 254;
 
@@ -37,9 +41,226 @@ use pretty_assertions::assert_eq;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// Error when encoding the data part.
+#[non_exhaustive]
 pub enum DataEncodingError {
+    /// The data can not be encoded with the chosen encodation, e.g., with
+    /// [Encodation::Exclusive] or a plan, or does not fit into a symbol otherwise.
     TooMuchOrIllegalData,
+    /// The character at `position` can not be encoded by any enabled encodation type.
+    ///
+    /// The position is the byte offset of the character in the input. For text
+    /// passed to [encode_str](crate::DataMatrixBuilder::encode_str) it is the offset
+    /// in the UTF-8 string, so `&text[position..]` starts with the character. Bytes
+    /// are shown as their Latin-1 character. This is also returned for text which
+    /// is not Latin-1 with [CharsetPolicy::Error](crate::CharsetPolicy::Error).
+    IllegalCharacter {
+        position: usize,
+        character: char,
+    },
+    /// The data needs about `needed` data codewords, but the largest allowed
+    /// symbol size only has `available`.
+    ///
+    /// The `constraint` tells what to change for the data to fit. If the data does not
+    /// even fit into 144x144, `needed` is estimated.
+    TooMuchData {
+        needed: usize,
+        available: usize,
+        constraint: Constraint,
+    },
     SymbolListEmpty,
+    /// The number of data codewords does not match the capacity of the symbol size.
+    WrongNumberOfCodewords,
+    /// The leading codewords (FNC1, structured append, reader programming,
+    /// macros) are combined or ordered in a way forbidden by the specification.
+    InvalidOptions(&'static str),
+    /// An invariant of the encoder was violated, this is a bug.
+    InternalError(&'static str),
+    /// Reading the input failed.
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+}
+
+/// The option which keeps the data from fitting, see [DataEncodingError::TooMuchData].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Constraint {
+    /// The data fits into one of the allowed symbol sizes if all encodation types are enabled.
+    EncodationTypes,
+    /// The data fits into one of the allowed symbol sizes without the
+    /// [headroom](crate::DataMatrixBuilder::with_headroom).
+    Headroom,
+    /// The data fits into a bigger symbol size than allowed, e.g., by a fixed size.
+    SymbolList,
+    /// The data does not fit into any symbol, it can be split with structured append.
+    Capacity,
+}
+
+impl core::fmt::Display for Constraint {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let hint = match self {
+            Self::EncodationTypes => "enable more encodation types",
+            Self::Headroom => "reduce the headroom",
+            Self::SymbolList => "allow bigger symbol sizes",
+            Self::Capacity => "split the data into several symbols",
+        };
+        f.write_str(hint)
+    }
+}
+
+impl core::fmt::Display for DataEncodingError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TooMuchOrIllegalData => {
+                write!(
+                    f,
+                    "data does not fit into the symbols or can not be encoded"
+                )
+            }
+            Self::IllegalCharacter {
+                position,
+                character,
+            } => write!(
+                f,
+                "character {:?} at position {} can not be encoded",
+                character, position
+            ),
+            Self::TooMuchData {
+                needed,
+                available,
+                constraint,
+            } => write!(
+                f,
+                "data needs about {} codewords, but the largest allowed symbol has {} ({})",
+                needed, available, constraint
+            ),
+            Self::SymbolListEmpty => write!(f, "the symbol list is empty"),
+            Self::WrongNumberOfCodewords => {
+                write!(f, "number of codewords does not match the symbol size")
+            }
+            Self::InvalidOptions(msg) => write!(f, "invalid options: {}", msg),
+            Self::InternalError(msg) => write!(f, "internal encoder error: {}", msg),
+            #[cfg(feature = "std")]
+            Self::Io(kind) => write!(f, "reading the input failed: {}", kind),
+        }
+    }
+}
+
+impl core::error::Error for DataEncodingError {}
+
+/// Check the special codewords at the beginning of the data codewords.
+///
+/// The specification allows an optional structured append header (233 followed
+/// by three codewords) first, then at most one of FNC1, reader programming, or
+/// a macro. Reader programming and macros can not be used together with
+/// structured append. An ECI designator may precede the FNC1.
+pub(crate) fn validate_header(codewords: &[u8]) -> Result<(), DataEncodingError> {
+    let mut pos = 0;
+    let struct_append = codewords.first() == Some(&STRUCT_APPEND);
+    if struct_append {
+        if codewords.len() < 4 {
+            return Err(DataEncodingError::InvalidOptions(
+                "structured append needs three more codewords",
+            ));
+        }
+        pos = 4;
+    }
+    let after_eci = skip_eci(codewords, pos);
+    if after_eci != pos {
+        match codewords.get(after_eci).copied() {
+            Some(ascii::FNC1) => pos = after_eci,
+            Some(READER_PROGRAMMING | MACRO05 | MACRO06) => {
+                return Err(DataEncodingError::InvalidOptions(
+                    "reader programming and macros must be placed before an ECI",
+                ));
+            }
+            _ => return Ok(()),
+        }
+    }
+    let first = codewords.get(pos).copied();
+    match first {
+        Some(READER_PROGRAMMING) if struct_append => {
+            return Err(DataEncodingError::InvalidOptions(
+                "reader programming can not be used with structured append",
+            ));
+        }
+        Some(MACRO05 | MACRO06) if struct_append => {
+            return Err(DataEncodingError::InvalidOptions(
+                "macros can not be used with structured append",
+            ));
+        }
+        Some(ascii::FNC1 | READER_PROGRAMMING | MACRO05 | MACRO06) => pos += 1,
+        _ => return Ok(()),
+    }
+    let msg = match (first, codewords.get(skip_eci(codewords, pos)).copied()) {
+        (_, Some(STRUCT_APPEND)) => "structured append must be the first codeword",
+        (Some(ascii::FNC1), Some(ascii::FNC1)) => "FNC1 can only be used once at the start",
+        (Some(ascii::FNC1), Some(MACRO05 | MACRO06))
+        | (Some(MACRO05 | MACRO06), Some(ascii::FNC1)) => "FNC1 can not be used with macros",
+        (Some(ascii::FNC1), Some(READER_PROGRAMMING))
+        | (Some(READER_PROGRAMMING), Some(ascii::FNC1)) => {
+            "FNC1 can not be used with reader programming"
+        }
+        (Some(READER_PROGRAMMING), Some(READER_PROGRAMMING)) => {
+            "reader programming can only be used once at the start"
+        }
+        (Some(READER_PROGRAMMING), Some(MACRO05 | MACRO06))
+        | (Some(MACRO05 | MACRO06), Some(READER_PROGRAMMING)) => {
+            "macros can not be used with reader programming"
+        }
+        (Some(MACRO05 | MACRO06), Some(MACRO05 | MACRO06)) => {
+            "only one macro can be used at the start"
+        }
+        _ => return Ok(()),
+    };
+    Err(DataEncodingError::InvalidOptions(msg))
+}
+
+/// Get the position after the ECI designator at `pos`, or `pos` if there is none.
+fn skip_eci(codewords: &[u8], pos: usize) -> usize {
+    if codewords.get(pos) != Some(&ascii::ECI) {
+        return pos;
+    }
+    match codewords.get(pos + 1) {
+        Some(192..) => pos + 4,
+        Some(128..) => pos + 3,
+        _ => pos + 2,
+    }
+}
+
+/// Check that an encodation plan given by the user can be used for `data_len` characters.
+fn validate_plan(
+    plan: &[(usize, EncodationType)],
+    data_len: usize,
+) -> Result<(), DataEncodingError> {
+    let msg = match plan {
+        [] => "the encodation plan is empty",
+        [.., (left, _)] if *left != 0 => "the encodation plan must end with zero characters left",
+        [(left, _), ..] if *left > data_len => "the encodation plan is longer than the data",
+        _ if plan.windows(2).any(|w| w[0].0 <= w[1].0) => {
+            "the characters left in the encodation plan must be decreasing"
+        }
+        _ => return Ok(()),
+    };
+    Err(DataEncodingError::InvalidOptions(msg))
+}
+
+/// Remove switches to the mode which is already active from a valid plan.
+///
+/// Such switches do nothing, but they fail if they are not at the end of
+/// a C40, Text, X12 or EDIFACT block. The planner never creates them, they
+/// come from given plans, e.g., if plans for consecutive fields are joined.
+fn merge_segments(plan: &mut Vec<(usize, EncodationType)>) {
+    // the last entry with zero characters left is kept
+    plan.dedup_by(|next, previous| next.1 == previous.1 && next.0 > 0);
+}
+
+/// Number of codewords of the designator for `eci`, including the ECI codeword.
+pub(crate) fn eci_len(eci: u32) -> usize {
+    match eci {
+        0..=126 => 2,
+        127..=16382 => 3,
+        _ => 4,
+    }
 }
 
 trait EncodingContext {
@@ -57,8 +278,17 @@ trait EncodingContext {
 
     fn eat(&mut self) -> Option<u8>;
 
+    /// Put the last `steps` characters returned by [eat](Self::eat) back.
+    ///
+    /// Panics if `steps` is bigger than the number of characters eaten so far.
     fn backup(&mut self, steps: usize);
 
+    /// Get the input which was not consumed by [eat](Self::eat) yet.
+    ///
+    /// This is the rest of the whole input and does not depend on the current
+    /// mode, e.g., it also contains characters which are encoded later
+    /// after a mode switch. Characters put back with [backup](Self::backup) are
+    /// part of it again. A macro header and trailer are never part of it.
     fn rest(&self) -> &[u8];
 
     fn push(&mut self, ch: u8);
@@ -72,7 +302,19 @@ trait EncodingContext {
 
     fn set_ascii_until_end(&mut self);
 
-    /// Number of characters yet to be encoded.
+    /// Are the optional end of data rules with an implicit UNLATCH disabled?
+    fn is_strict(&self) -> bool;
+
+    /// The symbol must have at least `num` data codewords, including the ones written so far.
+    fn require_codewords(&mut self, num: usize);
+
+    /// Is the data GS1, so that a group separator (byte 29) is written as FNC1?
+    fn is_gs1(&self) -> bool;
+
+    /// Number of characters not consumed yet, this is the length of [rest](Self::rest).
+    ///
+    /// Characters which were eaten but are still buffered by a mode, e.g.,
+    /// EDIFACT values waiting for a full triple, are not counted.
     fn characters_left(&self) -> usize {
         self.rest().len()
     }
@@ -83,6 +325,76 @@ trait EncodingContext {
     }
 }
 
+/// Source of the pseudo random padding codewords.
+///
+/// The specification uses [Randomize253], which is the default. Another source
+/// can be set with [DataMatrixBuilder::with_randomizer](crate::DataMatrixBuilder::with_randomizer),
+/// e.g., [NoRandomization] to compare symbols with the debug output of other tools.
+pub trait Randomizer: Sync {
+    /// Get the padding codeword for the position `pos`, starting at 1.
+    ///
+    /// This is only used for the padding after the first PAD.
+    fn pad(&self, pos: usize) -> u8;
+}
+
+/// The "253 state" randomization of the specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Randomize253;
+
+impl Randomizer for Randomize253 {
+    fn pad(&self, pos: usize) -> u8 {
+        let pseudo_random = (((149 * pos) % 253) + 1) as u16;
+        let tmp = ascii::PAD as u16 + pseudo_random;
+        if tmp <= 254 {
+            tmp as u8
+        } else {
+            (tmp - 254) as u8
+        }
+    }
+}
+
+/// Plain PAD codewords, to compare symbols with the debug output of other tools.
+///
+/// **Warning:** Such symbols do not conform to the specification, do not use
+/// this for symbols which are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NoRandomization;
+
+impl Randomizer for NoRandomization {
+    fn pad(&self, _pos: usize) -> u8 {
+        ascii::PAD
+    }
+}
+
+/// The [Randomizer] of a builder.
+///
+/// Two are equal if they give the same padding at every position of a symbol.
+#[derive(Clone, Copy)]
+pub(crate) struct RandomizerRef(pub(crate) &'static dyn Randomizer);
+
+impl Default for RandomizerRef {
+    fn default() -> Self {
+        Self(&Randomize253)
+    }
+}
+
+impl PartialEq for RandomizerRef {
+    fn eq(&self, other: &Self) -> bool {
+        (1..=SymbolSize::Square144.num_data_codewords())
+            .all(|pos| self.0.pad(pos) == other.0.pad(pos))
+    }
+}
+
+impl core::fmt::Debug for RandomizerRef {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if *self == Self::default() {
+            f.write_str("Randomize253")
+        } else {
+            f.write_str("Randomizer")
+        }
+    }
+}
+
 pub(crate) struct GenericDataEncoder<'a> {
     data: &'a [u8],
     input: &'a [u8],
@@ -92,17 +404,31 @@ pub(crate) struct GenericDataEncoder<'a> {
     new_mode: Option<u8>,
     codewords: Vec<u8>,
     enabled_modes: FlagSet<EncodationType>,
+    strict: bool,
+    prefer_text: bool,
+    effort: Effort,
+    start_mode: Option<EncodationType>,
+    fixed_plan: bool,
+    gs1: bool,
+    headroom: f32,
+    /// Minimal number of data codewords of the symbol, see [EncodingContext::require_codewords].
+    min_codewords: usize,
+    num_unpadded: usize,
+    /// Length of a macro header removed from the start of the input.
+    input_offset: usize,
+    segments: Vec<(usize, EncodationType)>,
+    randomizer: &'a dyn Randomizer,
 }
 
 impl<'a> EncodingContext for GenericDataEncoder<'a> {
     fn maybe_switch_mode(&mut self) -> Result<bool, DataEncodingError> {
         let chars_left = self.characters_left();
-        assert!(
-            chars_left >= self.planned_switches[0].0,
-            "expected to call maybe_switch_mode when {} chars left, but now {}",
-            self.planned_switches[0].0,
-            chars_left
-        );
+        if chars_left < self.planned_switches[0].0 {
+            // the planner does not do this, but a given plan might
+            return Err(DataEncodingError::InvalidOptions(
+                "the encodation plan switches at a position where this is not possible",
+            ));
+        }
         let new_mode = if chars_left > 0 && chars_left == self.planned_switches[0].0 {
             let switch = self.planned_switches.remove(0);
             switch.1
@@ -114,7 +440,7 @@ impl<'a> EncodingContext for GenericDataEncoder<'a> {
             // switch to new mode if not ASCII
             self.encodation = new_mode;
             if !new_mode.is_ascii() {
-                self.new_mode = Some(new_mode.latch_from_ascii());
+                self.new_mode = Some(new_mode.latch_from_ascii()?);
             }
         }
         Ok(switch)
@@ -133,7 +459,14 @@ impl<'a> EncodingContext for GenericDataEncoder<'a> {
     }
 
     fn backup(&mut self, steps: usize) {
-        let offset = (self.input.len() - self.data.len()) - steps;
+        let eaten = self.input.len() - self.data.len();
+        debug_assert!(
+            steps <= eaten,
+            "backup of {} but only {} eaten",
+            steps,
+            eaten
+        );
+        let offset = eaten - steps;
         self.data = &self.input[offset..];
     }
 
@@ -161,6 +494,18 @@ impl<'a> EncodingContext for GenericDataEncoder<'a> {
         self.encodation = EncodationType::Ascii;
         self.planned_switches = vec![(0, EncodationType::Ascii)];
     }
+
+    fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    fn require_codewords(&mut self, num: usize) {
+        self.min_codewords = self.min_codewords.max(num);
+    }
+
+    fn is_gs1(&self) -> bool {
+        self.gs1
+    }
 }
 
 impl<'a> GenericDataEncoder<'a> {
@@ -178,22 +523,103 @@ impl<'a> GenericDataEncoder<'a> {
             codewords: Vec::new(),
             planned_switches: vec![],
             enabled_modes,
+            strict: false,
+            prefer_text: false,
+            effort: Effort::Exhaustive,
+            start_mode: None,
+            fixed_plan: false,
+            gs1: false,
+            headroom: 0.0,
+            min_codewords: 0,
+            num_unpadded: 0,
+            input_offset: 0,
+            segments: Vec::new(),
+            randomizer: &Randomize253,
         }
     }
 
+    /// Follow the given plan instead of computing one.
+    ///
+    /// See [data::encodation_plan](crate::data::encodation_plan) for the format.
+    pub fn set_plan(&mut self, plan: Vec<(usize, EncodationType)>) {
+        self.planned_switches = plan;
+        self.fixed_plan = true;
+    }
+
+    /// Disable the optional end of data rules which leave out the UNLATCH.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Decide ties between C40 and Text in favor of Text when planning.
+    pub fn set_prefer_text(&mut self, prefer_text: bool) {
+        self.prefer_text = prefer_text;
+    }
+
+    /// Set how much work is spent on planning.
+    pub fn set_effort(&mut self, effort: Effort) {
+        self.effort = effort;
+    }
+
+    /// Encode the first character with `mode` instead of the planned one.
+    pub fn set_start_mode(&mut self, mode: Option<EncodationType>) {
+        self.start_mode = mode;
+    }
+
+    /// Use another source for the padding than the specification.
+    pub fn set_randomizer(&mut self, randomizer: &'a dyn Randomizer) {
+        self.randomizer = randomizer;
+    }
+
+    /// Pick a symbol size where the fraction `headroom` of the data codewords is unused.
+    pub fn set_headroom(&mut self, headroom: f32) {
+        self.headroom = headroom;
+    }
+
+    /// Write the codewords into `buffer` instead of a new allocation.
+    ///
+    /// The buffer is cleared.
+    pub fn use_buffer(&mut self, mut buffer: Vec<u8>) {
+        buffer.clear();
+        self.codewords = buffer;
+    }
+
     pub fn use_macro_if_possible(&mut self) {
-        if !self.codewords.is_empty() && !self.data.ends_with(MACRO_TRAIL) {
+        if !self.codewords.is_empty() || !self.data.ends_with(MACRO_TRAIL) {
             return;
         }
         for (head, cw) in [(MACRO05_HEAD, MACRO05), (MACRO06_HEAD, MACRO06)] {
             if self.data.starts_with(head) {
                 self.codewords.push(cw);
                 self.data = &self.data[head.len()..self.data.len() - MACRO_TRAIL.len()];
+                self.input = self.data;
+                self.input_offset = head.len();
                 break;
             }
         }
     }
 
+    /// Write a structured append header, the sequence indicator followed by the file id.
+    pub fn write_structured_append(&mut self, header: [u8; 3]) {
+        self.codewords.push(STRUCT_APPEND);
+        self.codewords.extend_from_slice(&header);
+    }
+
+    /// Write the codeword marking the symbol as reader programming.
+    pub fn write_reader_programming(&mut self) {
+        self.codewords.push(READER_PROGRAMMING);
+    }
+
+    /// Write an FNC1 to mark the data as GS1.
+    ///
+    /// If an ECI is used, it must be written before. Group separators in
+    /// the data are then encoded as FNC1, too.
+    pub fn write_fnc1(&mut self) {
+        self.codewords.push(ascii::FNC1);
+        self.gs1 = true;
+    }
+
+    /// Write an ECI designator, see [eci_len] for its length.
     pub fn write_eci(&mut self, mut c: u32) {
         self.codewords.push(ascii::ECI);
         match c {
@@ -217,6 +643,7 @@ impl<'a> GenericDataEncoder<'a> {
         if self.symbol_list.is_empty() {
             return Err(DataEncodingError::SymbolListEmpty);
         }
+        validate_header(&self.codewords)?;
 
         // bigger than theoretical limit? then fail early
         if self.data.len() > self.symbol_list.max_capacity() {
@@ -226,20 +653,38 @@ impl<'a> GenericDataEncoder<'a> {
         self.codewords
             .reserve(self.upper_limit_for_number_of_codewords()?);
 
-        self.planned_switches = planner::optimize(
-            self.data,
-            self.codewords.len(),
-            EncodationType::Ascii,
-            self.symbol_list,
-            self.enabled_modes,
-        )
-        .ok_or(DataEncodingError::TooMuchOrIllegalData)?;
+        if self.fixed_plan {
+            validate_plan(&self.planned_switches, self.data.len())?;
+            merge_segments(&mut self.planned_switches);
+        } else {
+            self.planned_switches = planner::plan(
+                self.data,
+                self.codewords.len(),
+                self.symbol_list,
+                self.headroom,
+                self.enabled_modes,
+                self.strict,
+                self.prefer_text,
+                self.effort,
+                self.start_mode,
+            )
+            .ok_or(DataEncodingError::TooMuchOrIllegalData)?;
+        }
 
         let mut no_write_run = 0;
+        self.min_codewords = 0;
+        self.segments.clear();
         while self.has_more_characters() {
             if let Some(new_mode) = self.new_mode.take() {
                 self.push(new_mode);
             }
+            let pos = self.input_offset + self.input.len() - self.data.len();
+            match self.segments.last_mut() {
+                Some(last) if last.1 == self.encodation => (),
+                // nothing was encoded in the last mode, e.g., a switch at the start
+                Some(last) if last.0 == pos => last.1 = self.encodation,
+                _ => self.segments.push((pos, self.encodation)),
+            }
             let len = self.codewords.len();
 
             self.encodation.clone().encode(self)?;
@@ -248,7 +693,9 @@ impl<'a> GenericDataEncoder<'a> {
             if words_written <= 1 {
                 // no mode can do something useful in 1 word (at EOD, but that is fine)
                 no_write_run += 1;
-                assert!(no_write_run <= 5, "no progress in encoder, this is a bug");
+                if no_write_run > 5 {
+                    return Err(DataEncodingError::InternalError("no progress in encoder"));
+                }
             } else {
                 no_write_run = 0;
             }
@@ -257,6 +704,7 @@ impl<'a> GenericDataEncoder<'a> {
         let symbol_size = self
             .symbol_for(0)
             .ok_or(DataEncodingError::TooMuchOrIllegalData)?;
+        self.num_unpadded = self.codewords.len();
         self.add_padding(symbol_size);
 
         let mut codewords = vec![];
@@ -265,9 +713,27 @@ impl<'a> GenericDataEncoder<'a> {
         Ok((codewords, symbol_size))
     }
 
+    /// Get the number of codewords written so far, e.g., by [write_eci](Self::write_eci).
+    pub fn num_written_codewords(&self) -> usize {
+        self.codewords.len()
+    }
+
+    /// Get the number of codewords before the padding from the last call of [codewords](Self::codewords).
+    pub fn num_unpadded_codewords(&self) -> usize {
+        self.num_unpadded
+    }
+
+    /// Take the modes used in the last call of [codewords](Self::codewords).
+    ///
+    /// Each entry contains the position in the input where the mode starts.
+    pub fn take_segments(&mut self) -> Vec<(usize, EncodationType)> {
+        core::mem::take(&mut self.segments)
+    }
+
     fn symbol_for(&self, extra_codewords: usize) -> Option<SymbolSize> {
+        let size_needed = (self.codewords.len() + extra_codewords).max(self.min_codewords);
         self.symbol_list
-            .first_symbol_big_enough_for(self.codewords.len() + extra_codewords)
+            .first_symbol_with_headroom(size_needed, self.headroom)
     }
 
     fn add_padding(&mut self, size: SymbolSize) {
@@ -285,15 +751,8 @@ impl<'a> GenericDataEncoder<'a> {
             size_left -= 1;
         }
         for _ in 0..size_left {
-            // "randomize 253 state"
             let pos = self.codewords.len() + 1;
-            let pseudo_random = (((149 * pos) % 253) + 1) as u16;
-            let tmp = ascii::PAD as u16 + pseudo_random;
-            if tmp <= 254 {
-                self.push(tmp as u8);
-            } else {
-                self.push((tmp - 254) as u8);
-            }
+            self.push(self.randomizer.pad(pos));
         }
     }
 
@@ -305,6 +764,72 @@ impl<'a> GenericDataEncoder<'a> {
     }
 }
 
+#[test]
+fn test_backup() {
+    let symbols = crate::SymbolList::default();
+    let mut enc = GenericDataEncoder::with_size(b"ABC", &symbols, EncodationType::all());
+    enc.backup(0);
+    assert_eq!(enc.rest(), b"ABC");
+    assert_eq!(enc.eat(), Some(b'A'));
+    assert_eq!(enc.eat(), Some(b'B'));
+    enc.backup(2);
+    assert_eq!(enc.rest(), b"ABC");
+    while enc.eat().is_some() {}
+    enc.backup(1);
+    assert_eq!(enc.rest(), b"C");
+    enc.backup(2);
+    assert_eq!(enc.rest(), b"ABC");
+}
+
+#[test]
+fn test_backup_after_macro() {
+    let symbols = crate::SymbolList::default();
+    let data = b"[)>\x1e05\x1dAB\x1e\x04";
+    let mut enc = GenericDataEncoder::with_size(data, &symbols, EncodationType::all());
+    enc.use_macro_if_possible();
+    assert_eq!(enc.rest(), b"AB");
+    enc.backup(0);
+    assert_eq!(enc.rest(), b"AB");
+    assert_eq!(enc.eat(), Some(b'A'));
+    enc.backup(1);
+    assert_eq!(enc.rest(), b"AB");
+}
+
+#[test]
+fn test_rest_and_characters_left() {
+    let symbols = crate::SymbolList::default();
+    let mut enc = GenericDataEncoder::with_size(b"AB.-", &symbols, EncodationType::all());
+    enc.set_plan(vec![
+        (2, EncodationType::Edifact),
+        (0, EncodationType::Edifact),
+    ]);
+    assert_eq!((enc.rest(), enc.characters_left()), (&b"AB.-"[..], 4));
+    enc.eat();
+    enc.eat();
+    assert!(enc.has_more_characters());
+    // a mode switch does not change the input
+    assert_eq!(enc.maybe_switch_mode(), Ok(true));
+    assert_eq!((enc.rest(), enc.characters_left()), (&b".-"[..], 2));
+    enc.eat();
+    enc.eat();
+    assert_eq!((enc.rest(), enc.characters_left()), (&b""[..], 0));
+    assert!(!enc.has_more_characters());
+    assert_eq!(enc.eat(), None);
+    assert_eq!(enc.characters_left(), 0);
+    enc.backup(1);
+    assert_eq!((enc.rest(), enc.characters_left()), (&b"-"[..], 1));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic]
+fn test_backup_too_far() {
+    let symbols = crate::SymbolList::default();
+    let mut enc = GenericDataEncoder::with_size(b"ABC", &symbols, EncodationType::all());
+    enc.eat();
+    enc.backup(2);
+}
+
 #[test]
 fn test_empty() {
     let symbols = crate::SymbolList::default();
@@ -312,3 +837,52 @@ fn test_empty() {
     let (cw, _) = GenericDataEncoder::codewords(&mut enc).unwrap();
     assert_eq!(cw, vec![ascii::PAD, 175, 70]);
 }
+
+#[test]
+fn test_padding_rectangular() {
+    // reference values computed from the 253-state algorithm in the specification
+    let symbols = SymbolSize::Rect8x32.into();
+    let mut enc = GenericDataEncoder::with_size(b"A", &symbols, EncodationType::all());
+    let (cw, size) = GenericDataEncoder::codewords(&mut enc).unwrap();
+    assert_eq!(size, SymbolSize::Rect8x32);
+    assert_eq!(
+        cw,
+        vec![66, ascii::PAD, 70, 220, 115, 11, 161, 56, 206, 101]
+    );
+
+    let symbols = SymbolSize::Rect16x36.into();
+    let mut enc = GenericDataEncoder::with_size(b"AB", &symbols, EncodationType::all());
+    let (cw, size) = GenericDataEncoder::codewords(&mut enc).unwrap();
+    assert_eq!(size, SymbolSize::Rect16x36);
+    assert_eq!(cw.len(), 32);
+    assert_eq!(
+        &cw[20..],
+        &[223, 118, 14, 164, 59, 209, 104, 254, 150, 45, 195, 90]
+    );
+}
+
+#[test]
+fn test_no_randomization() {
+    let symbols = SymbolSize::Rect8x32.into();
+    let mut enc = GenericDataEncoder::with_size(b"A", &symbols, EncodationType::all());
+    enc.set_randomizer(&NoRandomization);
+    let (cw, _) = GenericDataEncoder::codewords(&mut enc).unwrap();
+    assert_eq!(
+        cw,
+        vec![66, ascii::PAD, 129, 129, 129, 129, 129, 129, 129, 129]
+    );
+
+    // only the padding differs from the regular encoding
+    let symbols = SymbolSize::Square20.into();
+    for data in [&b"ABCDEF"[..], b"abc", b"\xe4\xe4\xe4\xe4\xe4"] {
+        let mut enc = GenericDataEncoder::with_size(data, &symbols, EncodationType::all());
+        let (regular, _) = GenericDataEncoder::codewords(&mut enc).unwrap();
+        let mut enc = GenericDataEncoder::with_size(data, &symbols, EncodationType::all());
+        enc.set_randomizer(&NoRandomization);
+        let (cw, _) = GenericDataEncoder::codewords(&mut enc).unwrap();
+        let unpadded = enc.num_unpadded_codewords();
+        assert_eq!(cw[..unpadded + 1], regular[..unpadded + 1]);
+        assert!(cw[unpadded..].iter().all(|cw| *cw == ascii::PAD));
+        assert_ne!(cw, regular);
+    }
+}
@@ -66,18 +66,22 @@ impl<T: ContextInformation> Plan for Base256Plan<T> {
 
     fn step(&mut self) -> Option<StepResult> {
         let end = !self.ctx.has_more_characters();
+        let mut unbeatable = false;
         if !end {
-            let _ = self.ctx.eat().unwrap();
+            let ch = self.ctx.eat().unwrap();
             self.written += 1;
             self.cost += 1;
             self.ctx.write(1);
             if self.written == 1556 {
                 return None;
             }
+            // Switching away before a byte from the upper range can not help:
+            // ASCII needs two codewords for it, C40 and Text at least three
+            // values, and X12 and EDIFACT can not encode it at all. Switching
+            // after the byte is at least as good, unless the byte makes the
+            // length field grow.
+            unbeatable = ch >= 128 && self.written != 250;
         }
-        Some(StepResult {
-            end,
-            unbeatable: false,
-        })
+        Some(StepResult { end, unbeatable })
     }
 }
@@ -122,7 +122,7 @@ impl<T: ContextInformation, U: CharsetInfo> Plan for C40LikePlan<T, U> {
             let space_left = self.ctx.symbol_size_left(1).unwrap_or(0);
             // this also includes the `self.two_digit_ascii_end` case...
             let ascii_size = ascii::encoding_size(&[self.ch]);
-            if space_left == 0 {
+            if space_left == 0 && !self.ctx.is_strict() {
                 if ascii_size == 1 {
                     1
                 } else {
@@ -147,7 +147,7 @@ impl<T: ContextInformation, U: CharsetInfo> Plan for C40LikePlan<T, U> {
             if matches!(self.ctx.rest(), [a, b] if a.is_ascii_digit() && b.is_ascii_digit()) {
                 let space_left = self.ctx.symbol_size_left(1)?;
                 self.two_digit_ascii_end = space_left <= 1;
-                if space_left == 1 {
+                if space_left == 1 || (space_left == 0 && self.ctx.is_strict()) {
                     // UNLATCH + ASCII(two digits)
                     self.unbeatable_reads = 2;
                     self.ctx.write(2);
@@ -3,6 +3,9 @@ use super::{frac::C, Frac, Plan, StepResult};
 use crate::encodation::ascii;
 use crate::encodation::edifact::is_encodable;
 
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
 #[derive(Debug, PartialEq, Clone)]
 pub(super) struct EdifactPlan<T: ContextInformation> {
     /// Number of values not yet written
@@ -10,6 +13,8 @@ pub(super) struct EdifactPlan<T: ContextInformation> {
     written: usize,
     ascii_end: Option<Frac>,
     cost: Frac,
+    /// Was the cost of the UNLATCH at the end added in strict mode?
+    strict_end: bool,
 }
 
 impl<T: ContextInformation> EdifactPlan<T> {
@@ -19,6 +24,7 @@ impl<T: ContextInformation> EdifactPlan<T> {
             ascii_end: None,
             written: if free_unlatch { 3 } else { 0 },
             cost: 0.into(),
+            strict_end: false,
         }
     }
 
@@ -58,7 +64,11 @@ impl<T: ContextInformation> Plan for EdifactPlan<T> {
     fn step(&mut self) -> Option<StepResult> {
         let end = !self.ctx.has_more_characters();
         if !end {
-            if self.written == 0 && self.ctx.characters_left() <= 4 && self.ascii_end.is_none() {
+            if self.written == 0
+                && self.ctx.characters_left() <= 4
+                && self.ascii_end.is_none()
+                && !self.ctx.is_strict()
+            {
                 // are we in a possible end of data situation?
                 let ascii_size = ascii::encoding_size(self.ctx.rest());
                 if ascii_size <= 2 {
@@ -84,9 +94,56 @@ impl<T: ContextInformation> Plan for EdifactPlan<T> {
                 }
             }
         }
+        if end && self.ctx.is_strict() && !self.strict_end {
+            // the UNLATCH group at the end takes three codewords, see edifact::handle_end
+            self.strict_end = true;
+            self.cost = self.cost - Frac::new(3 * self.written as C, 4) + 3;
+        }
         Some(StepResult {
             end,
             unbeatable: self.ascii_end.is_some(),
         })
     }
 }
+
+#[test]
+fn test_fractional_cost() {
+    use super::generic::Context;
+
+    let symbols = crate::SymbolList::default();
+    let data = b".-/+?:'=,;.-/+?:'=,;.-/+?:'=,;.-/+?:'=,;";
+    let mut plan = EdifactPlan::new(Context::new(data, &symbols));
+    for i in 1..=36 {
+        assert!(plan.step().is_some());
+        // every character costs exactly 3/4 codewords, no rounding
+        assert_eq!(plan.cost(), Frac::new(3 * i, 4), "char {}", i);
+    }
+}
+
+#[test]
+fn test_edifact_run_wins() {
+    use crate::data::{encodation_plan, encode_data};
+    use crate::symbol_size::SymbolSize;
+    use crate::EncodationType;
+    use alloc::vec;
+
+    let symbols = SymbolSize::Square144.into();
+    let data = b".-/+?:'=,;.-/+?:'=,;.-/+?:'=,;.-/+?:'=,;ABC123";
+    for len in 40..=46 {
+        let data = &data[..len];
+        let plan = encodation_plan(data, &symbols, EncodationType::all());
+        assert_eq!(
+            plan,
+            Some(vec![
+                (len, EncodationType::Edifact),
+                (0, EncodationType::Edifact)
+            ]),
+            "len {}",
+            len
+        );
+        // latch + 3 codewords per 4 chars + rest with UNLATCH
+        let expected = 1 + (len / 4) * 3 + [1, 2, 3, 3][len % 4];
+        let (cw, _) = encode_data(data, &symbols, None, EncodationType::all(), false).unwrap();
+        assert_eq!(cw[expected], crate::encodation::ascii::PAD, "len {}", len);
+    }
+}
@@ -1,9 +1,11 @@
 use alloc::{vec, vec::Vec};
+use arrayvec::ArrayVec;
 use core::fmt::{Debug, Error, Formatter};
+use core::ops::DerefMut;
 
 use flagset::FlagSet;
 
-use crate::{encodation::encodation_type::EncodationType, symbol_size::SymbolList};
+use crate::{encodation::encodation_type::EncodationType, symbol_size::Symbols};
 
 use super::{
     ascii::AsciiPlan, base256::Base256Plan, c40::C40Plan, edifact::EdifactPlan, frac::Frac,
@@ -13,14 +15,130 @@ use super::{
 #[cfg(test)]
 use pretty_assertions::assert_eq;
 
+/// Storage for the mode switches of a plan, see [GenericPlan::for_mode].
+pub(super) trait Switches: Clone + PartialEq + Debug {
+    fn new(switch: (usize, EncodationType)) -> Self;
+
+    fn push(&mut self, switch: (usize, EncodationType));
+
+    fn remove_first(&mut self);
+
+    /// Get the stored switches, this may only be the first ones.
+    fn as_slice(&self) -> &[(usize, EncodationType)];
+
+    /// Get the number of switches, including the ones which are not stored.
+    fn count(&self) -> usize;
+}
+
+impl Switches for Vec<(usize, EncodationType)> {
+    fn new(switch: (usize, EncodationType)) -> Self {
+        vec![switch]
+    }
+
+    fn push(&mut self, switch: (usize, EncodationType)) {
+        Vec::push(self, switch);
+    }
+
+    fn remove_first(&mut self) {
+        self.remove(0);
+    }
+
+    fn as_slice(&self) -> &[(usize, EncodationType)] {
+        self
+    }
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+}
+
+/// The first switches of a plan, enough to follow it for a while without allocation.
+///
+/// When they are used up, the rest of the data is planned again.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct FirstSwitches {
+    switches: ArrayVec<(usize, EncodationType), 3>,
+    count: usize,
+}
+
+impl FirstSwitches {
+    /// Get the stored switches, the last one may not be at the end of the data.
+    pub(super) fn into_inner(self) -> ArrayVec<(usize, EncodationType), 3> {
+        self.switches
+    }
+}
+
+impl Switches for FirstSwitches {
+    fn new(switch: (usize, EncodationType)) -> Self {
+        let mut switches = ArrayVec::new();
+        switches.push(switch);
+        Self { switches, count: 1 }
+    }
+
+    fn push(&mut self, switch: (usize, EncodationType)) {
+        let _ = self.switches.try_push(switch);
+        self.count += 1;
+    }
+
+    fn remove_first(&mut self) {
+        self.switches.remove(0);
+        self.count -= 1;
+    }
+
+    fn as_slice(&self) -> &[(usize, EncodationType)] {
+        &self.switches
+    }
+
+    fn count(&self) -> usize {
+        self.count
+    }
+}
+
+/// Storage for a list of plans, an `ArrayVec` to plan without allocation.
+pub(super) trait PlanList<T>: Default + DerefMut<Target = [T]> + Extend<T> {
+    fn push(&mut self, plan: T);
+
+    fn remove(&mut self, index: usize) -> T;
+
+    fn drain_all(&mut self) -> impl Iterator<Item = T> + '_;
+}
+
+impl<T> PlanList<T> for Vec<T> {
+    fn push(&mut self, plan: T) {
+        Vec::push(self, plan);
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        Vec::remove(self, index)
+    }
+
+    fn drain_all(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.drain(..)
+    }
+}
+
+impl<T, const CAP: usize> PlanList<T> for ArrayVec<T, CAP> {
+    fn push(&mut self, plan: T) {
+        ArrayVec::push(self, plan);
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        ArrayVec::remove(self, index)
+    }
+
+    fn drain_all(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.drain(..)
+    }
+}
+
 #[derive(Clone, PartialEq)]
-pub(super) struct GenericPlan<'a> {
+pub(super) struct GenericPlan<'a, S = Vec<(usize, EncodationType)>> {
     extra: Frac,
-    pub(super) switches: Vec<(usize, EncodationType)>,
+    pub(super) switches: S,
     plan: PlanImpl<'a>,
 }
 
-impl<'a> Debug for GenericPlan<'a> {
+impl<'a, S: Switches> Debug for GenericPlan<'a, S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         match &self.plan {
             PlanImpl::Ascii(pl) => f.write_fmt(format_args!(
@@ -85,19 +203,21 @@ enum PlanImpl<'a> {
     Base256(Base256Plan<Context<'a>>),
 }
 
-impl<'a> GenericPlan<'a> {
+impl<'a, S: Switches> GenericPlan<'a, S> {
     /// Create an instance which starts with the given encodation type.
     ///
-    /// The `free_unlatch` is currently only used by EDIFACT, it marks
-    /// that 3 out of 4 values are read, a UNLATCH would now not
-    /// result in additional cost.
+    /// The switches are stored in `S`, a `Vec` for the whole plan or [FirstSwitches].
     pub(super) fn for_mode(
         mode: EncodationType,
         data: &'a [u8],
         written: usize,
-        symbol_list: &'a SymbolList,
+        symbols: impl Into<Symbols<'a>>,
+        headroom: f32,
+        strict: bool,
     ) -> Self {
-        let mut ctx = Context::new(data, symbol_list);
+        let mut ctx = Context::new(data, symbols);
+        ctx.headroom = headroom;
+        ctx.strict = strict;
         ctx.write(written);
         let plan = match mode {
             EncodationType::Ascii => PlanImpl::Ascii(AsciiPlan::new(ctx)),
@@ -109,14 +229,14 @@ impl<'a> GenericPlan<'a> {
         };
         Self {
             extra: 0.into(),
-            switches: vec![(data.len(), mode)],
+            switches: S::new((data.len(), mode)),
             plan,
         }
     }
 
     /// Get the mode this plan started with.
     pub(super) fn start_mode(&self) -> EncodationType {
-        self.switches[0].1
+        self.switches.as_slice()[0].1
     }
 
     /// Get the current encodation type (mode).
@@ -133,7 +253,7 @@ impl<'a> GenericPlan<'a> {
 
     pub(super) fn add_switches(
         self,
-        list: &mut Vec<Self>,
+        list: &mut impl PlanList<Self>,
         rest_len: usize,
         as_start: bool,
         enabled_modes: FlagSet<EncodationType>,
@@ -147,18 +267,18 @@ impl<'a> GenericPlan<'a> {
 
         macro_rules! add_switch {
             ($plan:ident, $enum:ident, $cost_extra:expr) => {
-                let switches = if as_start {
-                    assert_eq!(self.switches.len(), 1);
-                    vec![(rest_len, EncodationType::$enum)]
-                } else {
-                    let mut switches = self.switches.clone();
-                    switches.push((rest_len, EncodationType::$enum));
-                    switches
-                };
                 let mut ctx = ctx.clone();
                 ctx.write($cost_extra); // LATCH byte
                 let mut new = $plan::new(ctx);
                 if let Some(_) = new.step() {
+                    let switches = if as_start {
+                        assert_eq!(self.switches.count(), 1);
+                        S::new((rest_len, EncodationType::$enum))
+                    } else {
+                        let mut switches = self.switches.clone();
+                        switches.push((rest_len, EncodationType::$enum));
+                        switches
+                    };
                     list.push(Self {
                         extra: ascii_cost + $cost_extra,
                         switches,
@@ -231,7 +351,7 @@ impl<'a> GenericPlan<'a> {
     }
 }
 
-impl<'a> Plan for GenericPlan<'a> {
+impl<'a, S: Switches> Plan for GenericPlan<'a, S> {
     type Context = Context<'a>;
 
     fn mode_switch_cost(&self) -> Option<Frac> {
@@ -284,18 +404,22 @@ impl<'a> Plan for GenericPlan<'a> {
 #[derive(Debug, PartialEq, Clone)]
 pub(super) struct Context<'a> {
     data: &'a [u8],
-    symbol_list: &'a SymbolList,
+    symbols: Symbols<'a>,
     consumed: usize,
     written: usize,
+    headroom: f32,
+    strict: bool,
 }
 
 impl<'a> Context<'a> {
-    pub(super) fn new(data: &'a [u8], symbol_list: &'a SymbolList) -> Self {
+    pub(super) fn new(data: &'a [u8], symbols: impl Into<Symbols<'a>>) -> Self {
         Self {
             data,
-            symbol_list,
+            symbols: symbols.into(),
             consumed: 0,
             written: 0,
+            headroom: 0.0,
+            strict: false,
         }
     }
 }
@@ -303,7 +427,9 @@ impl<'a> Context<'a> {
 impl<'a> ContextInformation for Context<'a> {
     fn symbol_size_left(&self, extra_chars: usize) -> Option<usize> {
         let size_needed = self.written + extra_chars;
-        let symbol = self.symbol_list.first_symbol_big_enough_for(size_needed)?;
+        let symbol = self
+            .symbols
+            .first_symbol_with_headroom(size_needed, self.headroom)?;
         Some(symbol.num_data_codewords() - size_needed)
     }
 
@@ -315,6 +441,10 @@ impl<'a> ContextInformation for Context<'a> {
         self.data
     }
 
+    fn is_strict(&self) -> bool {
+        self.strict
+    }
+
     fn eat(&mut self) -> Option<u8> {
         if let Some((ch, rest)) = self.data.split_first() {
             self.data = rest;
@@ -329,7 +459,8 @@ impl<'a> ContextInformation for Context<'a> {
 #[test]
 fn test_add_switch_ascii() {
     let symbols = crate::SymbolList::default();
-    let mut plan = GenericPlan::for_mode(EncodationType::Ascii, b"[]ABC01", 0, &symbols);
+    let mut plan: GenericPlan =
+        GenericPlan::for_mode(EncodationType::Ascii, b"[]ABC01", 0, &symbols, 0.0, false);
     plan.step();
     plan.step();
     plan.step();
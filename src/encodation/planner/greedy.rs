@@ -0,0 +1,129 @@
+//! A greedy mode selection which only looks at the run of similar characters ahead.
+//!
+//! Runs of characters in the base set of C40 or Text and runs of bytes
+//! from the upper half are checked one after another. A run is encoded
+//! in the mode if this is smaller than ASCII, otherwise it is encoded in ASCII.
+use alloc::vec::Vec;
+use flagset::FlagSet;
+
+use crate::encodation::{ascii, c40, text, EncodationType};
+
+#[cfg(test)]
+use alloc::vec;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Compute a plan for `data` starting in ASCII, all characters can be encoded in ASCII.
+pub(super) fn plan(
+    data: &[u8],
+    enabled_modes: FlagSet<EncodationType>,
+    prefer_text: bool,
+) -> Vec<(usize, EncodationType)> {
+    let mut plan = Vec::new();
+    let mut mode = EncodationType::Ascii;
+    let mut rest = data;
+    while !rest.is_empty() {
+        let (next, mut len) = next_run(rest, enabled_modes, prefer_text);
+        if next.is_ascii() {
+            // a digit pair at the end of the run is encoded together
+            let mut ascii_len = 0;
+            while ascii_len < len {
+                ascii_len += if ascii::two_digits_coming(&rest[ascii_len..]) {
+                    2
+                } else {
+                    1
+                };
+            }
+            len = ascii_len;
+        }
+        if next != mode {
+            plan.push((rest.len(), next));
+            mode = next;
+        }
+        rest = &rest[len..];
+    }
+    plan.push((0, mode));
+    plan
+}
+
+/// Get the mode and the length of the run at the start of `rest`.
+fn next_run(
+    rest: &[u8],
+    enabled_modes: FlagSet<EncodationType>,
+    prefer_text: bool,
+) -> (EncodationType, usize) {
+    let run = |in_set: fn(u8) -> bool| rest.iter().take_while(|ch| in_set(**ch)).count();
+    let c40_like = |mode: EncodationType, run: usize| {
+        // C40 and Text can only be left after a complete triple
+        let len = if run == rest.len() {
+            run
+        } else {
+            run - run % 3
+        };
+        // latch, two codewords per triple and UNLATCH
+        let cost = 1 + len.div_ceil(3) * 2 + usize::from(len < rest.len());
+        (mode, run, len, cost)
+    };
+    let high_len = run(|ch| ch >= 128);
+    let candidates = [
+        c40_like(EncodationType::C40, run(c40::in_base_set)),
+        c40_like(EncodationType::Text, run(text::in_base_set)),
+        // latch, length and the bytes
+        (
+            EncodationType::Base256,
+            high_len,
+            high_len,
+            2 + high_len + usize::from(high_len > 249),
+        ),
+    ];
+    let mut best = None;
+    let mut best_saving = 0;
+    for (mode, _, len, cost) in candidates {
+        if len == 0 || !enabled_modes.contains(mode) {
+            continue;
+        }
+        let saving = ascii::encoding_size(&rest[..len]).saturating_sub(cost);
+        let tie_for_text = saving == best_saving && prefer_text && mode == EncodationType::Text;
+        if saving > best_saving || (saving > 0 && tie_for_text) {
+            best = Some((mode, len));
+            best_saving = saving;
+        }
+    }
+    // otherwise the longest run is encoded in ASCII, its tail is not better
+    best.unwrap_or_else(|| {
+        let len = candidates.iter().map(|(_, run, _, _)| *run).max().unwrap();
+        (EncodationType::Ascii, len.max(1))
+    })
+}
+
+#[test]
+fn test_plan() {
+    use EncodationType::*;
+
+    let all = EncodationType::all();
+    assert_eq!(plan(b"", all, false), vec![(0, Ascii)]);
+    assert_eq!(plan(b"1234567890", all, false), vec![(0, Ascii)]);
+    assert_eq!(plan(b"ABCDEFGHIJKL", all, false), vec![(12, C40), (0, C40)]);
+    assert_eq!(
+        plan(b"abcdefghijkl", all, false),
+        vec![(12, Text), (0, Text)]
+    );
+    assert_eq!(
+        plan(b"ABCDEFGHI.abcdefghi", all, false),
+        vec![(19, C40), (10, Ascii), (9, Text), (0, Text)]
+    );
+    // nine characters are needed to save a codeword
+    assert_eq!(plan(b"ABCDEFGH.", all, false), vec![(0, Ascii)]);
+    assert_eq!(
+        plan(b"\xe4\xe5\xe6.", all, false),
+        vec![(4, Base256), (1, Ascii), (0, Ascii)]
+    );
+    // ties between C40 and Text
+    assert_eq!(plan(b"ABCDEF GHIJKL", all, false)[0], (13, C40));
+    assert_eq!(plan(b"      ", all, true)[0], (6, Text));
+    // digit pairs are not split
+    assert_eq!(plan(b" c122BA C  D", all, false), vec![(0, Ascii)]);
+    // disabled modes are never used
+    assert_eq!(plan(b"ABCDEFGHIJKL", Ascii | Text, false), vec![(0, Ascii)]);
+}
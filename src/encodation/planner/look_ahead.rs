@@ -0,0 +1,237 @@
+//! The look-ahead test of the specification (ISO/IEC 16022, Annex P).
+//!
+//! For each mode the codewords needed for the following characters are
+//! counted, until one mode is clearly better. This is repeated at every
+//! position where the active mode could be left.
+use alloc::vec::Vec;
+use flagset::FlagSet;
+
+use super::Frac;
+use crate::encodation::{ascii, c40, edifact, text, x12, EncodationType};
+
+#[cfg(test)]
+use alloc::vec;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Count for modes which are not enabled, bigger than any real count.
+const DISABLED: u32 = 1 << 20;
+
+/// Compute a plan for `data` starting in ASCII, all characters can be encoded in ASCII.
+pub(super) fn plan(
+    data: &[u8],
+    enabled_modes: FlagSet<EncodationType>,
+) -> Vec<(usize, EncodationType)> {
+    let mut plan = Vec::new();
+    let mut mode = EncodationType::Ascii;
+    let mut rest = data;
+    while !rest.is_empty() {
+        let next = if mode.is_ascii() && ascii::two_digits_coming(rest) {
+            EncodationType::Ascii
+        } else {
+            look_ahead(rest, mode, enabled_modes)
+        };
+        let next = if can_encode(next, rest, enabled_modes) {
+            next
+        } else {
+            EncodationType::Ascii
+        };
+        if next != mode {
+            plan.push((rest.len(), next));
+            mode = next;
+        }
+        // characters until the mode can be left again
+        let len = match mode {
+            EncodationType::Ascii if ascii::two_digits_coming(rest) => 2,
+            EncodationType::Ascii | EncodationType::Base256 => 1,
+            EncodationType::C40 => values_len(rest, c40::val_size),
+            EncodationType::Text => values_len(rest, text::val_size),
+            EncodationType::X12 => 3,
+            EncodationType::Edifact => rest
+                .iter()
+                .take(4)
+                .take_while(|ch| edifact::is_encodable(**ch))
+                .count(),
+        };
+        rest = &rest[len..];
+    }
+    plan.push((0, mode));
+    plan
+}
+
+/// Can `mode` encode the characters up to the next possible switch?
+fn can_encode(mode: EncodationType, rest: &[u8], enabled_modes: FlagSet<EncodationType>) -> bool {
+    enabled_modes.contains(mode)
+        && match mode {
+            EncodationType::X12 => {
+                rest.len() >= 3 && rest[..3].iter().all(|ch| x12::is_native_x12(*ch))
+            }
+            EncodationType::Edifact => edifact::is_encodable(rest[0]),
+            _ => true,
+        }
+}
+
+/// Number of characters at the start of `rest` which fill complete C40 or Text triples.
+fn values_len(rest: &[u8], val_size: fn(u8) -> u8) -> usize {
+    let mut values = 0;
+    for (i, ch) in rest.iter().enumerate() {
+        values += val_size(*ch) as usize;
+        if values % 3 == 0 {
+            return i + 1;
+        }
+    }
+    rest.len()
+}
+
+/// Get the mode for the characters in `data` if `mode` is active.
+fn look_ahead(
+    data: &[u8],
+    mode: EncodationType,
+    enabled_modes: FlagSet<EncodationType>,
+) -> EncodationType {
+    use EncodationType::*;
+
+    // step J, a latch is needed to leave the active mode
+    let mut counts = [Frac::from(1); 6];
+    counts[Base256.index()] = Frac::new(5, 4);
+    if !mode.is_ascii() {
+        for count in counts.iter_mut() {
+            *count += 1;
+        }
+        counts[mode.index()] = Frac::from(0);
+    }
+    counts[Ascii.index()] = Frac::from(if mode.is_ascii() { 0 } else { 1 });
+    for disabled in !enabled_modes {
+        counts[disabled.index()] = Frac::from(DISABLED);
+    }
+    let add = |counts: &mut [Frac; 6], mode: EncodationType, num, denum| {
+        if enabled_modes.contains(mode) {
+            counts[mode.index()] += Frac::new(num, denum);
+        }
+    };
+
+    for (i, &ch) in data.iter().enumerate() {
+        let high = ch >= 128;
+        // step L
+        if ch.is_ascii_digit() {
+            add(&mut counts, Ascii, 1, 2);
+        } else {
+            counts[Ascii.index()] = counts[Ascii.index()].ceil();
+            add(&mut counts, Ascii, if high { 2 } else { 1 }, 1);
+        }
+        // steps M and N
+        for (mode, in_base_set) in [
+            (C40, c40::in_base_set as fn(u8) -> bool),
+            (Text, text::in_base_set),
+        ] {
+            match (in_base_set(ch), high) {
+                (true, _) => add(&mut counts, mode, 2, 3),
+                (false, true) => add(&mut counts, mode, 8, 3),
+                (false, false) => add(&mut counts, mode, 4, 3),
+            }
+        }
+        // step O
+        match (x12::is_native_x12(ch), high) {
+            (true, _) => add(&mut counts, X12, 2, 3),
+            (false, true) => add(&mut counts, X12, 13, 3),
+            (false, false) => add(&mut counts, X12, 10, 3),
+        }
+        // step P
+        match (edifact::is_encodable(ch), high) {
+            (true, _) => add(&mut counts, Edifact, 3, 4),
+            (false, true) => add(&mut counts, Edifact, 17, 4),
+            (false, false) => add(&mut counts, Edifact, 13, 4),
+        }
+        // step Q
+        add(&mut counts, Base256, 1, 1);
+
+        // step R, after at least four characters
+        if i >= 3 {
+            let ceil = counts.map(Frac::ceil);
+            let count = |mode: EncodationType| ceil[mode.index()];
+            let min_without =
+                |modes: &[EncodationType]| modes.iter().map(|mode| count(*mode)).min().unwrap();
+            if count(Ascii) < min_without(&[Base256, C40, Text, X12, Edifact]) {
+                return Ascii;
+            }
+            if count(Base256) < count(Ascii)
+                || count(Base256) + 1 < min_without(&[C40, Text, X12, Edifact])
+            {
+                return Base256;
+            }
+            if count(Edifact) + 1 < min_without(&[Base256, C40, Text, X12, Ascii]) {
+                return Edifact;
+            }
+            if count(Text) + 1 < min_without(&[Base256, C40, Edifact, X12, Ascii]) {
+                return Text;
+            }
+            if count(X12) + 1 < min_without(&[Base256, C40, Edifact, Text, Ascii]) {
+                return X12;
+            }
+            if count(C40) + 1 < min_without(&[Base256, Edifact, Text, Ascii]) {
+                if count(C40) < count(X12) {
+                    return C40;
+                }
+                if count(C40) == count(X12) {
+                    // X12 if a terminator comes before the next non-X12 character
+                    let terminator = data[i + 1..]
+                        .iter()
+                        .find(|ch| matches!(ch, 13 | b'*' | b'>') || !x12::is_native_x12(**ch));
+                    return match terminator {
+                        Some(13 | b'*' | b'>') => X12,
+                        _ => C40,
+                    };
+                }
+            }
+        }
+    }
+
+    // step K, end of data
+    let ceil = counts.map(Frac::ceil);
+    let min = *ceil.iter().min().unwrap();
+    if ceil[Ascii.index()] == min {
+        return Ascii;
+    }
+    if ceil.iter().filter(|count| **count == min).count() == 1 {
+        for mode in [Base256, Edifact, Text, X12] {
+            if ceil[mode.index()] == min {
+                return mode;
+            }
+        }
+    }
+    C40
+}
+
+#[test]
+fn test_look_ahead() {
+    use EncodationType::*;
+
+    let all = EncodationType::all();
+    assert_eq!(look_ahead(b"12345678", Ascii, all), Ascii);
+    assert_eq!(look_ahead(b"ABCDEFGHIJKL", Ascii, all), C40);
+    assert_eq!(look_ahead(b"abcdefghijkl", Ascii, all), Text);
+    assert_eq!(look_ahead(b"ABC>ABC>ABC>", Ascii, all), X12);
+    assert_eq!(look_ahead(b"A.B.C.D.E.F.", Ascii, all), Edifact);
+    assert_eq!(look_ahead(b"\xe4\xe5\xe6\xe7\xe8", Ascii, all), Base256);
+    // disabled modes are never chosen
+    assert_eq!(look_ahead(b"ABCDEFGHIJKL", Ascii, Ascii | Text), Ascii);
+    // the active mode is kept if it is not worse
+    assert_eq!(look_ahead(b"ABCDEFGHIJKL", Text, all), C40);
+    assert_eq!(look_ahead(b"AB12", C40, all), C40);
+}
+
+#[test]
+fn test_plan() {
+    use EncodationType::*;
+
+    let all = EncodationType::all();
+    assert_eq!(plan(b"", all), vec![(0, Ascii)]);
+    assert_eq!(plan(b"ABCDEFGHIJKL", all), vec![(12, C40), (0, C40)]);
+    assert_eq!(plan(b"1234ABCDEFGHIJKL", all), vec![(12, C40), (0, C40)]);
+    // X12 is only used for complete triples
+    assert_eq!(
+        plan(b"ABC>ABC>ABC>", Ascii | X12),
+        vec![(12, X12), (0, X12)]
+    );
+}
@@ -11,24 +11,144 @@ mod x12;
 
 mod frac;
 mod generic;
+mod greedy;
+mod look_ahead;
 mod shortest_path;
 use frac::Frac;
 
-pub(crate) use shortest_path::optimize;
+pub(crate) use shortest_path::{optimize, optimize_first};
+
+use alloc::vec::Vec;
+use flagset::FlagSet;
+
+use super::EncodationType;
+use crate::symbol_size::SymbolList;
+
+/// How much work the encoder spends on finding a small encodation.
+///
+/// See [DataMatrixBuilder::with_effort](crate::DataMatrixBuilder::with_effort).
+/// The faster levels are heuristics and only used if ASCII is enabled and
+/// the first character is not forced into another mode, otherwise the encodation
+/// is always minimal. Their result may need a bigger symbol.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Effort {
+    /// Choose the mode greedily by the run of similar characters ahead.
+    ///
+    /// A run of C40 or Text characters, or of bytes above 127, uses that mode
+    /// if it is smaller than ASCII. X12 and EDIFACT are not used. This takes
+    /// linear time.
+    Fast,
+    /// Use the look-ahead test of the specification (ISO/IEC 16022, Annex P).
+    ///
+    /// At each position where the mode can be changed, the following characters
+    /// are counted for each mode until one mode is clearly smaller. This is
+    /// quadratic in the worst case, but usually only a few characters are counted.
+    /// If more than half of the bytes are outside of printable ASCII, e.g., for
+    /// compressed data, a minimal encodation is used instead. It is restricted to ASCII
+    /// and Base256 unless the data contains a run of more than three printable characters.
+    Balanced,
+    /// Find a minimal encodation with all enabled modes.
+    ///
+    /// This is a shortest path search over the combinations of start and current mode,
+    /// linear in the input length, but with a large constant factor.
+    #[default]
+    Exhaustive,
+}
+
+/// Compute a plan for `data` with the given `effort`, see [optimize] for the arguments.
+///
+/// With [Effort::Fast] and [Effort::Balanced] the plan is returned even if the
+/// data does not fit. A `start` mode is always used for the first character, if it
+/// is not enabled no plan is returned.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn plan(
+    data: &[u8],
+    written: usize,
+    symbol_list: &SymbolList,
+    headroom: f32,
+    enabled_modes: FlagSet<EncodationType>,
+    strict: bool,
+    prefer_text: bool,
+    effort: Effort,
+    start: Option<EncodationType>,
+) -> Option<Vec<(usize, EncodationType)>> {
+    if start.is_some_and(|mode| !enabled_modes.contains(mode)) {
+        return None;
+    }
+    let mut modes = enabled_modes;
+    let heuristic =
+        enabled_modes.contains(EncodationType::Ascii) && start.is_none_or(|mode| mode.is_ascii());
+    match effort {
+        Effort::Fast if heuristic => return Some(greedy::plan(data, enabled_modes, prefer_text)),
+        Effort::Balanced if heuristic && is_binary(data) && !has_text_run(data) => {
+            // each of these modes can encode all bytes
+            modes = enabled_modes & (EncodationType::Ascii | EncodationType::Base256);
+        }
+        // the other modes are planned for longer runs of text
+        Effort::Balanced if heuristic && is_binary(data) => (),
+        Effort::Balanced if heuristic => return Some(look_ahead::plan(data, enabled_modes)),
+        _ => (),
+    }
+    optimize(
+        data,
+        written,
+        EncodationType::Ascii,
+        symbol_list,
+        headroom,
+        modes,
+        strict,
+        prefer_text,
+        start,
+    )
+}
+
+/// Percentage of bytes outside of printable ASCII above which data is binary.
+const BINARY_PERCENT: usize = 50;
+
+/// Longest run of printable ASCII in binary data.
+///
+/// No other mode saves a codeword compared to ASCII on a run of this length,
+/// even at the end of the data.
+const MAX_TEXT_RUN: usize = 3;
+
+/// Is `data` mostly binary?
+///
+/// C40 and Text need at least two values for such a byte, so they rarely
+/// beat Base256 then. Short runs of text are still encoded in ASCII.
+fn is_binary(data: &[u8]) -> bool {
+    let binary = data.iter().filter(|ch| !is_printable(**ch)).count();
+    binary * 100 > data.len() * BINARY_PERCENT
+}
+
+/// Does `data` contain a run of printable ASCII longer than [MAX_TEXT_RUN]?
+fn has_text_run(data: &[u8]) -> bool {
+    data.split(|ch| !is_printable(*ch))
+        .any(|run| run.len() > MAX_TEXT_RUN)
+}
+
+fn is_printable(ch: u8) -> bool {
+    (32..=126).contains(&ch)
+}
 
 trait ContextInformation: Clone {
     fn symbol_size_left(&self, extra_chars: usize) -> Option<usize>;
 
+    /// Get the input not consumed by [eat](Self::eat) yet, independent of the current mode.
     fn rest(&self) -> &[u8];
 
     fn eat(&mut self) -> Option<u8>;
 
     fn write(&mut self, bytes: usize);
 
+    /// Are the optional end of data rules with an implicit UNLATCH disabled?
+    fn is_strict(&self) -> bool;
+
     fn peek(&self, n: usize) -> Option<u8> {
         self.rest().get(n).copied()
     }
 
+    /// Number of characters not consumed yet, this is the length of [rest](Self::rest).
     fn characters_left(&self) -> usize {
         self.rest().len()
     }
@@ -1,14 +1,21 @@
+use arrayvec::ArrayVec;
 use flagset::FlagSet;
 
 use super::Plan;
-use crate::{encodation::encodation_type::EncodationType, symbol_size::SymbolList};
+use crate::{
+    encodation::encodation_type::EncodationType,
+    symbol_size::{SymbolList, Symbols},
+};
 
-use super::generic::GenericPlan;
+use super::generic::{FirstSwitches, GenericPlan, PlanList, Switches};
 
 #[cfg(test)]
 use alloc::vec;
 use alloc::vec::Vec;
 
+/// Maximal number of plans kept after a step, one per start and current mode.
+const MAX_PLANS: usize = 6 * 6;
+
 #[cfg(test)]
 use pretty_assertions::assert_eq;
 
@@ -21,32 +28,120 @@ use pretty_assertions::assert_eq;
 /// - `mode` is the currently active encodation mode.
 /// - `free_unlatch` is only used when `mode` is EDIFACT (state: three values written),
 /// - `size` is the symbol size
+/// - `headroom` is the fraction of the symbol's data codewords left unused,
+/// - `strict` disables the end of data rules with an implicit UNLATCH,
+/// - `prefer_text` decides ties between C40 and Text in favor of Text,
+/// - `start` forces the mode of the first character if given,
 /// - `base256_written` is only used when `mode` is Base256, it contains the
 ///   number data bytes written so far.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn optimize(
     data: &[u8],
     written: usize,
     mode: EncodationType,
     symbol_list: &SymbolList,
+    headroom: f32,
     enabled_modes: FlagSet<EncodationType>,
+    strict: bool,
+    prefer_text: bool,
+    start: Option<EncodationType>,
 ) -> Option<Vec<(usize, EncodationType)>> {
-    let start_plan = GenericPlan::for_mode(mode, data, written, symbol_list);
+    search::<_, Vec<_>, Vec<_>>(
+        data,
+        written,
+        mode,
+        symbol_list.into(),
+        headroom,
+        enabled_modes,
+        strict,
+        prefer_text,
+        start,
+    )
+}
+
+/// Like [optimize], but without allocation, only the first switches of the plan are returned.
+///
+/// Follow them and plan again from the last one with `start`. The plans are kept on
+/// the stack, a few dozen kilobytes.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn optimize_first(
+    data: &[u8],
+    written: usize,
+    mode: EncodationType,
+    symbols: Symbols,
+    headroom: f32,
+    enabled_modes: FlagSet<EncodationType>,
+    strict: bool,
+    prefer_text: bool,
+    start: Option<EncodationType>,
+) -> Option<ArrayVec<(usize, EncodationType), 3>> {
+    // every kept plan steps and adds the switches to the five other modes
+    search::<FirstSwitches, ArrayVec<_, MAX_PLANS>, ArrayVec<_, { MAX_PLANS * 6 }>>(
+        data,
+        written,
+        mode,
+        symbols,
+        headroom,
+        enabled_modes,
+        strict,
+        prefer_text,
+        start,
+    )
+    .map(FirstSwitches::into_inner)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn search<'a, S, P, N>(
+    data: &'a [u8],
+    written: usize,
+    mode: EncodationType,
+    symbols: Symbols<'a>,
+    headroom: f32,
+    enabled_modes: FlagSet<EncodationType>,
+    strict: bool,
+    prefer_text: bool,
+    start: Option<EncodationType>,
+) -> Option<S>
+where
+    S: Switches,
+    P: PlanList<GenericPlan<'a, S>>,
+    N: PlanList<GenericPlan<'a, S>>,
+{
+    let start_plan = GenericPlan::for_mode(mode, data, written, symbols, headroom, strict);
+    // `prefer_text` moves Text in front of C40 in the order of the modes
+    let rank = |mode: EncodationType| match mode {
+        EncodationType::Text if prefer_text => 2 * EncodationType::C40.index() - 1,
+        _ => 2 * mode.index(),
+    };
 
-    let mut plans = Vec::with_capacity(36);
-    let mut new_plan = Vec::with_capacity(36);
+    let mut plans = P::default();
+    let mut new_plan = N::default();
 
-    if enabled_modes.contains(mode) {
+    // modes allowed for the first character
+    let first_modes = start.map_or(enabled_modes, |start| enabled_modes & start);
+    if first_modes.is_empty() {
+        return None;
+    }
+    let first_iteration = if first_modes.contains(mode) {
         plans.push(start_plan);
+        0
     } else {
-        start_plan.add_switches(&mut plans, data.len(), true, enabled_modes);
-    }
+        // add_switches already steps the new plans over the first character
+        start_plan.add_switches(&mut plans, data.len(), true, first_modes);
+        data.len().min(1)
+    };
 
-    for iteration in 0usize.. {
+    for iteration in first_iteration.. {
         let mut at_end = false;
         let use_as_start = iteration == 0;
+        let switch_modes = if use_as_start {
+            first_modes
+        } else {
+            enabled_modes
+        };
 
         let rest_chars = data.len() - iteration;
-        for mut plan in plans.drain(0..) {
+        for mut plan in plans.drain_all() {
             let plan_copy_before_step = plan.clone();
             let result = if let Some(result) = plan.step() {
                 result
@@ -55,7 +150,7 @@ pub(crate) fn optimize(
                     &mut new_plan,
                     rest_chars, // chars left
                     use_as_start,
-                    enabled_modes,
+                    switch_modes,
                 );
                 // remove plan, it can not process input
                 continue;
@@ -70,7 +165,7 @@ pub(crate) fn optimize(
                     &mut new_plan,
                     rest_chars,
                     use_as_start,
-                    enabled_modes,
+                    switch_modes,
                 );
             }
             if result.end {
@@ -90,29 +185,38 @@ pub(crate) fn optimize(
         if at_end {
             // all plans are at the end of data, pick the best one
             let mut plan = new_plan
-                .into_iter()
-                .min_by_key(|p| {
+                .drain_all()
+                .min_by(|a, b| {
                     // To decide a tie we use the ordering given by ".index()"
-                    let max_enc = p.switches.iter().map(|e| e.1.index()).max().unwrap();
-                    (p.cost().ceil(), max_enc, p.switches.len())
+                    let key = |p: &GenericPlan<S>| {
+                        let switches = p.switches.as_slice();
+                        let max_enc = switches.iter().map(|e| rank(e.1)).max().unwrap();
+                        (p.cost().ceil(), max_enc, p.switches.count())
+                    };
+                    // then compare the switches, so the result does not depend
+                    // on the order of the plans
+                    let switches_a = a.switches.as_slice().iter().map(|e| (rank(e.1), e.0));
+                    let switches_b = b.switches.as_slice().iter().map(|e| (rank(e.1), e.0));
+                    key(a).cmp(&key(b)).then_with(|| switches_a.cmp(switches_b))
                 })
                 .unwrap();
-            plan.switches.push((0, plan.current()));
+            let current = plan.current();
+            plan.switches.push((0, current));
 
             // Remove a "switch" to ASCII if we are at the very beginning
-            if written == 0 && plan.switches[0] == (data.len(), EncodationType::Ascii) {
-                plan.switches.remove(0);
+            if written == 0 && plan.switches.as_slice()[0] == (data.len(), EncodationType::Ascii) {
+                plan.switches.remove_first();
             }
 
             return Some(plan.switches);
         }
-        core::mem::swap(&mut plans, &mut new_plan);
+        plans.extend(new_plan.drain_all());
     }
     unreachable!()
 }
 
 // Only keep one minimizer for every start mode.
-fn remove_hopeless_cases(list: &mut Vec<GenericPlan>) {
+fn remove_hopeless_cases<'a, S: Switches>(list: &mut impl PlanList<GenericPlan<'a, S>>) {
     list.sort_unstable_by_key(Plan::cost);
 
     // only keep min among all plans with tuple (start mode, current mode)
@@ -161,12 +265,13 @@ fn remove_hopeless_cases(list: &mut Vec<GenericPlan>) {
 #[test]
 fn test_hopeless_remove_duplicates() {
     let symbols = crate::SymbolList::default();
-    let mut a = GenericPlan::for_mode(EncodationType::Ascii, &[1, 2, 3], 0, &symbols);
+    let mut a: GenericPlan =
+        GenericPlan::for_mode(EncodationType::Ascii, &[1, 2, 3], 0, &symbols, 0.0, false);
     a.step(); // cost = 1
-    let mut b = GenericPlan::for_mode(EncodationType::C40, &[b'A', b'C', b'D'], 0, &symbols);
+    let mut b = GenericPlan::for_mode(EncodationType::C40, b"ACD", 0, &symbols, 0.0, false);
     b.step();
     b.step(); // cost = 4/3
-    let mut c = GenericPlan::for_mode(EncodationType::X12, &[b'A', b'C', b'D'], 0, &symbols);
+    let mut c = GenericPlan::for_mode(EncodationType::X12, b"ACD", 0, &symbols, 0.0, false);
     c.step();
     c.step(); // cost = 4/3
     let mut list = vec![a.clone(), b.clone(), c.clone()];
@@ -177,8 +282,9 @@ fn test_hopeless_remove_duplicates() {
 #[test]
 fn test_hopeless_remove_1() {
     let symbols = crate::SymbolList::default();
-    let a = GenericPlan::for_mode(EncodationType::Ascii, &[1, 2, 3], 0, &symbols);
-    let mut b = GenericPlan::for_mode(EncodationType::C40, &[b'A', b'C', b'D'], 0, &symbols);
+    let a: GenericPlan =
+        GenericPlan::for_mode(EncodationType::Ascii, &[1, 2, 3], 0, &symbols, 0.0, false);
+    let mut b = GenericPlan::for_mode(EncodationType::C40, b"ACD", 0, &symbols, 0.0, false);
     b.step();
     b.step();
     b.step();
@@ -190,10 +296,11 @@ fn test_hopeless_remove_1() {
 #[test]
 fn test_hopeless_remove_2() {
     let symbols = SymbolList::default();
-    let mut a = GenericPlan::for_mode(EncodationType::Ascii, &[1, 2, 3], 0, &symbols);
+    let mut a: GenericPlan =
+        GenericPlan::for_mode(EncodationType::Ascii, &[1, 2, 3], 0, &symbols, 0.0, false);
     a.step();
     a.step();
-    let mut c = GenericPlan::for_mode(EncodationType::C40, b"ABCDEFGH", 0, &symbols);
+    let mut c = GenericPlan::for_mode(EncodationType::C40, b"ABCDEFGH", 0, &symbols, 0.0, false);
     c.step(); // not a boundary, will not compare, so kept
     let mut list = vec![a.clone(), c.clone()];
     remove_hopeless_cases(&mut list);
@@ -207,7 +314,11 @@ fn test_ascii_case1() {
         0,
         EncodationType::Ascii,
         &SymbolList::default(),
+        0.0,
         EncodationType::all(),
+        false,
+        false,
+        None,
     );
     assert_eq!(result.map(|v| v[0].1), Some(EncodationType::Ascii));
 }
@@ -220,7 +331,11 @@ fn test_x12_case1() {
         0,
         EncodationType::X12,
         &SymbolList::default(),
+        0.0,
         EncodationType::all(),
+        false,
+        false,
+        None,
     );
     assert_eq!(result.map(|v| v[0].1), Some(EncodationType::X12));
 }
@@ -232,7 +347,11 @@ fn test_x12_case2() {
         3,
         EncodationType::X12,
         &SymbolList::default(),
+        0.0,
         EncodationType::all(),
+        false,
+        false,
+        None,
     );
     assert_eq!(result.map(|v| v[0].1), Some(EncodationType::X12));
 }
@@ -246,7 +365,11 @@ fn test_x12_case3() {
         0,
         EncodationType::Ascii,
         &SymbolList::default(),
+        0.0,
         EncodationType::all(),
+        false,
+        false,
+        None,
     );
     assert_eq!(result.map(|v| v[0].1), Some(EncodationType::X12));
 }
@@ -258,7 +381,11 @@ fn test_edifact_case1() {
         42,
         EncodationType::Edifact,
         &SymbolList::default(),
+        0.0,
         EncodationType::all(),
+        false,
+        false,
+        None,
     );
     assert_eq!(result.map(|v| v[0].1), Some(EncodationType::Edifact));
 }
@@ -271,7 +398,11 @@ fn test_edifact_case2() {
         971,
         EncodationType::Edifact,
         &SymbolList::default(),
+        0.0,
         EncodationType::all(),
+        false,
+        false,
+        None,
     );
     assert!(result.is_some());
 }
@@ -283,7 +414,154 @@ fn test_x12_case4() {
         11,
         EncodationType::X12,
         &SymbolList::default(),
+        0.0,
         EncodationType::all(),
+        false,
+        false,
+        None,
     );
     assert_eq!(result.map(|v| v[0].1), Some(EncodationType::X12));
 }
+
+#[test]
+fn test_start_mode_disabled() {
+    // the switch to X12 must happen after "\xaad", not one character later
+    let result = optimize(
+        b"\xaad ASCII 123",
+        0,
+        EncodationType::Ascii,
+        &SymbolList::default(),
+        0.0,
+        EncodationType::Base256 | EncodationType::X12,
+        false,
+        false,
+        None,
+    );
+    assert_eq!(
+        result,
+        Some(vec![
+            (12, EncodationType::Base256),
+            (10, EncodationType::X12),
+            (0, EncodationType::X12),
+        ])
+    );
+}
+
+#[test]
+fn test_tie_break() {
+    // C40 and X12 need the same number of codewords, C40 is preferred
+    for data in [&b"ABCDEFGHIJKL"[..], b"ABC DEF GHI JKL "] {
+        let result = optimize(
+            data,
+            0,
+            EncodationType::Ascii,
+            &SymbolList::default(),
+            0.0,
+            EncodationType::all(),
+            false,
+            false,
+            None,
+        );
+        let expected = vec![(data.len(), EncodationType::C40), (0, EncodationType::C40)];
+        assert_eq!(result, Some(expected), "{:?}", data);
+    }
+}
+
+#[test]
+fn test_disabled_start_mode() {
+    let result = optimize(
+        b"ABCDEF",
+        0,
+        EncodationType::Ascii,
+        &SymbolList::default(),
+        0.0,
+        EncodationType::Ascii | EncodationType::C40,
+        false,
+        false,
+        Some(EncodationType::Base256),
+    );
+    assert_eq!(result, None);
+}
+
+#[test]
+fn test_edifact_tail_unlatch() {
+    use crate::data::encode_data;
+    use crate::symbol_size::SymbolSize;
+
+    // Without the UNLATCH EDIFACT looks cheaper: 1 + 5 * 3/4 < 5.
+    // But the fifth value needs an UNLATCH, so the total would be 6.
+    let data = b"abc.-/+?";
+    let tail = &data[3..];
+    let symbols = SymbolSize::Square16.into();
+    let (cw, _) = encode_data(tail, &symbols, None, EncodationType::Edifact, false).unwrap();
+    assert_eq!(cw[6], crate::encodation::ascii::PAD);
+    for strict in [false, true] {
+        let result = optimize(
+            tail,
+            3,
+            EncodationType::Ascii,
+            &symbols,
+            0.0,
+            EncodationType::all(),
+            strict,
+            false,
+            None,
+        );
+        assert_eq!(
+            result,
+            Some(vec![(5, EncodationType::Ascii), (0, EncodationType::Ascii)])
+        );
+    }
+    let (cw, _) = encode_data(data, &symbols, None, EncodationType::all(), false).unwrap();
+    assert_eq!(
+        cw[..9],
+        [
+            b'a' + 1,
+            b'b' + 1,
+            b'c' + 1,
+            b'.' + 1,
+            b'-' + 1,
+            b'/' + 1,
+            b'+' + 1,
+            b'?' + 1,
+            crate::encodation::ascii::PAD
+        ]
+    );
+}
+
+#[test]
+fn test_upper_shift_or_base256() {
+    let plan = |data: &[u8]| {
+        optimize(
+            data,
+            0,
+            EncodationType::Ascii,
+            &SymbolList::default(),
+            0.0,
+            EncodationType::all(),
+            false,
+            false,
+            None,
+        )
+        .unwrap()
+    };
+    // a single high byte costs 2 codewords with an upper shift,
+    // Base256 needs a latch and a length byte in addition
+    assert_eq!(plan(b"\xe4"), vec![(0, EncodationType::Ascii)]);
+    assert_eq!(plan(b"abcd\xe4efgh"), vec![(0, EncodationType::Ascii)]);
+
+    // a run of 10 high bytes costs 12 instead of 20 codewords in Base256
+    let mut data = b"abcd".to_vec();
+    data.extend_from_slice(&[0xe4; 10]);
+    data.extend_from_slice(b"efgh");
+    let switches = plan(&data);
+    assert!(
+        switches.contains(&(0, EncodationType::Base256)),
+        "{:?}",
+        switches
+    );
+    assert_eq!(
+        plan(&[0xe4; 10]),
+        vec![(10, EncodationType::Base256), (0, EncodationType::Base256)]
+    );
+}
@@ -59,7 +59,7 @@ impl<T: ContextInformation> Plan for X12Plan<T> {
             if self.values == 0 && self.ctx.characters_left() <= 2 && self.ascii_end.is_none() {
                 // are we in a possible end of data situation?
                 let ascii_size = ascii::encoding_size(self.ctx.rest());
-                if ascii_size == 1 {
+                if ascii_size == 1 && !self.ctx.is_strict() {
                     let space_left = self.ctx.symbol_size_left(ascii_size)?;
                     if space_left <= 1 {
                         if space_left == 1 {
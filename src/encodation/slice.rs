@@ -0,0 +1,292 @@
+//! Encoder which writes into a buffer of the caller, without allocation.
+use arrayvec::ArrayVec;
+use flagset::FlagSet;
+
+use super::{
+    ascii, planner, DataEncodingError, EncodationType, EncodingContext, Randomize253, Randomizer,
+    UNLATCH,
+};
+use crate::symbol_size::{SymbolSize, Symbols};
+
+/// Like [GenericDataEncoder](super::GenericDataEncoder), but the codewords are
+/// written into a slice.
+///
+/// The planner only returns the first switches of a plan, see
+/// [optimize_first](planner::optimize_first). When they are used up the rest
+/// of the data is planned again, starting with the mode of the last switch.
+pub(crate) struct SliceEncoder<'a> {
+    data: &'a [u8],
+    input: &'a [u8],
+    encodation: EncodationType,
+    symbols: Symbols<'a>,
+    planned_switches: ArrayVec<(usize, EncodationType), 3>,
+    /// Mode of the last planned switch, the rest is planned when it starts.
+    replan: Option<EncodationType>,
+    new_mode: Option<u8>,
+    codewords: &'a mut [u8],
+    len: usize,
+    /// Set if a codeword did not fit into `codewords`.
+    overflow: bool,
+    enabled_modes: FlagSet<EncodationType>,
+    strict: bool,
+    prefer_text: bool,
+    headroom: f32,
+    /// Minimal number of data codewords of the symbol, see [EncodingContext::require_codewords].
+    min_codewords: usize,
+    num_unpadded: usize,
+    randomizer: &'a dyn Randomizer,
+}
+
+impl<'a> EncodingContext for SliceEncoder<'a> {
+    fn maybe_switch_mode(&mut self) -> Result<bool, DataEncodingError> {
+        let chars_left = self.characters_left();
+        let Some(&(switch_at, mode)) = self.planned_switches.first() else {
+            return Ok(false);
+        };
+        if chars_left < switch_at {
+            return Err(DataEncodingError::InternalError(
+                "the plan switches at a position where this is not possible",
+            ));
+        }
+        if chars_left == 0 || chars_left != switch_at {
+            return Ok(false);
+        }
+        self.planned_switches.remove(0);
+        if self.planned_switches.is_empty() {
+            self.replan = Some(mode);
+        }
+        let switch = mode != self.encodation;
+        if switch {
+            self.encodation = mode;
+            if !mode.is_ascii() {
+                self.new_mode = Some(mode.latch_from_ascii()?);
+            }
+        }
+        Ok(switch)
+    }
+
+    fn symbol_size_left(&mut self, extra_codewords: usize) -> Option<usize> {
+        let size_used = self.len + extra_codewords;
+        let symbol = self.symbol_for(extra_codewords)?;
+        Some(symbol.num_data_codewords() - size_used)
+    }
+
+    fn eat(&mut self) -> Option<u8> {
+        let (ch, rest) = self.data.split_first()?;
+        self.data = rest;
+        Some(*ch)
+    }
+
+    fn backup(&mut self, steps: usize) {
+        let eaten = self.input.len() - self.data.len();
+        debug_assert!(
+            steps <= eaten,
+            "backup of {} but only {} eaten",
+            steps,
+            eaten
+        );
+        self.data = &self.input[eaten - steps..];
+    }
+
+    fn rest(&self) -> &[u8] {
+        self.data
+    }
+
+    fn push(&mut self, ch: u8) {
+        match self.codewords.get_mut(self.len) {
+            Some(codeword) => {
+                *codeword = ch;
+                self.len += 1;
+            }
+            None => self.overflow = true,
+        }
+    }
+
+    fn codewords(&self) -> &[u8] {
+        &self.codewords[..self.len]
+    }
+
+    fn replace(&mut self, index: usize, ch: u8) {
+        self.codewords[index] = ch;
+    }
+
+    fn insert(&mut self, index: usize, ch: u8) {
+        if self.len == self.codewords.len() {
+            self.overflow = true;
+            return;
+        }
+        self.codewords.copy_within(index..self.len, index + 1);
+        self.codewords[index] = ch;
+        self.len += 1;
+    }
+
+    fn set_ascii_until_end(&mut self) {
+        self.encodation = EncodationType::Ascii;
+        self.planned_switches.clear();
+        self.planned_switches.push((0, EncodationType::Ascii));
+        self.replan = None;
+    }
+
+    fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    fn require_codewords(&mut self, num: usize) {
+        self.min_codewords = self.min_codewords.max(num);
+    }
+
+    fn is_gs1(&self) -> bool {
+        false
+    }
+}
+
+impl<'a> SliceEncoder<'a> {
+    /// Create an encoder for `data` which writes the codewords into `out`.
+    ///
+    /// `out` must hold the data codewords of all `symbols`.
+    pub fn new(
+        data: &'a [u8],
+        symbols: Symbols<'a>,
+        enabled_modes: FlagSet<EncodationType>,
+        out: &'a mut [u8],
+    ) -> Self {
+        Self {
+            data,
+            input: data,
+            encodation: EncodationType::Ascii,
+            symbols,
+            planned_switches: ArrayVec::new(),
+            replan: None,
+            new_mode: None,
+            codewords: out,
+            len: 0,
+            overflow: false,
+            enabled_modes,
+            strict: false,
+            prefer_text: false,
+            headroom: 0.0,
+            min_codewords: 0,
+            num_unpadded: 0,
+            randomizer: &Randomize253,
+        }
+    }
+
+    #[cfg(feature = "heapless")]
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    #[cfg(feature = "heapless")]
+    pub fn set_prefer_text(&mut self, prefer_text: bool) {
+        self.prefer_text = prefer_text;
+    }
+
+    #[cfg(feature = "heapless")]
+    pub fn set_headroom(&mut self, headroom: f32) {
+        self.headroom = headroom;
+    }
+
+    #[cfg(feature = "heapless")]
+    pub fn set_randomizer(&mut self, randomizer: &'a dyn Randomizer) {
+        self.randomizer = randomizer;
+    }
+
+    /// Encode the data and add the padding, return the picked symbol size.
+    ///
+    /// The data codewords are at the start of the buffer given to [new](Self::new).
+    pub fn encode(&mut self) -> Result<SymbolSize, DataEncodingError> {
+        self.planned_switches = self
+            .plan(None)
+            .ok_or(DataEncodingError::TooMuchOrIllegalData)?;
+        let mut no_write_run = 0;
+        // the buffer holds the data codewords of the biggest symbol, so an
+        // overflow means the data does not fit
+        while self.has_more_characters() && !self.overflow {
+            if let Some(mode) = self.replan.take() {
+                // the plan is optimal for the rest, so this finds an equally long one
+                self.planned_switches = self
+                    .plan(Some(mode))
+                    .ok_or(DataEncodingError::InternalError("planning the rest failed"))?;
+                // the plan starts with the switch to `mode`, which was done already
+                self.planned_switches.remove(0);
+            }
+            if let Some(new_mode) = self.new_mode.take() {
+                self.push(new_mode);
+            }
+            let len = self.len;
+
+            self.encodation.clone().encode(self)?;
+
+            if self.len - len <= 1 {
+                // no mode can do something useful in 1 word (at EOD, but that is fine)
+                no_write_run += 1;
+                if no_write_run > 5 {
+                    return Err(DataEncodingError::InternalError("no progress in encoder"));
+                }
+            } else {
+                no_write_run = 0;
+            }
+        }
+        if self.overflow {
+            return Err(DataEncodingError::TooMuchOrIllegalData);
+        }
+        let symbol_size = self
+            .symbol_for(0)
+            .ok_or(DataEncodingError::TooMuchOrIllegalData)?;
+        self.num_unpadded = self.len;
+        self.add_padding(symbol_size);
+        if self.overflow {
+            return Err(DataEncodingError::InternalError(
+                "codewords exceed the buffer",
+            ));
+        }
+        Ok(symbol_size)
+    }
+
+    /// Get the number of codewords before the padding from the last call of [encode](Self::encode).
+    #[cfg(feature = "heapless")]
+    pub fn num_unpadded_codewords(&self) -> usize {
+        self.num_unpadded
+    }
+
+    /// Plan the rest of the data from ASCII mode, the first character is encoded with `start`.
+    fn plan(&self, start: Option<EncodationType>) -> Option<ArrayVec<(usize, EncodationType), 3>> {
+        planner::optimize_first(
+            self.data,
+            self.len,
+            EncodationType::Ascii,
+            self.symbols,
+            self.headroom,
+            self.enabled_modes,
+            self.strict,
+            self.prefer_text,
+            start,
+        )
+    }
+
+    fn symbol_for(&self, extra_codewords: usize) -> Option<SymbolSize> {
+        let size_needed = (self.len + extra_codewords).max(self.min_codewords);
+        self.symbols
+            .first_symbol_with_headroom(size_needed, self.headroom)
+    }
+
+    fn add_padding(&mut self, size: SymbolSize) {
+        let mut size_left = size.num_data_codewords() - self.len;
+        if size_left == 0 {
+            return;
+        }
+        if self.encodation != EncodationType::Ascii {
+            self.encodation = EncodationType::Ascii;
+            self.push(UNLATCH);
+            size_left -= 1;
+        }
+        if size_left > 0 {
+            self.push(ascii::PAD);
+            size_left -= 1;
+        }
+        for _ in 0..size_left {
+            let pos = self.len + 1;
+            self.push(self.randomizer.pad(pos));
+        }
+    }
+}
@@ -2,7 +2,7 @@ use alloc::{vec, vec::Vec};
 
 use flagset::FlagSet;
 
-use super::{encodation_type::EncodationType, DataEncodingError, EncodingContext};
+use super::{encodation_type::EncodationType, Constraint, DataEncodingError, EncodingContext};
 use crate::data::encode_data;
 use crate::symbol_size::SymbolList;
 
@@ -86,6 +86,18 @@ impl EncodingContext for TestEncodingContext {
         self.mode = EncodationType::Ascii;
     }
 
+    fn is_strict(&self) -> bool {
+        false
+    }
+
+    fn require_codewords(&mut self, _num: usize) {
+        // the size is fixed
+    }
+
+    fn is_gs1(&self) -> bool {
+        false
+    }
+
     fn codewords(&self) -> &[u8] {
         &self.codewords
     }
@@ -310,6 +322,59 @@ fn test_x12_5() {
     );
 }
 
+#[test]
+fn test_x12_tails() {
+    use super::{ascii, UNLATCH};
+    use crate::{DataMatrixBuilder, SymbolSize};
+
+    let chars = b"AB\r>*C 12";
+    for len in 1..=14 {
+        let data: Vec<u8> = chars.iter().copied().cycle().take(len).collect();
+        let rest = &data[len / 3 * 3..];
+        for symbols in [SymbolList::default(), SymbolList::all().enforce_square()] {
+            let code = DataMatrixBuilder::new()
+                .with_symbol_list(symbols)
+                .with_encodation_plan(vec![(len, EncodationType::X12), (0, EncodationType::X12)])
+                .encode(&data)
+                .unwrap();
+            let cw = code.data_codewords();
+            assert_eq!(cw[0], 238);
+            // only full triples, then ASCII
+            let tail = &cw[1 + len / 3 * 2..];
+            let ascii_size = ascii::encoding_size(rest);
+            if tail.len() == ascii_size && ascii_size == 1 {
+                // 5.2.7.2, single ASCII codeword in the last position
+                assert!(!rest.is_empty());
+            } else if !rest.is_empty() {
+                assert_eq!(tail[0], UNLATCH);
+                assert!(tail[1 + ascii_size..].iter().all(|ch| *ch != UNLATCH));
+            } else if !tail.is_empty() {
+                assert_eq!(tail[0], UNLATCH);
+                assert!(tail.len() == 1 || tail[1] == ascii::PAD);
+            }
+            assert_eq!(crate::data::decode_data(cw).unwrap(), data, "{}", len);
+        }
+    }
+    // two characters at the end of the symbol still need the UNLATCH
+    let plan = vec![(5, EncodationType::X12), (0, EncodationType::X12)];
+    let builder = DataMatrixBuilder::new().with_encodation_plan(plan);
+    assert_eq!(
+        builder
+            .clone()
+            .with_symbol_list(SymbolSize::Square10)
+            .encode(b"ABCDE"),
+        Err(DataEncodingError::TooMuchOrIllegalData)
+    );
+    let code = builder
+        .with_symbol_list(SymbolSize::Square14)
+        .encode(b"ABCDE")
+        .unwrap();
+    assert_eq!(
+        &code.data_codewords()[..7],
+        &[238, 89, 233, UNLATCH, 69, 70, ascii::PAD]
+    );
+}
+
 #[test]
 fn test_edifact_1() {
     // 240 shifts to EDIFACT encodation
@@ -387,6 +452,42 @@ fn test_edifact_8() {
     );
 }
 
+#[test]
+fn test_edifact_padding() {
+    use crate::SymbolSize;
+
+    let encode = |data: &[u8]| {
+        encode_data(
+            data,
+            &SymbolSize::Square20.into(),
+            None,
+            EncodationType::Edifact,
+            false,
+        )
+        .unwrap()
+    };
+    // UNLATCH alone, then PAD and the 253-state padding, no ASCII UNLATCH
+    let (cw, size) = encode(b".A.C1.3.");
+    assert_eq!(size, SymbolSize::Square20);
+    assert_eq!(
+        cw,
+        vec![
+            240, 184, 27, 131, 198, 236, 238, 124, 129, 101, 251, 147, 42, 192, 87, 237, 133, 28,
+            178, 73, 223, 118
+        ]
+    );
+    // UNLATCH packed with the last three values
+    let (cw, size) = encode(b".A.C1.3.X.X");
+    assert_eq!(size, SymbolSize::Square20);
+    assert_eq!(
+        cw,
+        vec![
+            240, 184, 27, 131, 198, 236, 238, 98, 230, 31, 129, 147, 42, 192, 87, 237, 133, 28,
+            178, 73, 223, 118
+        ]
+    );
+}
+
 #[cfg(test)]
 fn create_binary_test_message(len: usize) -> Vec<u8> {
     let mut vec = vec![171, 228, 246, 252, 233, 224, 225, 45];
@@ -494,6 +595,29 @@ fn test_base256_10() {
     assert_eq!(&words[words.len() - end.len()..], &end);
 }
 
+#[test]
+fn test_base256_mid_stream() {
+    // ASCII + Base256 + ASCII, the length only counts the Base256 segment
+    // and ASCII continues after it without an UNLATCH
+    let data = b"12345678\xff\xfe\xfd\xfc\xfb12345678";
+    let plan = crate::data::encodation_plan(data, &SymbolList::default(), EncodationType::all());
+    assert_eq!(
+        plan,
+        Some(vec![
+            (13, EncodationType::Base256),
+            (8, EncodationType::Ascii),
+            (0, EncodationType::Ascii),
+        ])
+    );
+    let words = enc(data);
+    assert_eq!(
+        &words[..15],
+        &[142, 164, 186, 208, 231, 135, 23, 171, 64, 212, 105, 142, 164, 186, 208]
+    );
+    assert_eq!(words[15], super::ascii::PAD);
+    assert_eq!(crate::data::decode_data(&words).unwrap(), data);
+}
+
 #[test]
 fn test_c40_unlatching() {
     assert_eq!(
@@ -606,6 +730,50 @@ fn test_only_edifact() {
     );
 }
 
+#[test]
+fn test_error_details() {
+    use crate::{DataMatrixBuilder, Encodation, SymbolSize};
+
+    let edifact =
+        DataMatrixBuilder::new().with_encodation(Encodation::Exclusive(EncodationType::Edifact));
+    let err = edifact.encode(b"RENE DUPONT \xe9").unwrap_err();
+    assert_eq!(
+        alloc::format!("{}", err),
+        "character '\u{e9}' at position 12 can not be encoded"
+    );
+    let err = DataMatrixBuilder::new()
+        .with_symbol_list(SymbolSize::Square12)
+        .encode(b"ABCDEFGH")
+        .unwrap_err();
+    assert_eq!(
+        err,
+        DataEncodingError::TooMuchData {
+            needed: 7,
+            available: 5,
+            constraint: Constraint::SymbolList
+        }
+    );
+    assert_eq!(
+        alloc::format!("{}", err),
+        "data needs about 7 codewords, but the largest allowed symbol has 5 \
+         (allow bigger symbol sizes)"
+    );
+    // C40 would fit
+    let err = DataMatrixBuilder::new()
+        .with_symbol_list(SymbolSize::Square14)
+        .with_encodation_types(EncodationType::Ascii)
+        .encode(b"ABCDEFGHI")
+        .unwrap_err();
+    assert_eq!(
+        err,
+        DataEncodingError::TooMuchData {
+            needed: 9,
+            available: 8,
+            constraint: Constraint::EncodationTypes
+        }
+    );
+}
+
 #[test]
 fn test_only_edifact_impossible() {
     let code = encode_data(
@@ -615,5 +783,731 @@ fn test_only_edifact_impossible() {
         EncodationType::Edifact,
         false,
     );
-    assert_eq!(code, Err(DataEncodingError::TooMuchOrIllegalData),);
+    assert_eq!(
+        code,
+        Err(DataEncodingError::IllegalCharacter {
+            position: 0,
+            character: 'a'
+        })
+    );
+}
+
+#[test]
+fn test_validate_header_legal() {
+    use super::validate_header;
+
+    for header in [
+        &[][..],
+        &[66, 67],
+        &[232, 66],
+        &[234, 66],
+        &[236, 66],
+        &[237, 241, 27, 66],
+        &[233, 0x12, 1, 1, 66],
+        &[233, 0x12, 1, 1, 232, 66],
+        &[233, 0x12, 1, 1, 241, 27],
+        &[241, 27, 232, 66],
+        &[241, 128, 1, 232, 66],
+        &[241, 192, 1, 1, 232, 66],
+        &[233, 0x12, 1, 1, 241, 27, 232, 66],
+        &[236, 241, 27, 66],
+    ] {
+        assert_eq!(validate_header(header), Ok(()), "{:?}", header);
+    }
+}
+
+#[test]
+fn test_validate_header_illegal() {
+    use super::validate_header;
+
+    for header in [
+        &[233, 0x12, 1][..],
+        &[233, 0x12, 1, 1, 234],
+        &[233, 0x12, 1, 1, 236],
+        &[233, 0x12, 1, 1, 232, 233],
+        &[232, 233],
+        &[234, 233],
+        &[236, 233],
+        &[232, 232],
+        &[232, 236],
+        &[237, 232],
+        &[232, 234],
+        &[234, 232],
+        &[234, 234],
+        &[234, 237],
+        &[236, 234],
+        &[236, 237],
+        &[241, 27, 232, 232],
+        &[241, 27, 232, 236],
+        &[241, 27, 234],
+        &[241, 128, 1, 237],
+        &[236, 241, 27, 232],
+        &[232, 241, 27, 232],
+    ] {
+        assert!(
+            matches!(
+                validate_header(header),
+                Err(DataEncodingError::InvalidOptions(_))
+            ),
+            "{:?}",
+            header
+        );
+    }
+}
+
+#[test]
+fn test_from_codewords_illegal_header() {
+    let mut codewords = vec![129; 12];
+    codewords[0] = 232;
+    codewords[1] = 236;
+    assert_eq!(
+        crate::DataMatrix::from_codewords(&codewords, crate::SymbolSize::Square16),
+        Err(DataEncodingError::InvalidOptions(
+            "FNC1 can not be used with macros"
+        ))
+    );
+}
+
+#[test]
+fn test_estimate_codewords() {
+    use crate::symbol_size::SymbolSize;
+
+    let cases: &[(EncodationType, &[u8])] = &[
+        (EncodationType::Ascii, b"Hello, World! 123"),
+        (EncodationType::Ascii, b"\xe4\xf6\xfc1"),
+        (EncodationType::C40, b"ABCDEF"),
+        (EncodationType::C40, b"ABCDEFG"),
+        (EncodationType::C40, b"ABCDEFGH"),
+        (EncodationType::C40, b"ABC12"),
+        (EncodationType::C40, b"AB12"),
+        (EncodationType::C40, b"AIMAIMa\xcb"),
+        (EncodationType::Text, b"hello world"),
+        (EncodationType::Text, b"abc12"),
+        (EncodationType::Text, b"some text ABC"),
+        (EncodationType::X12, b"ABC*>\r"),
+        (EncodationType::X12, b"ABC*>\rD"),
+        (EncodationType::X12, b"ABC*>\rDE"),
+        (EncodationType::X12, b"ABC12"),
+        (EncodationType::Edifact, b".A.C"),
+        (EncodationType::Edifact, b".A.C1"),
+        (EncodationType::Edifact, b".A.C12"),
+        (EncodationType::Edifact, b".A.C123"),
+        (EncodationType::Base256, b"\xab\xe4\xf6\xfc\xe9\xbb"),
+        (EncodationType::Base256, &[0xab; 300]),
+    ];
+    for (mode, data) in cases {
+        let estimate = mode.estimate_codewords(data).unwrap();
+        let (cw, _) = encode_data(data, &SymbolSize::Square144.into(), None, *mode, false).unwrap();
+        if !mode.is_ascii() {
+            assert_eq!(Ok(cw[0]), mode.latch_from_ascii());
+        }
+        assert_eq!(cw[estimate], super::ascii::PAD, "{:?} {:?}", mode, data);
+    }
+
+    // the capacity is not checked
+    assert_eq!(
+        EncodationType::C40.estimate_codewords(&[b'A'; 3200]),
+        Some(2136)
+    );
+    assert_eq!(
+        EncodationType::Base256.estimate_codewords(&[0; 1556]),
+        Some(1 + 2 + 1555 + 1 + 1 + 1)
+    );
+
+    // random data in every mode which can encode it
+    let mut rnd = crate::test::random_data();
+    for i in 0..300 {
+        let data: Vec<u8> = rnd(i % 40 + 1)
+            .iter()
+            .map(|b| b"ABC>*\r123 .-/abc\xe4"[(*b % 17) as usize])
+            .collect();
+        for mode in EncodationType::all() {
+            if let Some(estimate) = mode.estimate_codewords(&data) {
+                let (cw, _) =
+                    encode_data(&data, &SymbolSize::Square144.into(), None, mode, false).unwrap();
+                assert_eq!(cw[estimate], super::ascii::PAD, "{:?} {:?}", mode, data);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_latch_from_ascii() {
+    assert_eq!(
+        EncodationType::C40.latch_from_ascii(),
+        Ok(super::ascii::LATCH_C40)
+    );
+    assert!(matches!(
+        EncodationType::Ascii.latch_from_ascii(),
+        Err(DataEncodingError::InternalError(_))
+    ));
+}
+
+#[test]
+fn test_estimate_codewords_impossible() {
+    assert_eq!(EncodationType::X12.estimate_codewords(b"ABa"), None);
+    assert_eq!(EncodationType::Edifact.estimate_codewords(b"abc"), None);
+    assert_eq!(EncodationType::Edifact.estimate_codewords(b""), Some(0));
+}
+
+#[test]
+fn test_can_encode() {
+    use EncodationType::*;
+
+    let full = Ascii | C40 | Text | Base256;
+    assert_eq!(EncodationType::encodable_by(0x1f), full);
+    assert_eq!(EncodationType::encodable_by(0xe4), full);
+    assert_eq!(EncodationType::encodable_by(b'a'), full);
+    assert_eq!(EncodationType::encodable_by(b'\r'), full | X12);
+    assert_eq!(EncodationType::encodable_by(b'.'), full | Edifact);
+    assert_eq!(EncodationType::encodable_by(b'A'), EncodationType::all());
+
+    // consistent with the planner
+    for ch in 0..=255 {
+        for mode in EncodationType::all() {
+            let plan = crate::data::encodation_plan(&[ch; 6], &SymbolList::default(), mode);
+            assert_eq!(mode.can_encode(ch), plan.is_some(), "{:?} {}", mode, ch);
+        }
+    }
+}
+
+#[test]
+fn test_all_mode_combinations() {
+    use crate::{DataMatrix, DataMatrixBuilder};
+
+    let corpus: &[&[u8]] = &[
+        b"",
+        b"A",
+        b"0123456789",
+        b"HELLO WORLD 2024",
+        b"ABC*>\rDEF*>\rGHI",
+        b"Hello, World! How are you?",
+        b"EDIFACT: A.B/C-D+E?F:G'H",
+        b"lower case text only",
+        b"\x00\x01\x02\x1f tab\tcontrol",
+        b"\xe4\xf6\xfc\xdf Latin-1 \xa9\xae",
+        b"\xff\xfe\xfd\xfc\xfb\xfa\xf9\xf8\xf7\xf6 binary",
+    ];
+    for bits in 1..=EncodationType::all().bits() {
+        let modes = FlagSet::<EncodationType>::new(bits).unwrap();
+        for data in corpus {
+            let encodable = data.iter().all(|ch| {
+                modes.contains(EncodationType::Ascii)
+                    || modes.contains(EncodationType::C40)
+                    || modes.contains(EncodationType::Text)
+                    || modes.contains(EncodationType::Base256)
+                    || (modes.contains(EncodationType::X12) && super::x12::is_native_x12(*ch))
+                    || (modes.contains(EncodationType::Edifact)
+                        && super::edifact::is_encodable(*ch))
+            });
+            let result = DataMatrixBuilder::new()
+                .with_encodation_types(modes)
+                .with_macros(false)
+                .encode(data);
+            let code = match result {
+                Ok(code) => code,
+                Err(err) => {
+                    // X12 and EDIFACT may still finish some data with ASCII,
+                    // so we only know the error case for sure
+                    assert!(!encodable, "{:?} failed with {:?}", data, modes);
+                    let position = data
+                        .iter()
+                        .position(|ch| (EncodationType::encodable_by(*ch) & modes).is_empty())
+                        .unwrap();
+                    assert_eq!(
+                        err,
+                        DataEncodingError::IllegalCharacter {
+                            position,
+                            character: data[position] as char
+                        }
+                    );
+                    continue;
+                }
+            };
+            let bitmap = code.bitmap();
+            let decoded = DataMatrix::decode(bitmap.bits(), bitmap.width()).unwrap();
+            assert_eq!(&decoded, data, "{:?}", modes);
+        }
+    }
+
+    // binary input, but no mode to encode it
+    let result = DataMatrixBuilder::new()
+        .with_encodation_types(EncodationType::X12 | EncodationType::Edifact)
+        .encode(b"\xff\xfe\xfd\xfc\xfb\xfa");
+    assert_eq!(
+        result,
+        Err(DataEncodingError::IllegalCharacter {
+            position: 0,
+            character: 'ÿ'
+        })
+    );
+}
+
+#[test]
+fn test_binary_stays_optimal() {
+    use crate::symbol_size::SymbolSize;
+
+    let mut rnd = crate::test::random_bytes();
+    for len in [3, 10, 100, 249, 250, 251, 600] {
+        let data: Vec<u8> = (0..len).map(|_| rnd() | 0x80).collect();
+        let base256 = EncodationType::Base256.estimate_codewords(&data).unwrap();
+        let symbols = SymbolSize::Square144.into();
+        let (cw, _) = encode_data(&data, &symbols, None, EncodationType::all(), false).unwrap();
+        let (cw_base256, _) =
+            encode_data(&data, &symbols, None, EncodationType::Base256, false).unwrap();
+        assert_eq!(cw[base256], super::ascii::PAD);
+        assert_eq!(cw, cw_base256);
+    }
+
+    // a run of digits in binary data is still encoded with ASCII
+    let mut data: Vec<u8> = (0..20).map(|_| rnd() | 0x80).collect();
+    data.extend_from_slice(b"01234567890123456789");
+    data.extend((0..20).map(|_| rnd() | 0x80));
+    let plan = crate::data::encodation_plan(&data, &SymbolList::default(), EncodationType::all());
+    assert_eq!(
+        plan,
+        Some(vec![
+            (60, EncodationType::Base256),
+            (40, EncodationType::Ascii),
+            (20, EncodationType::Base256),
+            (0, EncodationType::Base256),
+        ])
+    );
+}
+
+#[test]
+fn test_strict_mode() {
+    use crate::{DataMatrix, DataMatrixBuilder};
+
+    let encode = |data: &[u8], strict: bool| {
+        let code = DataMatrixBuilder::new()
+            .with_strict(strict)
+            .encode(data)
+            .unwrap();
+        let bitmap = code.bitmap();
+        let decoded = DataMatrix::decode(bitmap.bits(), bitmap.width()).unwrap();
+        assert_eq!(&decoded, data, "strict {}", strict);
+        code.data_codewords().to_vec()
+    };
+
+    // C40 case d), last character in ASCII without UNLATCH
+    assert_eq!(
+        encode(b"    \x1d    ", false),
+        vec![230, 19, 60, 18, 222, 19, 60, 33]
+    );
+    assert_eq!(
+        encode(b"    \x1d    ", true),
+        vec![33, 33, 33, 33, 30, 33, 33, 33, 33, 129]
+    );
+    // C40, last two digits in ASCII without UNLATCH
+    assert_eq!(
+        encode(b" 9 aaabbb00", false),
+        vec![239, 20, 204, 89, 191, 96, 40, 130]
+    );
+    assert_eq!(
+        encode(b" 9 aaabbb00", true),
+        vec![239, 20, 204, 89, 191, 96, 40, 254, 130, 129]
+    );
+    // X12, last character in ASCII without UNLATCH
+    assert_eq!(
+        encode(b"ABCDE>ABCDE>A", false),
+        vec![238, 89, 233, 109, 19, 89, 233, 109, 19, 66]
+    );
+    assert_eq!(
+        encode(b"ABCDE>ABCDE>A", true),
+        vec![230, 89, 233, 109, 18, 121, 0, 102, 187, 9, 71, 254]
+    );
+
+    // EDIFACT, last character in ASCII without UNLATCH
+    let edifact = |data: &[u8], strict: bool| {
+        let code = DataMatrixBuilder::new()
+            .with_encodation_types(EncodationType::Edifact)
+            .with_strict(strict)
+            .encode(data)
+            .unwrap();
+        let bitmap = code.bitmap();
+        let decoded = DataMatrix::decode(bitmap.bits(), bitmap.width()).unwrap();
+        assert_eq!(&decoded, data, "strict {}", strict);
+        code.data_codewords().to_vec()
+    };
+    assert_eq!(
+        edifact(b".A.C1.3.X", false),
+        vec![240, 184, 27, 131, 198, 236, 238, 89]
+    );
+    assert_eq!(
+        edifact(b".A.C1.3.X", true),
+        vec![240, 184, 27, 131, 198, 236, 238, 97, 240, 129]
+    );
+
+    // strict encodings are never smaller
+    let chars = b" 0123ABCDE>*\rabc";
+    let mut rnd = crate::test::random_bytes();
+    for len in 1..100 {
+        let data: Vec<u8> = (0..len)
+            .map(|_| chars[rnd() as usize % chars.len()])
+            .collect();
+        assert!(encode(&data, true).len() >= encode(&data, false).len());
+        let data: Vec<u8> = data.iter().map(|ch| ch.to_ascii_uppercase()).collect();
+        if data.iter().all(|ch| (32..=94).contains(ch)) {
+            assert!(edifact(&data, true).len() >= edifact(&data, false).len());
+        }
+    }
+}
+
+#[test]
+fn test_given_plan() {
+    use crate::DataMatrixBuilder;
+
+    let encode = |data: &[u8], plan: &[(usize, EncodationType)]| {
+        DataMatrixBuilder::new()
+            .with_encodation_plan(plan.to_vec())
+            .encode(data)
+            .map(|code| code.data_codewords().to_vec())
+    };
+
+    // the alternative solution from test_c40_basic2_3
+    assert_eq!(
+        encode(
+            b"AIMAIMAIM\xcb",
+            &[
+                (10, EncodationType::C40),
+                (1, EncodationType::Ascii),
+                (0, EncodationType::Ascii)
+            ]
+        ),
+        Ok(vec![230, 91, 11, 91, 11, 91, 11, 254, 235, 76])
+    );
+    // the planner's result is reproduced
+    let data = b"A_2_D_5_G7H_9J_1L2";
+    let plan = crate::data::encodation_plan(data, &SymbolList::default(), EncodationType::all());
+    assert_eq!(encode(data, &plan.unwrap()), Ok(enc(data)));
+
+    // malformed plans
+    for plan in [
+        &[][..],
+        &[(3, EncodationType::C40)],
+        &[(7, EncodationType::C40), (0, EncodationType::C40)],
+        &[
+            (2, EncodationType::C40),
+            (2, EncodationType::Ascii),
+            (0, EncodationType::Ascii),
+        ],
+    ] {
+        assert!(
+            matches!(
+                encode(b"ABC", plan),
+                Err(DataEncodingError::InvalidOptions(_))
+            ),
+            "{:?}",
+            plan
+        );
+    }
+    // switches to the active mode are merged, even inside of a C40 triple
+    let c40 = [
+        (9, EncodationType::C40),
+        (7, EncodationType::C40),
+        (3, EncodationType::C40),
+        (0, EncodationType::C40),
+    ];
+    assert_eq!(
+        encode(b"AIMAIMAIM", &c40),
+        encode(b"AIMAIMAIM", &[c40[0], c40[3]])
+    );
+    assert_eq!(
+        encode(b"AIMAIMAIM", &c40).unwrap()[..7],
+        [230, 91, 11, 91, 11, 91, 11]
+    );
+    let joined = [
+        (12, EncodationType::Text),
+        (6, EncodationType::Text),
+        (3, EncodationType::Ascii),
+        (2, EncodationType::Ascii),
+        (0, EncodationType::Ascii),
+    ];
+    assert_eq!(
+        encode(b"abcdefghi123", &joined),
+        encode(b"abcdefghi123", &[joined[0], joined[2], joined[4]])
+    );
+    // switch inside of a X12 triple
+    assert!(matches!(
+        encode(
+            b"ABCDEFGH",
+            &[
+                (8, EncodationType::X12),
+                (4, EncodationType::Ascii),
+                (0, EncodationType::Ascii)
+            ]
+        ),
+        Err(DataEncodingError::InvalidOptions(_))
+    ));
+    // the modes can not encode the data
+    assert_eq!(
+        encode(
+            b"abcdef",
+            &[(6, EncodationType::X12), (0, EncodationType::X12)]
+        ),
+        Err(DataEncodingError::TooMuchOrIllegalData)
+    );
+    assert_eq!(
+        encode(
+            b"abcdef",
+            &[(6, EncodationType::Edifact), (0, EncodationType::Edifact)]
+        ),
+        Err(DataEncodingError::TooMuchOrIllegalData)
+    );
+}
+
+#[test]
+fn test_plan_is_minimal() {
+    use crate::DataMatrixBuilder;
+
+    // compare with all plans which assign a mode to each character
+    let modes = [
+        EncodationType::Ascii,
+        EncodationType::C40,
+        EncodationType::Text,
+        EncodationType::X12,
+        EncodationType::Edifact,
+        EncodationType::Base256,
+    ];
+    let builder = DataMatrixBuilder::new().with_macros(false);
+    for data in [&b"AB12*>"[..], b"a1B.c2", b"A.B-C/", b"\xe4b\r9Z "] {
+        let minimal = builder.clone().encode(data).unwrap().size;
+        let mut valid = 0;
+        for mut n in 0..modes.len().pow(data.len() as u32) {
+            let mut plan: Vec<(usize, EncodationType)> = Vec::new();
+            for left in (1..=data.len()).rev() {
+                let mode = modes[n % modes.len()];
+                n /= modes.len();
+                if plan.last().is_none_or(|(_, last)| *last != mode) {
+                    plan.push((left, mode));
+                }
+            }
+            plan.push((0, plan.last().unwrap().1));
+            if let Ok(code) = builder.clone().with_encodation_plan(plan).encode(data) {
+                valid += 1;
+                assert!(
+                    minimal.num_data_codewords() <= code.size.num_data_codewords(),
+                    "{:?}",
+                    data
+                );
+            }
+        }
+        assert!(valid > 1000);
+    }
+}
+
+#[test]
+fn test_edifact_long_tails() {
+    use crate::DataMatrixBuilder;
+
+    let head = b".-/+?:'=,;.-/+?:'=,;";
+    for tail in [&b"12345"[..], b"ABCDE", b"123456", b"ABCDEF", b"12ABC3"] {
+        // the end of data rule can not be used for five or more characters
+        assert!(super::ascii::encoding_size(tail) > 2);
+
+        let mut data = head.to_vec();
+        data.extend_from_slice(tail);
+        let size = |plan: Option<Vec<(usize, EncodationType)>>| {
+            let builder = DataMatrixBuilder::new();
+            let builder = match plan {
+                Some(plan) => builder.with_encodation_plan(plan),
+                None => builder,
+            };
+            builder.encode(&data).unwrap().size.num_data_codewords()
+        };
+        let best = size(None);
+        for split in 0..=tail.len() {
+            let mut plan = vec![(data.len(), EncodationType::Edifact)];
+            if split > 0 {
+                plan.push((split, EncodationType::Ascii));
+            }
+            plan.push((0, plan.last().unwrap().1));
+            assert!(best <= size(Some(plan)), "{:?}, split {}", tail, split);
+        }
+    }
+}
+
+#[test]
+fn test_no_empty_segments() {
+    use crate::data::{encodation_plan, encode_data};
+
+    let mut rnd = crate::test::random_bytes();
+    let alphabets: [&[u8]; 5] = [
+        b"AB01 *>\r",
+        b"ab01.-",
+        b"A1a.\xe4",
+        b"ABCabc123!@#\xe4\xe5",
+        b"0123456789A",
+    ];
+    let symbols = SymbolList::default();
+    for _ in 0..2000 {
+        let alphabet = alphabets[rnd() as usize % alphabets.len()];
+        let len = rnd() as usize % 40 + 1;
+        let data: Vec<u8> = (0..len)
+            .map(|_| alphabet[rnd() as usize % alphabet.len()])
+            .collect();
+        // every segment of the plan contains at least one character
+        let plan = encodation_plan(&data, &symbols, EncodationType::all()).unwrap();
+        let segments = &plan[..plan.len() - 1];
+        assert!(
+            segments.windows(2).all(|w| w[0].0 > w[1].0),
+            "{:?} {:?}",
+            data,
+            plan
+        );
+        // and no switch to the active mode
+        assert!(
+            plan.windows(2).all(|w| w[0].1 != w[1].1 || w[1].0 == 0),
+            "{:?} {:?}",
+            data,
+            plan
+        );
+        // so a latch is never directly followed by an UNLATCH
+        let (cw, _) = encode_data(&data, &symbols, None, EncodationType::all(), false).unwrap();
+        assert!(
+            !cw.windows(2)
+                .any(|w| matches!(w[0], 230 | 238 | 239) && w[1] == 254),
+            "{:?} {:?}",
+            data,
+            cw
+        );
+    }
+}
+
+#[test]
+fn test_slice_encoder_random() {
+    use super::SliceEncoder;
+
+    let mut rnd = crate::test::random_bytes();
+    let alphabets: [&[u8]; 4] = [
+        b"AB01 *>\r",
+        b"ab01.-@",
+        b"A1a.\xe4",
+        b"ABCabc123!@#\xe4\xe5",
+    ];
+    let symbols = SymbolList::default();
+    let mut out = [0; 1558];
+    for _ in 0..1000 {
+        let alphabet = alphabets[rnd() as usize % alphabets.len()];
+        let len = rnd() as usize % 300 + 1;
+        let data: Vec<u8> = (0..len)
+            .map(|_| alphabet[rnd() as usize % alphabet.len()])
+            .collect();
+        let mut modes = FlagSet::from(EncodationType::Ascii);
+        for mode in EncodationType::all() {
+            if rnd().is_multiple_of(2) {
+                modes |= mode;
+            }
+        }
+        // the plan is followed piecewise, but the result is the same
+        let mut encoder = SliceEncoder::new(&data, (&symbols).into(), modes, &mut out);
+        let result = encoder
+            .encode()
+            .map(|size| (out[..size.num_data_codewords()].to_vec(), size));
+        assert_eq!(
+            result,
+            encode_data(&data, &symbols, None, modes, false),
+            "{:?} {:?}",
+            data,
+            modes
+        );
+    }
+}
+
+// Examples worked by hand with the rules of ISO/IEC 16022, each in a fixed symbol.
+
+#[cfg(test)]
+fn enc_symbol(
+    data: &[u8],
+    size: crate::SymbolSize,
+    enabled_modes: impl Into<FlagSet<EncodationType>>,
+) -> Vec<u8> {
+    let code = crate::DataMatrixBuilder::new()
+        .with_symbol_list(size)
+        .with_encodation_types(enabled_modes)
+        .encode(data)
+        .unwrap();
+    assert_eq!(code.size, size);
+    code.codewords().to_vec()
+}
+
+#[test]
+fn test_iso_example_ascii() {
+    // the encodation example of the specification, including error correction
+    assert_eq!(
+        enc_symbol(
+            b"123456",
+            crate::SymbolSize::Square10,
+            EncodationType::all()
+        ),
+        vec![142, 164, 186, 114, 25, 5, 88, 102]
+    );
+}
+
+#[test]
+fn test_iso_example_c40_text_x12() {
+    use crate::SymbolSize::Square14;
+
+    // "AIM" has the values 14, 22, 26 in C40, Text (lowercase) and X12,
+    // 1600 * 14 + 40 * 22 + 26 + 1 = 23307 = 91 * 256 + 11
+    let c40 = enc_symbol(b"AIMAIMAIM", Square14, EncodationType::all());
+    assert_eq!(c40[..8], [230, 91, 11, 91, 11, 91, 11, 254]);
+    let text = enc_symbol(b"aimaimaim", Square14, EncodationType::all());
+    assert_eq!(text[..8], [239, 91, 11, 91, 11, 91, 11, 254]);
+    let x12 = enc_symbol(
+        b"AIMAIMAIM",
+        Square14,
+        EncodationType::Ascii | EncodationType::X12,
+    );
+    assert_eq!(x12[..8], [238, 91, 11, 91, 11, 91, 11, 254]);
+}
+
+#[test]
+fn test_iso_example_edifact() {
+    // "ABC." has the values 1, 2, 3, 46, packed into 24 bits: 0x0420EE,
+    // "DEF." has 4, 5, 6, 46: 0x1051AE. One codeword is left, so
+    // the padding follows without UNLATCH.
+    let cw = enc_symbol(
+        b"ABC.DEF.",
+        crate::SymbolSize::Square14,
+        EncodationType::Ascii | EncodationType::Edifact,
+    );
+    assert_eq!(cw[..8], [240, 0x04, 0x20, 0xEE, 0x10, 0x51, 0xAE, 129]);
+}
+
+#[test]
+fn test_iso_example_base256() {
+    // 255-state randomization of length and data at positions 2 to 5,
+    // followed by PAD and the 253-state randomized padding at 7 and 8
+    let cw = enc_symbol(
+        b"\xab\xcd\xef",
+        crate::SymbolSize::Square14,
+        EncodationType::Ascii | EncodationType::Base256,
+    );
+    assert_eq!(cw[..8], [231, 47, 108, 36, 219, 129, 161, 56]);
+}
+
+#[test]
+fn test_edifact_switch_on_other_characters() {
+    use crate::DataMatrixBuilder;
+
+    // lowercase letters in between EDIFACT segments are encoded with ASCII
+    let data = b"ABC.DEF/GHI-JKL:MNOabcdPQR.STU/VWX-YZA:BCD";
+    let (code, segments) = DataMatrixBuilder::new()
+        .with_encodation_types(EncodationType::Ascii | EncodationType::Edifact)
+        .encode_with_segments(data)
+        .unwrap();
+    assert_eq!(
+        segments,
+        [
+            (0, EncodationType::Edifact),
+            (19, EncodationType::Ascii),
+            (23, EncodationType::Edifact)
+        ]
+    );
+    assert_eq!(
+        crate::data::decode_data(code.data_codewords()),
+        Ok(data.to_vec())
+    );
 }
@@ -0,0 +1,152 @@
+//! Test vectors from other encoders.
+//!
+//! The expected codewords are taken from the high level encoder tests of zxing.
+//! Our planner works different to theirs, so if it picks other modes the plan
+//! of zxing is given and followed. The bit packing of every mode must then be
+//! byte-exact.
+//!
+//! The GS1 vectors are worked out by hand from ISO/IEC 16022. They check that
+//! the FNC1 is placed after the ECI, and that group separators become FNC1 in
+//! ASCII, C40 and Text.
+use crate::{DataMatrixBuilder, EncodationType};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+use EncodationType::*;
+
+struct Vector {
+    data: &'static [u8],
+    plan: Option<&'static [(usize, EncodationType)]>,
+    gs1: bool,
+    eci: Option<u32>,
+    codewords: &'static [u8],
+}
+
+const VECTORS: &[Vector] = &[
+    // ASCII
+    Vector {
+        data: b"123456",
+        plan: None,
+        gs1: false,
+        eci: None,
+        codewords: &[142, 164, 186],
+    },
+    // C40
+    Vector {
+        data: b"AIMAIMAIM",
+        plan: None,
+        gs1: false,
+        eci: None,
+        codewords: &[230, 91, 11, 91, 11, 91, 11, 254],
+    },
+    Vector {
+        data: b"AIMAIMAIM\xcb",
+        plan: Some(&[(10, C40), (1, Ascii), (0, Ascii)]),
+        gs1: false,
+        eci: None,
+        codewords: &[230, 91, 11, 91, 11, 91, 11, 254, 235, 76],
+    },
+    Vector {
+        data: b"AIMAIMAIM\xeb",
+        plan: Some(&[(10, C40), (1, Ascii), (0, Ascii)]),
+        gs1: false,
+        eci: None,
+        codewords: &[230, 91, 11, 91, 11, 91, 11, 254, 235, 108],
+    },
+    // Text
+    Vector {
+        data: b"aimaimaim",
+        plan: None,
+        gs1: false,
+        eci: None,
+        codewords: &[239, 91, 11, 91, 11, 91, 11, 254],
+    },
+    // X12
+    Vector {
+        data: b"ABC>ABC123>AB",
+        plan: None,
+        gs1: false,
+        eci: None,
+        codewords: &[238, 89, 233, 14, 192, 100, 207, 44, 31, 67],
+    },
+    // EDIFACT
+    Vector {
+        data: b".A.C1.3.DATA.123DATA.123DATA",
+        plan: None,
+        gs1: false,
+        eci: None,
+        codewords: &[
+            240, 184, 27, 131, 198, 236, 238, 16, 21, 1, 187, 28, 179, 16, 21, 1, 187, 28, 179, 16,
+            21, 1,
+        ],
+    },
+    // Base256
+    Vector {
+        data: b"\xab\xe4\xf6\xfc\xe9\xbb",
+        plan: None,
+        gs1: false,
+        eci: None,
+        codewords: &[231, 44, 108, 59, 226, 126, 1, 104],
+    },
+    Vector {
+        data: b"\xab\xe4\xf6\xfc\xe9\xe0\xbb",
+        plan: None,
+        gs1: false,
+        eci: None,
+        codewords: &[231, 51, 108, 59, 226, 126, 1, 141, 254, 129],
+    },
+    // GS1, FNC1 first and then digit pairs
+    Vector {
+        data: b"0112345678901231",
+        plan: None,
+        gs1: true,
+        eci: None,
+        codewords: &[232, 131, 142, 164, 186, 208, 220, 142, 161],
+    },
+    // group separator in ASCII
+    Vector {
+        data: b"10ABC\x1d2112",
+        plan: None,
+        gs1: true,
+        eci: None,
+        codewords: &[232, 140, 66, 67, 68, 232, 151, 142],
+    },
+    // group separator in C40 and Text, shift 2 and 27
+    Vector {
+        data: b"10ABCD\x1d2112",
+        plan: Some(&[(9, C40), (4, Ascii), (0, Ascii)]),
+        gs1: true,
+        eci: None,
+        codewords: &[232, 140, 230, 89, 233, 106, 132, 254, 151, 142],
+    },
+    Vector {
+        data: b"10abcd\x1d2112",
+        plan: Some(&[(9, Text), (4, Ascii), (0, Ascii)]),
+        gs1: true,
+        eci: None,
+        codewords: &[232, 140, 239, 89, 233, 106, 132, 254, 151, 142],
+    },
+    // the ECI comes before the FNC1
+    Vector {
+        data: b"0112",
+        plan: None,
+        gs1: true,
+        eci: Some(26),
+        codewords: &[241, 27, 232, 131, 142],
+    },
+];
+
+#[test]
+fn test_vectors() {
+    for vector in VECTORS {
+        let mut builder = DataMatrixBuilder::new().with_gs1(vector.gs1);
+        if let Some(plan) = vector.plan {
+            builder = builder.with_encodation_plan(plan.to_vec());
+        }
+        let code = builder.encode_eci(vector.data, vector.eci).unwrap();
+        // the reference does not include the padding
+        let codewords = &code.data_codewords()[..vector.codewords.len()];
+        assert_eq!(codewords, vector.codewords, "{:?}", vector.data);
+    }
+}
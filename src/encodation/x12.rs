@@ -16,9 +16,23 @@ fn enc(ch: u8) -> u8 {
     }
 }
 
+/// Number of codewords written by [encode] for non-empty `data`, see
+/// [EncodationType::estimate_codewords](super::EncodationType::estimate_codewords).
+pub(super) fn estimate_codewords(data: &[u8]) -> Option<usize> {
+    if !data.iter().copied().all(is_native_x12) {
+        return None;
+    }
+    let full = data.len() / 3;
+    // latch, triples, UNLATCH and the rest in ASCII
+    Some(1 + full * 2 + 1 + ascii::encoding_size(&data[full * 3..]))
+}
+
 pub(super) fn encode<T: EncodingContext>(ctx: &mut T) -> Result<(), DataEncodingError> {
     let mut switch = false;
     while ctx.characters_left() >= 3 {
+        if !ctx.rest()[..3].iter().copied().all(is_native_x12) {
+            return Err(DataEncodingError::TooMuchOrIllegalData);
+        }
         let c1 = enc(ctx.eat().unwrap());
         let c2 = enc(ctx.eat().unwrap());
         let c3 = enc(ctx.eat().unwrap());
@@ -33,6 +47,7 @@ pub(super) fn encode<T: EncodingContext>(ctx: &mut T) -> Result<(), DataEncoding
     let one_ascii_remain_maybe =
         ctx.characters_left() <= 2 && ascii::encoding_size(ctx.rest()) == 1;
     if one_ascii_remain_maybe
+        && !ctx.is_strict()
         && ctx
             .symbol_size_left(1)
             .ok_or(DataEncodingError::TooMuchOrIllegalData)?
@@ -0,0 +1,347 @@
+//! Correction of errors and erasures with the Berlekamp-Massey algorithm.
+//!
+//! An erasure is a codeword at a known position with an unknown value,
+//! e.g., from a damaged area of the symbol. Each block can correct `v` errors
+//! and `e` erasures as long as `2 * v + e` is at most the number of error
+//! correction codewords per block.
+
+use super::ErrorDecodingError;
+use crate::errorcode::GF;
+use crate::SymbolSize;
+
+use alloc::{vec, vec::Vec};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Evaluate the polynomial with coefficients `p`, lowest power first, at `x`.
+fn eval(p: &[GF], x: GF) -> GF {
+    p.iter().rev().fold(GF(0), |acc, c| acc * x + *c)
+}
+
+/// Split the index of a codeword in the symbol into the block and the index in the block.
+fn block_position(index: usize, size: SymbolSize) -> (usize, usize) {
+    let stride = size.block_setup().num_ecc_blocks;
+    let num_data = size.num_data_codewords();
+    if index < num_data {
+        (index % stride, index / stride)
+    } else {
+        let block = (index - num_data) % stride;
+        let block_data = (num_data - block).div_ceil(stride);
+        (block, block_data + (index - num_data) / stride)
+    }
+}
+
+/// Get the indices of the codewords of `block` in the symbol, data first.
+fn block_indices(block: usize, size: SymbolSize) -> impl Iterator<Item = usize> {
+    let stride = size.block_setup().num_ecc_blocks;
+    let num_data = size.num_data_codewords();
+    (block..num_data)
+        .step_by(stride)
+        .chain((num_data + block..size.num_codewords()).step_by(stride))
+}
+
+/// Compute the syndromes of all blocks.
+///
+/// The `codewords` contain the data and the error correction of a symbol of
+/// the given `size`. For each block the syndromes are returned one after the other,
+/// [num_error_codewords](SymbolSize::num_error_codewords) in total.
+/// They are all zero if and only if no error was detected.
+///
+/// # Panics
+///
+/// Panics if the number of codewords does not match the `size`.
+pub fn syndromes(codewords: &[u8], size: SymbolSize) -> Vec<u8> {
+    assert_eq!(
+        codewords.len(),
+        size.num_codewords(),
+        "wrong number of codewords"
+    );
+    let setup = size.block_setup();
+    let mut out = Vec::with_capacity(setup.num_ecc_blocks * setup.num_ecc_per_block);
+    let mut syn = vec![GF(0); setup.num_ecc_per_block];
+    for block in 0..setup.num_ecc_blocks {
+        let received: Vec<u8> = block_indices(block, size).map(|i| codewords[i]).collect();
+        super::primitive_element_evaluation(received.iter().copied(), &mut syn);
+        out.extend(syn.iter().map(|s| s.0));
+    }
+    out
+}
+
+/// Correct errors and erasures in-place and return the number of changed codewords.
+///
+/// The `erasures` are the indices of codewords in `codewords` whose values are
+/// unknown, their content is ignored. Duplicate indices are allowed.
+/// Without erasures this corrects the same errors as [decode](super::decode).
+///
+/// Fails with [ErrorDecodingError::TooManyErrors] if a block has too many errors
+/// and erasures, and with [ErrorDecodingError::ErrorsOutsideRange] if an erasure is
+/// not an index of `codewords`. The codewords are not changed in this case.
+///
+/// # Panics
+///
+/// Panics if the number of codewords does not match the `size`.
+///
+/// # Example
+///
+/// ```rust
+/// # use datamatrix::{errorcode, SymbolSize};
+/// let mut codewords = b"Hi!".map(|ch| ch + 1).to_vec();
+/// codewords.extend(errorcode::encode_error(&codewords, SymbolSize::Square10));
+/// let original = codewords.clone();
+/// // one wrong codeword, and three unreadable ones
+/// codewords[0] = 0;
+/// codewords[1..4].fill(0);
+/// let corrected = errorcode::correct_errors(&mut codewords, SymbolSize::Square10, &[1, 2, 3]);
+/// assert_eq!(corrected, Ok(4));
+/// assert_eq!(codewords, original);
+/// ```
+pub fn correct_errors(
+    codewords: &mut [u8],
+    size: SymbolSize,
+    erasures: &[usize],
+) -> Result<usize, ErrorDecodingError> {
+    assert_eq!(
+        codewords.len(),
+        size.num_codewords(),
+        "wrong number of codewords"
+    );
+    if erasures.iter().any(|i| *i >= codewords.len()) {
+        return Err(ErrorDecodingError::ErrorsOutsideRange);
+    }
+    let setup = size.block_setup();
+    let mut corrected: Vec<(usize, u8)> = Vec::new();
+    for block in 0..setup.num_ecc_blocks {
+        let indices: Vec<usize> = block_indices(block, size).collect();
+        let mut received: Vec<u8> = indices.iter().map(|i| codewords[*i]).collect();
+        let mut block_erasures: Vec<usize> = erasures
+            .iter()
+            .map(|i| block_position(*i, size))
+            .filter(|(b, _)| *b == block)
+            .map(|(_, i)| i)
+            .collect();
+        block_erasures.sort_unstable();
+        block_erasures.dedup();
+        correct_block(&mut received, setup.num_ecc_per_block, &block_erasures)?;
+        for (i, value) in indices.iter().zip(received) {
+            if codewords[*i] != value {
+                corrected.push((*i, value));
+            }
+        }
+    }
+    for (i, value) in &corrected {
+        codewords[*i] = *value;
+    }
+    Ok(corrected.len())
+}
+
+/// Correct a single block with `num_ecc` error correction codewords at the end.
+fn correct_block(
+    received: &mut [u8],
+    num_ecc: usize,
+    erasures: &[usize],
+) -> Result<(), ErrorDecodingError> {
+    let n = received.len();
+    // the first codeword is the coefficient of the highest power
+    let locator = |idx: usize| GF::primitive_power((n - 1 - idx) as u8);
+
+    let mut syn = vec![GF(0); num_ecc];
+    if !super::primitive_element_evaluation(received.iter().copied(), &mut syn) {
+        return Ok(());
+    }
+    let e = erasures.len();
+    if e > num_ecc {
+        return Err(ErrorDecodingError::TooManyErrors);
+    }
+
+    // erasure locator polynomial, prod (1 + X x), lowest power first
+    let mut gamma = vec![GF(1)];
+    for idx in erasures {
+        let x = locator(*idx);
+        gamma.push(GF(0));
+        for i in (1..gamma.len()).rev() {
+            let prev = gamma[i - 1];
+            gamma[i] += x * prev;
+        }
+    }
+
+    // Berlekamp-Massey, initialized with the erasures
+    let mut lambda = gamma.clone();
+    let mut b = gamma;
+    let mut l = e;
+    for r in e..num_ecc {
+        let delta: GF = lambda
+            .iter()
+            .take(r + 1)
+            .enumerate()
+            .map(|(j, c)| *c * syn[r - j])
+            .sum();
+        b.insert(0, GF(0));
+        if delta != GF(0) {
+            let mut t = lambda.clone();
+            t.resize(t.len().max(b.len()), GF(0));
+            for (t, b) in t.iter_mut().zip(&b) {
+                *t -= delta * *b;
+            }
+            if 2 * l <= r + e {
+                b = lambda.iter().map(|c| *c / delta).collect();
+                l = r + 1 + e - l;
+            }
+            lambda = t;
+        }
+    }
+    while lambda.last() == Some(&GF(0)) {
+        lambda.pop();
+    }
+    let degree = lambda.len() - 1;
+    if degree != l || 2 * (l - e) + e > num_ecc {
+        return Err(ErrorDecodingError::TooManyErrors);
+    }
+
+    // Chien search in the positions of the block
+    let positions: Vec<usize> = (0..n)
+        .filter(|idx| eval(&lambda, GF(1) / locator(*idx)) == GF(0))
+        .collect();
+    if positions.len() != degree {
+        return Err(ErrorDecodingError::ErrorsOutsideRange);
+    }
+
+    // Forney, omega = syn * lambda mod x^num_ecc
+    let mut omega = vec![GF(0); num_ecc];
+    for (i, s) in syn.iter().enumerate() {
+        for (j, c) in lambda.iter().take(num_ecc - i).enumerate() {
+            omega[i + j] += *s * *c;
+        }
+    }
+    // formal derivative, the even powers vanish in GF(256)
+    let derivative: Vec<GF> = lambda
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, c)| if i % 2 == 1 { *c } else { GF(0) })
+        .collect();
+    for idx in positions {
+        let x_inv = GF(1) / locator(idx);
+        let denominator = eval(&derivative, x_inv);
+        if denominator == GF(0) {
+            return Err(ErrorDecodingError::Malfunction);
+        }
+        let value = eval(&omega, x_inv) / denominator;
+        received[idx] = (GF(received[idx]) - value).into();
+    }
+
+    if super::primitive_element_evaluation(received.iter().copied(), &mut syn) {
+        return Err(ErrorDecodingError::Malfunction);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_block_position() {
+    for size in crate::SymbolList::all() {
+        let setup = size.block_setup();
+        let mut seen = vec![false; size.num_codewords()];
+        for block in 0..setup.num_ecc_blocks {
+            for (i, index) in block_indices(block, size).enumerate() {
+                assert_eq!(block_position(index, size), (block, i), "{:?}", size);
+                seen[index] = true;
+            }
+        }
+        assert!(seen.iter().all(|s| *s));
+    }
+}
+
+#[test]
+fn test_syndromes() {
+    for size in [SymbolSize::Square10, SymbolSize::Square144] {
+        let data = crate::test::random_data()(size.num_data_codewords());
+        let mut codewords = data.clone();
+        codewords.extend(crate::errorcode::encode_error(&data, size));
+        let syn = syndromes(&codewords, size);
+        assert_eq!(syn.len(), size.num_error_codewords());
+        assert!(syn.iter().all(|s| *s == 0));
+        codewords[1] ^= 0x42;
+        assert!(syndromes(&codewords, size).iter().any(|s| *s != 0));
+    }
+}
+
+#[test]
+fn test_errors_and_erasures() {
+    let mut rnd = crate::test::random_data();
+    let sizes = [
+        SymbolSize::Square10,
+        SymbolSize::Square20,
+        SymbolSize::Rect8x18,
+        SymbolSize::Square52,
+        SymbolSize::Square144,
+    ];
+    for size in sizes {
+        let setup = size.block_setup();
+        let num_ecc = setup.num_ecc_per_block;
+        for num_erasures in 0..=num_ecc {
+            let num_errors = (num_ecc - num_erasures) / 2;
+            let data = rnd(size.num_data_codewords());
+            let mut original = data.clone();
+            original.extend(crate::errorcode::encode_error(&data, size));
+
+            // distinct positions in the last block
+            let mut positions: Vec<usize> = block_indices(setup.num_ecc_blocks - 1, size).collect();
+            for (i, r) in rnd(positions.len()).iter().enumerate() {
+                let j = i + *r as usize % (positions.len() - i);
+                positions.swap(i, j);
+            }
+            let (erasures, rest) = positions.split_at(num_erasures);
+            let errors = &rest[..num_errors];
+
+            let mut codewords = original.clone();
+            for i in erasures {
+                codewords[*i] = 0;
+            }
+            for i in errors {
+                codewords[*i] ^= 0x5a;
+            }
+            let changed = codewords
+                .iter()
+                .zip(&original)
+                .filter(|(a, b)| a != b)
+                .count();
+            assert_eq!(
+                correct_errors(&mut codewords, size, erasures),
+                Ok(changed),
+                "{:?} {} {}",
+                size,
+                num_erasures,
+                num_errors
+            );
+            assert_eq!(codewords, original);
+        }
+    }
+}
+
+#[test]
+fn test_too_many_erasures() {
+    let size = SymbolSize::Square10;
+    let data = [1, 2, 3];
+    let mut codewords = data.to_vec();
+    codewords.extend(crate::errorcode::encode_error(&data, size));
+    let original = codewords.clone();
+
+    // no errors, the erasures do not matter
+    assert_eq!(
+        correct_errors(&mut codewords, size, &[0, 1, 2, 3, 4, 5]),
+        Ok(0)
+    );
+
+    codewords[0] = 0;
+    assert_eq!(
+        correct_errors(&mut codewords, size, &[0, 1, 2, 3, 4, 5]),
+        Err(ErrorDecodingError::TooManyErrors)
+    );
+    assert_eq!(
+        correct_errors(&mut codewords, size, &[8]),
+        Err(ErrorDecodingError::ErrorsOutsideRange)
+    );
+    // duplicates count once
+    assert_eq!(correct_errors(&mut codewords, size, &[0, 0, 1, 1]), Ok(1));
+    assert_eq!(codewords, original);
+}
@@ -1,3 +1,4 @@
+mod erasures;
 mod syndrome_based;
 
 use super::galois::GF;
@@ -11,6 +12,7 @@ use pretty_assertions::assert_eq;
 ///
 /// The specific variant is not relevant for practice.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ErrorDecodingError {
     TooManyErrors,
     /// Error locations were found outside of the codeword.
@@ -20,6 +22,19 @@ pub enum ErrorDecodingError {
     Malfunction,
 }
 
+impl core::fmt::Display for ErrorDecodingError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TooManyErrors => write!(f, "too many errors to correct"),
+            Self::ErrorsOutsideRange => write!(f, "error locations outside of the codewords"),
+            Self::Malfunction => write!(f, "error correction failed"),
+        }
+    }
+}
+
+impl core::error::Error for ErrorDecodingError {}
+
+pub use erasures::{correct_errors, syndromes};
 pub use syndrome_based::decode;
 
 /// Evaluate the polynomical given by coefficients `c` at
@@ -57,8 +57,8 @@ where
     F: Fn(&[GF]) -> Result<Vec<GF>, ErrorDecodingError>,
     G: Fn(&mut [GF], &[GF], &mut [GF]),
 {
-    let n_data = (data.len() + stride - 1) / stride;
-    let n_error = (error.len() + stride - 1) / stride;
+    let n_data = data.len().div_ceil(stride);
+    let n_error = error.len().div_ceil(stride);
     let n = n_data + n_error;
     // generator polynomial has degree d = err_len
     assert!(err_len >= 1, "degree of generator polynomial must be >= 1");
@@ -115,11 +115,12 @@ where
         if i >= n {
             return Err(ErrorDecodingError::ErrorsOutsideRange);
         }
-        let mut idx = (n - i - 1) * stride;
-        if idx < data.len() {
+        let k = n - i - 1;
+        if k < n_data {
+            let idx = k * stride;
             data[idx] = (GF(data[idx]) - *err).into();
         } else {
-            idx -= data.len();
+            let idx = (k - n_data) * stride;
             error[idx] = (GF(error[idx]) - *err).into();
         }
     }
@@ -139,7 +140,7 @@ fn find_inv_error_locations_levinson_durbin(syn: &[GF]) -> Result<Vec<GF>, Error
     // initialize y = [1/b_v, 0, ..., 0]
     let mut y = Vec::with_capacity(t);
     y.push(GF(1) / syn[v - 1]);
-    y.extend(core::iter::repeat(GF(0)).take(v - 1));
+    y.extend(core::iter::repeat_n(GF(0), v - 1));
 
     // initialize w, solve lower right triangular system H_v w = h_v
     let mut w = Vec::<GF>::with_capacity(t);
@@ -525,6 +526,22 @@ fn test_recovery() {
     assert_eq!(&data, &received);
 }
 
+#[test]
+fn test_recovery_interleaved() {
+    // the blocks of 144x144 have different lengths
+    let size = SymbolSize::Square144;
+    let mut data: Vec<u8> = (0..size.num_data_codewords()).map(|i| i as u8).collect();
+    let ecc = crate::errorcode::encode_error(&data, size);
+    data.extend_from_slice(&ecc);
+    let mut received = data.clone();
+    let len = received.len();
+    for i in (0..31).chain(len - 31..len) {
+        received[i] ^= 0x55;
+    }
+    decode(&mut received, size).unwrap();
+    assert_eq!(&data, &received);
+}
+
 #[test]
 fn test_recovery1() {
     let mut data = vec![
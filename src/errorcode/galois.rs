@@ -55,7 +55,7 @@ const fn compute_alog_log() -> ([u8; 255], [u8; 256]) {
         // code in extra/gf.py.
         p *= 2;
         if p >= 256 {
-            p ^= 0x12D;
+            p ^= super::RS_PRIMITIVE_POLY;
         }
 
         i += 1;
@@ -69,7 +69,18 @@ const ANTI_LOG: [u8; 255] = compute_alog_log().0;
 
 /// Lookup table to convert an element from GF(256) represented as a degree 7 polynomial
 /// to a power i for a generator a.
-const LOG: [u8; 256] = compute_alog_log().1;
+pub(super) const LOG: [u8; 256] = compute_alog_log().1;
+
+/// [ANTI_LOG] repeated, so the sum of two powers can be looked up without a modulo.
+const ANTI_LOG_TWICE: [u8; 510] = {
+    let mut table = [0; 510];
+    let mut i = 0;
+    while i < 510 {
+        table[i] = ANTI_LOG[i % 255];
+        i += 1;
+    }
+    table
+};
 
 #[derive(Clone, Copy, PartialEq)]
 pub struct GF(pub u8);
@@ -88,6 +99,12 @@ impl GF {
         assert!(self != GF(0), "log of 0");
         LOG[self.0 as usize] as usize
     }
+
+    /// Compute x^(i + j), i.e., the product of two elements given by their powers.
+    #[inline]
+    pub fn power_product(i: u8, j: u8) -> Self {
+        GF(ANTI_LOG_TWICE[i as usize + j as usize])
+    }
 }
 
 impl core::fmt::Debug for GF {
@@ -133,10 +150,7 @@ impl Mul<GF> for GF {
         if self.0 == 0 || rhs.0 == 0 {
             return GF(0);
         }
-        let ia = LOG[self.0 as usize];
-        let ib = LOG[rhs.0 as usize];
-        let i = (ia as u16 + ib as u16) % 255;
-        GF(ANTI_LOG[i as usize])
+        Self::power_product(LOG[self.0 as usize], LOG[rhs.0 as usize])
     }
 }
 
@@ -229,6 +243,16 @@ fn sanity_check_tables() {
     }
 }
 
+#[test]
+fn test_power_product() {
+    for i in 0..255 {
+        for j in 0..255 {
+            let product = GF::primitive_power(i) * GF::primitive_power(j);
+            assert_eq!(GF::power_product(i, j), product);
+        }
+    }
+}
+
 #[test]
 fn gf256_mul() {
     assert_eq!(GF(123) * GF(1), GF(123));
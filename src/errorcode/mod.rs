@@ -33,6 +33,10 @@
 //!
 //! The other possibilities mentionend for step 2 and 4
 //! are still in the source code in case someone is interested in them.
+//!
+//! If the positions of some damaged codewords are known, [correct_errors] can
+//! use them as erasures. It uses the Berlekamp-Massey algorithm and Forney's formula
+//! and also returns the number of corrected codewords.
 mod decoding;
 mod galois;
 
@@ -43,10 +47,15 @@ use galois::GF;
 
 pub use decoding::decode as decode_error;
 pub use decoding::ErrorDecodingError;
+pub use decoding::{correct_errors, syndromes};
 
 #[cfg(test)]
 use pretty_assertions::assert_eq;
 
+/// The irreducible polynomial `x^8 + x^5 + x^3 + x^2 + 1` defining the
+/// multiplication in GF(256), the field used by the Reed-Solomon code.
+pub const RS_PRIMITIVE_POLY: u16 = 0x12D;
+
 /// The coefficients of the generator polynomicals used
 /// by the Reed-Solomon code specified for Data Matrix.
 ///
@@ -173,11 +182,52 @@ const GENERATOR_POLYNOMIALS: [&[u8]; 25] = [
     ],
 ];
 
-fn generator(len: usize) -> &'static [u8] {
+/// The coefficients of [GENERATOR_POLYNOMIALS] as powers of 2.
+///
+/// All coefficients are non-zero, and multiplying with them only needs
+/// one table lookup this way, see [ecc_block].
+const GENERATOR_LOGS: [[u8; 68 + 1]; GENERATOR_POLYNOMIALS.len()] = {
+    let mut logs = [[0; 68 + 1]; GENERATOR_POLYNOMIALS.len()];
+    let mut i = 0;
+    while i < GENERATOR_POLYNOMIALS.len() {
+        let g = GENERATOR_POLYNOMIALS[i];
+        let mut j = 0;
+        while j < g.len() {
+            logs[i][j] = galois::LOG[g[j] as usize];
+            j += 1;
+        }
+        i += 1;
+    }
+    logs
+};
+
+/// Get the coefficients of the generator polynomial for `num_ecc` error codewords per block.
+///
+/// The coefficients start with the highest power, so the first one is always 1.
+/// The generator polynomial is `(x - 2)(x - 2^2)...(x - 2^num_ecc)`. Returns `None`
+/// if no symbol size uses `num_ecc` error codewords per block.
+///
+/// # Example
+///
+/// ```rust
+/// # use datamatrix::errorcode::generator_polynomial;
+/// assert_eq!(generator_polynomial(5), Some(&[1, 62, 111, 15, 48, 228][..]));
+/// assert_eq!(generator_polynomial(6), None);
+/// ```
+pub fn generator_polynomial(num_ecc: usize) -> Option<&'static [u8]> {
     GENERATOR_POLYNOMIALS
         .iter()
-        .find(|p| p.len() - 1 == len)
-        .expect("no generator polynomical defined for this symbol size, this is a bug")
+        .find(|p| p.len() - 1 == num_ecc)
+        .copied()
+}
+
+/// Get the generator coefficients for `len` error codewords as in [GENERATOR_LOGS].
+fn generator_logs(len: usize) -> &'static [u8] {
+    let i = GENERATOR_POLYNOMIALS
+        .iter()
+        .position(|p| p.len() - 1 == len)
+        .expect("no generator polynomical defined for this symbol size, this is a bug");
+    &GENERATOR_LOGS[i][..=len]
 }
 
 /// Compute the Reed-Solomon code used by Data Matrix for error correction.
@@ -191,23 +241,33 @@ fn generator(len: usize) -> &'static [u8] {
 /// Panics if the size of `data` dooes not match the number of data
 /// codewords needed for the symbol size.
 pub fn encode_error(data: &[u8], size: SymbolSize) -> Vec<u8> {
+    let setup = size.block_setup();
+    let mut full_ecc = vec![0; setup.num_ecc_per_block * setup.num_ecc_blocks];
+    encode_error_into(data, size, &mut full_ecc);
+    full_ecc
+}
+
+/// Compute the error code like [encode_error] and write it to `full_ecc`.
+///
+/// `full_ecc` must be zeroed and have the size of the error code.
+pub(crate) fn encode_error_into(data: &[u8], size: SymbolSize, full_ecc: &mut [u8]) {
     let setup = size.block_setup();
     let num_codewords = size.num_data_codewords();
     assert!(data.len() == num_codewords);
-    let gen = generator(setup.num_ecc_per_block);
+    assert!(full_ecc.len() == setup.num_ecc_per_block * setup.num_ecc_blocks);
+    let gen = generator_logs(setup.num_ecc_per_block);
     // For bigger symbol sizes the data is split up into interleaved blocks
     // for which an error code is computed individually. we store
     // the error blocks interleaved in the returned result.
     let stride = setup.num_ecc_blocks;
-    let mut ecc = vec![0; setup.num_ecc_per_block + 1];
-    let mut full_ecc = vec![0; setup.num_ecc_per_block * setup.num_ecc_blocks];
+    // the longest generator polynomial has degree 68
+    let mut ecc = [0; 68 + 1];
+    let ecc = &mut ecc[..setup.num_ecc_per_block + 1];
     for block in 0..setup.num_ecc_blocks {
-        for item in &mut ecc {
-            // reset ecc for new block
-            *item = 0;
-        }
-        let strided_data_input = (block..data.len()).step_by(stride).map(|i| data[i]);
-        ecc_block(strided_data_input, gen, &mut ecc);
+        // reset ecc for new block
+        ecc.fill(0);
+        let strided_data_input = data[block..].iter().step_by(stride).copied();
+        ecc_block(strided_data_input, gen, ecc);
 
         // copy block interleaved to result vector
         for (result, ecc_i) in full_ecc
@@ -220,10 +280,9 @@ pub fn encode_error(data: &[u8], size: SymbolSize) -> Vec<u8> {
             *result = *ecc_i;
         }
     }
-    full_ecc
 }
 
-fn ecc_block<T: Iterator<Item = u8>>(data: T, g: &[u8], ecc: &mut [u8]) {
+fn ecc_block<T: Iterator<Item = u8>>(data: T, g_log: &[u8], ecc: &mut [u8]) {
     // Let d be the data polynomical (n coefficients) and g the generating polynomical
     // with k + 1 coefficients.
     //
@@ -240,11 +299,20 @@ fn ecc_block<T: Iterator<Item = u8>>(data: T, g: &[u8], ecc: &mut [u8]) {
     // the last k the error code, i.e., the coefficient of r. The algorithm
     // is modified to not compute q and store r directly in ecc. The ecc
     // array is used to store intermediate results.
-    let ecc_len = g.len() - 1;
+    //
+    // The coefficients of g are given as powers of 2, so k * g[j] is a single
+    // lookup once the power of k is known.
+    let ecc_len = g_log.len() - 1;
     for a in data {
         let k = GF(ecc[0]) + GF(a);
+        if k == GF(0) {
+            // ecc[ecc_len] stays 0
+            ecc.copy_within(1.., 0);
+            continue;
+        }
+        let k_log = k.log() as u8;
         for j in 0..ecc_len {
-            ecc[j] = (GF(ecc[j + 1]) + k * GF(g[j + 1])).into();
+            ecc[j] = ecc[j + 1] ^ GF::power_product(k_log, g_log[j + 1]).0;
         }
     }
 }
@@ -253,8 +321,34 @@ fn ecc_block<T: Iterator<Item = u8>>(data: T, g: &[u8], ecc: &mut [u8]) {
 fn ecc_block_1() {
     // The test case was computed with the Python script
     let data = [23, 40, 11];
-    let g = GENERATOR_POLYNOMIALS[0];
+    let g = generator_logs(5);
     let mut ecc = vec![0; 5 + 1];
     ecc_block(data.iter().cloned(), g, &mut ecc);
     assert_eq!(ecc[..5], vec![255, 207, 37, 244, 81]);
 }
+
+#[test]
+fn test_generator_polynomials() {
+    for g in GENERATOR_POLYNOMIALS {
+        // multiply the linear factors
+        let mut product = vec![GF(1)];
+        for alpha in GF::primitive_powers().skip(1).take(g.len() - 1) {
+            product.push(GF(0));
+            for j in (1..product.len()).rev() {
+                let prev = product[j - 1];
+                product[j] -= alpha * prev;
+            }
+        }
+        let product: Vec<u8> = product.into_iter().map(u8::from).collect();
+        assert_eq!(&product, g);
+        assert!(g.iter().all(|c| *c != 0));
+        let powers: Vec<u8> = generator_logs(g.len() - 1)
+            .iter()
+            .map(|i| GF::primitive_power(*i).into())
+            .collect();
+        assert_eq!(&powers, g);
+    }
+    for size in crate::SymbolList::all() {
+        assert!(generator_polynomial(size.block_setup().num_ecc_per_block).is_some());
+    }
+}
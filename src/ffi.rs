@@ -0,0 +1,311 @@
+//! C interface for encoding, requires the feature `ffi`.
+//!
+//! The crate is also built as a shared library, e.g., with
+//!
+//! ```text
+//! cargo build --release --features ffi
+//! ```
+//!
+//! this writes `libdatamatrix.so` (or `.dylib`, `.dll`) to `target/release`.
+//! Only targets with an operating system build the shared library, the module
+//! itself needs no more than `alloc`. The functions are declared in the header
+//! `include/datamatrix.h`:
+//!
+//! ```c
+//! #include "datamatrix.h"
+//!
+//! DmtxBitmap bitmap;
+//! if (dmtx_encode((const uint8_t *)"Hello", 5, NULL, &bitmap) == DMTX_OK) {
+//!     // bitmap.modules[y * bitmap.width + x] is 1 for dark modules
+//!     dmtx_bitmap_free(&bitmap);
+//! }
+//! ```
+//!
+//! [dmtx_encode] returns one of the `DMTX_*` codes, zero means success.
+use alloc::boxed::Box;
+use core::ffi::{c_char, c_int};
+use core::ptr;
+
+use crate::encodation::DataEncodingError;
+use crate::{DataMatrixBuilder, SymbolList};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Success.
+pub const DMTX_OK: c_int = 0;
+/// A required pointer was null.
+pub const DMTX_NULL_POINTER: c_int = 1;
+/// The data does not fit into the symbol, or can not be encoded with the options.
+pub const DMTX_TOO_MUCH_DATA: c_int = 2;
+/// No symbol has the requested number of rows and columns.
+pub const DMTX_INVALID_SIZE: c_int = 3;
+/// The encoder failed otherwise.
+pub const DMTX_ENCODING_FAILED: c_int = 4;
+
+/// Options for [dmtx_encode].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmtxOptions {
+    /// Number of rows of the symbol, or zero to pick the smallest one which fits.
+    pub rows: u32,
+    /// Number of columns of the symbol, zero if `rows` is zero.
+    pub columns: u32,
+    /// Use the macro codewords where possible, see [DataMatrixBuilder::with_macros].
+    pub use_macros: bool,
+    /// Encode GS1 data with FNC1 for the group separators, see [DataMatrixBuilder::with_gs1].
+    pub gs1: bool,
+}
+
+/// Bitmap of an encoded symbol, free it with [dmtx_bitmap_free].
+#[repr(C)]
+#[derive(Debug)]
+pub struct DmtxBitmap {
+    /// The modules row by row, `1` for dark and `0` for light.
+    pub modules: *mut u8,
+    /// Number of modules per row.
+    pub width: usize,
+    /// Number of rows.
+    pub height: usize,
+}
+
+impl DmtxOptions {
+    fn builder(&self) -> Result<DataMatrixBuilder, c_int> {
+        let symbol_list = if self.rows == 0 && self.columns == 0 {
+            SymbolList::default()
+        } else {
+            let size = SymbolList::with_extended_rectangles()
+                .iter()
+                .find(|size| {
+                    let ((width, height), _) = size.module_dimensions();
+                    (height, width) == (self.rows as usize, self.columns as usize)
+                })
+                .ok_or(DMTX_INVALID_SIZE)?;
+            SymbolList::from(size)
+        };
+        Ok(DataMatrixBuilder::new()
+            .with_symbol_list(symbol_list)
+            .with_macros(self.use_macros)
+            .with_gs1(self.gs1))
+    }
+}
+
+impl Default for DmtxOptions {
+    fn default() -> Self {
+        Self {
+            rows: 0,
+            columns: 0,
+            use_macros: true,
+            gs1: false,
+        }
+    }
+}
+
+/// Get the default options, any symbol size of [SymbolList::default] and macros enabled.
+#[no_mangle]
+pub extern "C" fn dmtx_options_default() -> DmtxOptions {
+    DmtxOptions::default()
+}
+
+/// Encode `len` bytes at `data` and write the bitmap to `out`.
+///
+/// If `options` is null the defaults are used. On failure `out` is
+/// set to an empty bitmap, which may also be passed to [dmtx_bitmap_free].
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, or may be null if `len` is zero.
+/// `options` must be null or valid, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn dmtx_encode(
+    data: *const u8,
+    len: usize,
+    options: *const DmtxOptions,
+    out: *mut DmtxBitmap,
+) -> c_int {
+    if out.is_null() {
+        return DMTX_NULL_POINTER;
+    }
+    // SAFETY: checked for null, the caller guarantees validity
+    let out = unsafe { &mut *out };
+    *out = DmtxBitmap {
+        modules: ptr::null_mut(),
+        width: 0,
+        height: 0,
+    };
+    let data: &[u8] = match (data.is_null(), len) {
+        (true, 0) => &[],
+        (true, _) => return DMTX_NULL_POINTER,
+        // SAFETY: the caller guarantees that `len` bytes can be read
+        (false, _) => unsafe { core::slice::from_raw_parts(data, len) },
+    };
+    // SAFETY: the caller guarantees that a non-null pointer is valid
+    let options = unsafe { options.as_ref() }.copied().unwrap_or_default();
+    let builder = match options.builder() {
+        Ok(builder) => builder,
+        Err(code) => return code,
+    };
+    let code = match builder.encode(data) {
+        Ok(code) => code,
+        Err(
+            DataEncodingError::TooMuchOrIllegalData
+            | DataEncodingError::TooMuchData { .. }
+            | DataEncodingError::IllegalCharacter { .. },
+        ) => return DMTX_TOO_MUCH_DATA,
+        Err(_) => return DMTX_ENCODING_FAILED,
+    };
+    let bitmap = code.bitmap();
+    let modules: Box<[u8]> = bitmap.bits().iter().map(|dark| *dark as u8).collect();
+    *out = DmtxBitmap {
+        modules: Box::into_raw(modules).cast(),
+        width: bitmap.width(),
+        height: bitmap.height(),
+    };
+    DMTX_OK
+}
+
+/// Free the modules of a bitmap returned by [dmtx_encode] and reset it.
+///
+/// Null pointers and empty bitmaps are ignored.
+///
+/// # Safety
+///
+/// `bitmap` must be null or point to a bitmap written by [dmtx_encode] which
+/// was not modified, and not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn dmtx_bitmap_free(bitmap: *mut DmtxBitmap) {
+    // SAFETY: the caller guarantees that a non-null pointer is valid
+    let Some(bitmap) = (unsafe { bitmap.as_mut() }) else {
+        return;
+    };
+    if !bitmap.modules.is_null() {
+        let len = bitmap.width * bitmap.height;
+        // SAFETY: the pointer and length come from the boxed slice in dmtx_encode
+        drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(bitmap.modules, len)) });
+    }
+    *bitmap = DmtxBitmap {
+        modules: ptr::null_mut(),
+        width: 0,
+        height: 0,
+    };
+}
+
+/// Get a static, NUL terminated description of an error code.
+#[no_mangle]
+pub extern "C" fn dmtx_error_message(code: c_int) -> *const c_char {
+    let message: &'static [u8] = match code {
+        DMTX_OK => b"success\0",
+        DMTX_NULL_POINTER => b"a required pointer was null\0",
+        DMTX_TOO_MUCH_DATA => b"the data does not fit into the symbol\0",
+        DMTX_INVALID_SIZE => b"no symbol has the given number of rows and columns\0",
+        DMTX_ENCODING_FAILED => b"the encoding failed\0",
+        _ => b"unknown error code\0",
+    };
+    message.as_ptr().cast()
+}
+
+#[test]
+fn test_ffi_encode() {
+    let options = DmtxOptions {
+        rows: 12,
+        columns: 36,
+        ..dmtx_options_default()
+    };
+    let mut out = DmtxBitmap {
+        modules: ptr::null_mut(),
+        width: 7,
+        height: 7,
+    };
+    let data = b"Hello, C!";
+    assert_eq!(
+        unsafe { dmtx_encode(data.as_ptr(), data.len(), &options, &mut out) },
+        DMTX_OK
+    );
+    assert_eq!((out.width, out.height), (36, 12));
+    let modules = unsafe { core::slice::from_raw_parts(out.modules, 36 * 12) };
+    let expected = DataMatrixBuilder::new()
+        .with_symbol_list(crate::SymbolSize::Rect12x36)
+        .encode(data)
+        .unwrap()
+        .bitmap();
+    assert!(modules
+        .iter()
+        .zip(expected.bits())
+        .all(|(a, b)| (*a == 1) == *b));
+    unsafe { dmtx_bitmap_free(&mut out) };
+    assert!(out.modules.is_null());
+    // freeing twice and null are fine
+    unsafe { dmtx_bitmap_free(&mut out) };
+    unsafe { dmtx_bitmap_free(ptr::null_mut()) };
+}
+
+#[test]
+fn test_ffi_errors() {
+    let mut out = DmtxBitmap {
+        modules: ptr::null_mut(),
+        width: 0,
+        height: 0,
+    };
+    let options = DmtxOptions {
+        rows: 10,
+        columns: 12,
+        ..DmtxOptions::default()
+    };
+    unsafe {
+        assert_eq!(
+            dmtx_encode(ptr::null(), 0, &options, &mut out),
+            DMTX_INVALID_SIZE
+        );
+        assert_eq!(
+            dmtx_encode(ptr::null(), 3, ptr::null(), &mut out),
+            DMTX_NULL_POINTER
+        );
+        assert_eq!(
+            dmtx_encode(b"1".as_ptr(), 1, ptr::null(), ptr::null_mut()),
+            DMTX_NULL_POINTER
+        );
+        let options = DmtxOptions {
+            rows: 10,
+            columns: 10,
+            ..DmtxOptions::default()
+        };
+        let data = [b'a'; 10];
+        assert_eq!(
+            dmtx_encode(data.as_ptr(), data.len(), &options, &mut out),
+            DMTX_TOO_MUCH_DATA
+        );
+        assert!(out.modules.is_null());
+        assert_eq!(dmtx_encode(ptr::null(), 0, ptr::null(), &mut out), DMTX_OK);
+        assert_eq!((out.width, out.height), (10, 10));
+        dmtx_bitmap_free(&mut out);
+
+        let message = core::ffi::CStr::from_ptr(dmtx_error_message(DMTX_TOO_MUCH_DATA));
+        assert_eq!(
+            message.to_str(),
+            Ok("the data does not fit into the symbol")
+        );
+        assert!(!dmtx_error_message(-1).is_null());
+    }
+}
+
+#[test]
+fn test_ffi_header() {
+    let header = include_str!("../include/datamatrix.h");
+    for (name, code) in [
+        ("DMTX_OK", DMTX_OK),
+        ("DMTX_NULL_POINTER", DMTX_NULL_POINTER),
+        ("DMTX_TOO_MUCH_DATA", DMTX_TOO_MUCH_DATA),
+        ("DMTX_INVALID_SIZE", DMTX_INVALID_SIZE),
+        ("DMTX_ENCODING_FAILED", DMTX_ENCODING_FAILED),
+    ] {
+        assert!(header.contains(&alloc::format!("#define {} {}\n", name, code)));
+    }
+    for declaration in [
+        "DmtxOptions dmtx_options_default(void);",
+        "int dmtx_encode(const uint8_t *data, size_t len, const DmtxOptions *options, DmtxBitmap *out);",
+        "void dmtx_bitmap_free(DmtxBitmap *bitmap);",
+        "const char *dmtx_error_message(int code);",
+    ] {
+        assert!(header.contains(declaration), "{}", declaration);
+    }
+}
@@ -0,0 +1,256 @@
+//! Element strings for GS1 Data Matrix.
+//!
+//! GS1 data consists of elements, each an application identifier (AI) followed
+//! by its value. The symbol starts with an FNC1, and an element whose AI does
+//! not have a predefined length is terminated by a group separator unless it is
+//! the last one. The separators are encoded as FNC1, too.
+//!
+//! Only a selection of common AIs is known, see [element_string].
+//!
+//! # Example
+//!
+//! ```rust
+//! # use datamatrix::{DataMatrix, SymbolList};
+//! let code = DataMatrix::encode_gs1(
+//!     &[("01", "09501101530003"), ("17", "270630"), ("10", "AB-123")],
+//!     SymbolList::default(),
+//! )
+//! .unwrap();
+//! ```
+use alloc::vec::Vec;
+
+use crate::encodation::{ascii::GS, DataEncodingError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Characters allowed in the values of the AIs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Digits only.
+    Numeric,
+    /// Digits, the last one is a check digit.
+    CheckDigit,
+    /// A date YYMMDD, the day may be 00.
+    Date,
+    /// The 82 characters of GS1 AI encodable character set 82.
+    Alphanumeric,
+}
+
+/// Get the kind and the minimal and maximal length of the value of `ai`.
+fn format(ai: &str) -> Option<(Kind, usize, usize)> {
+    use Kind::*;
+
+    let measure = ai.len() == 4
+        && ai.bytes().all(|ch| ch.is_ascii_digit())
+        && matches!(
+            ai[..3].parse::<u16>(),
+            Ok(310..=316 | 320..=337 | 340..=357 | 360..=369)
+        );
+    if measure {
+        return Some((Numeric, 6, 6));
+    }
+    let format = match ai {
+        "00" => (CheckDigit, 18, 18),
+        "01" | "02" => (CheckDigit, 14, 14),
+        "10" | "21" | "22" | "254" | "420" => (Alphanumeric, 1, 20),
+        "11" | "12" | "13" | "15" | "16" | "17" => (Date, 6, 6),
+        "20" => (Numeric, 2, 2),
+        "235" => (Alphanumeric, 1, 28),
+        "240" | "241" | "250" | "251" | "400" | "401" | "403" | "8004" | "90" => {
+            (Alphanumeric, 1, 30)
+        }
+        "242" => (Numeric, 1, 6),
+        "30" | "37" => (Numeric, 1, 8),
+        "402" => (CheckDigit, 17, 17),
+        "410" | "411" | "412" | "413" | "414" | "415" | "416" | "417" => (CheckDigit, 13, 13),
+        "422" => (Numeric, 3, 3),
+        "7003" => (Numeric, 10, 10),
+        "8005" => (Numeric, 6, 6),
+        "8017" | "8018" => (CheckDigit, 18, 18),
+        "8020" => (Alphanumeric, 1, 25),
+        "91" | "92" | "93" | "94" | "95" | "96" | "97" | "98" | "99" => (Alphanumeric, 1, 90),
+        _ => return None,
+    };
+    Some(format)
+}
+
+/// Does the AI have a predefined length, so that no separator is needed after it?
+///
+/// This depends only on the first two digits, e.g., AI 402 needs one.
+fn has_predefined_length(ai: &str) -> bool {
+    matches!(
+        &ai[..2],
+        "00" | "01"
+            | "02"
+            | "03"
+            | "04"
+            | "11"
+            | "12"
+            | "13"
+            | "14"
+            | "15"
+            | "16"
+            | "17"
+            | "18"
+            | "19"
+            | "20"
+            | "31"
+            | "32"
+            | "33"
+            | "34"
+            | "35"
+            | "36"
+            | "41"
+    )
+}
+
+fn is_encodable(ch: u8) -> bool {
+    ch.is_ascii_alphanumeric() || b"!\"%&'()*+,-./:;<=>?_".contains(&ch)
+}
+
+/// Check the GS1 check digit at the end of the digits.
+fn is_check_digit_valid(digits: &[u8]) -> bool {
+    let (check, rest) = digits.split_last().unwrap();
+    let sum: u32 = rest
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, ch)| (ch - b'0') as u32 * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    (10 - sum % 10) % 10 == (check - b'0') as u32
+}
+
+fn is_date_valid(digits: &[u8]) -> bool {
+    let two = |i: usize| (digits[i] - b'0') * 10 + digits[i + 1] - b'0';
+    (1..=12).contains(&two(2)) && two(4) <= 31
+}
+
+fn validate(ai: &str, value: &str) -> Result<(), DataEncodingError> {
+    let (kind, min, max) = format(ai).ok_or(DataEncodingError::InvalidOptions(
+        "unknown GS1 application identifier",
+    ))?;
+    let value = value.as_bytes();
+    if !(min..=max).contains(&value.len()) {
+        return Err(DataEncodingError::InvalidOptions(
+            "wrong length of a GS1 element value",
+        ));
+    }
+    if kind == Kind::Alphanumeric {
+        if !value.iter().all(|ch| is_encodable(*ch)) {
+            return Err(DataEncodingError::InvalidOptions(
+                "invalid character in a GS1 element value",
+            ));
+        }
+        return Ok(());
+    }
+    if !value.iter().all(|ch| ch.is_ascii_digit()) {
+        return Err(DataEncodingError::InvalidOptions(
+            "a numeric GS1 element value contains a non-digit",
+        ));
+    }
+    match kind {
+        Kind::CheckDigit if !is_check_digit_valid(value) => Err(DataEncodingError::InvalidOptions(
+            "wrong check digit in a GS1 element value",
+        )),
+        Kind::Date if !is_date_valid(value) => Err(DataEncodingError::InvalidOptions(
+            "invalid date in a GS1 element value",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Join the `elements`, pairs of an AI and its value, into the data of a GS1 symbol.
+///
+/// A group separator (byte 29) is inserted after each value of variable length,
+/// except after the last one. Encode the result with
+/// [with_gs1](crate::DataMatrixBuilder::with_gs1), or use
+/// [encode_gs1](crate::DataMatrixBuilder::encode_gs1) directly.
+///
+/// Known AIs are 00, 01, 02, 10 to 17, 20, 21, 22, 235, 240 to 242, 250, 251, 254,
+/// 30, the measures 310n to 369n, 37, 400 to 403, 410 to 417, 420, 422, 7003,
+/// 8004, 8005, 8017, 8018, 8020, and 90 to 99. The length and characters of
+/// the values are checked, as well as check digits and dates.
+///
+/// Fails with [DataEncodingError::InvalidOptions] if there are no elements
+/// or an element is invalid.
+///
+/// # Example
+///
+/// ```rust
+/// # use datamatrix::gs1;
+/// let data = gs1::element_string(&[("10", "AB-123"), ("17", "270630")]).unwrap();
+/// assert_eq!(data, b"10AB-123\x1d17270630");
+/// ```
+pub fn element_string(elements: &[(&str, &str)]) -> Result<Vec<u8>, DataEncodingError> {
+    if elements.is_empty() {
+        return Err(DataEncodingError::InvalidOptions("no GS1 elements"));
+    }
+    let mut out = Vec::new();
+    for (i, (ai, value)) in elements.iter().enumerate() {
+        validate(ai, value)?;
+        out.extend_from_slice(ai.as_bytes());
+        out.extend_from_slice(value.as_bytes());
+        if !has_predefined_length(ai) && i + 1 < elements.len() {
+            out.push(GS);
+        }
+    }
+    Ok(out)
+}
+
+#[test]
+fn test_element_string() {
+    let data = element_string(&[
+        ("01", "09501101530003"),
+        ("17", "270630"),
+        ("10", "AB-123"),
+        ("21", "12345"),
+    ])
+    .unwrap();
+    assert_eq!(data, b"01095011015300031727063010AB-123\x1d2112345");
+
+    // only AIs with a predefined length do not need a separator
+    let elements = [
+        ("00", "106141411234567897"),
+        ("3103", "001250"),
+        ("402", "12345678901234560"),
+        ("422", "276"),
+    ];
+    let data = element_string(&elements).unwrap();
+    assert_eq!(
+        data,
+        b"00106141411234567897310300125040212345678901234560\x1d422276"
+    );
+}
+
+#[test]
+fn test_validation() {
+    let invalid = [
+        ("01", "09501101530004"),
+        ("01", "0950110153000"),
+        ("17", "271301"),
+        ("17", "27063A"),
+        ("10", ""),
+        ("10", "ÄB"),
+        ("10", "A B"),
+        ("10", "123456789012345678901"),
+        ("3170", "001250"),
+        ("999", "1"),
+        ("1", "1"),
+    ];
+    for (ai, value) in invalid {
+        assert!(
+            matches!(
+                element_string(&[(ai, value)]),
+                Err(DataEncodingError::InvalidOptions(_))
+            ),
+            "{} {}",
+            ai,
+            value
+        );
+    }
+    assert!(element_string(&[]).is_err());
+    // the day may be zero
+    assert!(element_string(&[("17", "270600")]).is_ok());
+    assert!(element_string(&[("401", "1A!%&'()*+,-./:;<=>?_z")]).is_ok());
+}
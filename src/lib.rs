@@ -12,7 +12,7 @@
 //! ```
 //!
 //! This toy example will print a Data Matrix using Unicode block characters.
-//! For guidance on how to generate other output formats see the helper functions
+//! An SVG image is created by [to_svg()](Bitmap::to_svg). For guidance on how to generate other output formats see the helper functions
 //! defined for the [Bitmap struct](Bitmap), or the `examples/` directory of
 //! this project.
 //!
@@ -62,36 +62,100 @@
 //! assert_eq!(&data, b"Hello, World!");
 //! ```
 //!
+//! # Features
+//!
+//! The crate is `no_std` and only needs `alloc`, all features are optional.
+//! Only `std`, `rayon`, `image`, and `cli` need the standard library.
+//!
+//! With the `serde` feature [DataMatrix], [DataMatrixBuilder], [SymbolList],
+//! and the related types implement `Serialize` and `Deserialize`, e.g., to cache
+//! encodings. Symbol sizes are written as strings like `"22x22"`, and a
+//! [Bitmap] with eight modules per byte.
+//!
+//! The `heapless` feature adds `DataMatrixBuilder::encode_into_heapless` to write
+//! the data codewords into a vector with fixed capacity, without allocating.
+//! [DataMatrix::encode_without_alloc] encodes the whole symbol into buffers
+//! of the caller.
+//!
+//! The `std` feature adds `DataMatrixBuilder::encode_reader` to split data
+//! from an `std::io::Read` into symbols without reading it at once.
+//!
+//! The `rayon` feature makes [DataMatrixBuilder::encode_batch] encode the
+//! inputs in parallel, it implies `std`.
+//!
+//! The `ffi` feature adds C functions for encoding to the shared library,
+//! declared in `include/datamatrix.h`, see the module `ffi`.
+//!
+//! The `wasm` feature adds JavaScript bindings with `wasm-bindgen` to render
+//! SVGs in the browser, see the module `wasm`.
+//!
+//! The `cli` feature builds the `datamatrix` binary, which prints symbols as text,
+//! SVG, or PNG, and decodes images, e.g., `datamatrix encode "HELLO" --out svg`.
+//!
+//! The `image` feature adds `Bitmap::to_image` to render a grayscale image of the
+//! `image` crate, which can be saved as PNG.
+//!
+//! The `embedded-graphics` feature adds `Bitmap::drawable` to draw a symbol on
+//! displays supported by `embedded-graphics`.
+//!
+//! The `extended_eci` feature adds more character sets for decoding ECIs, see
+//! [decode_str](data::decode_str).
+//!
+//! The `placement-check` feature adds `placement::placement_matches` to check
+//! that a bitmap read back in placement order gives the codewords.
+//!
 //! # Current limitations
 //!
-//! No visual detection is currently implemented, but the decoding backend
-//! is done and exposed in the API. All that is missing is a detector to extract a matrix of true and false values
-//! from an image. A general purpose detector is planned for the future, though.
+//! Only a basic visual detection is implemented in [detection], it expects a single
+//! symbol seen mostly from the front. The decoding backend is exposed in the API, so
+//! a matrix of true and false values from another detector can also be decoded.
 //!
-//! Other limitations: Currently there is no support for GS1/FCN1 character encoding,
-//! full ECI, structured append, and
-//! reader programming. The decoding output format specified in ISO/IEC 15424 is
-//! also not implemented (metadata, ECI, etc.), if you have a use case for this
-//! please open an issue.
+//! ECIs are written with [DataMatrixBuilder::with_eci] and [DataMatrixBuilder::encode_str],
+//! and interpreted by [decode_str](data::decode_str). The decoding output format
+//! specified in ISO/IEC 15424 (symbology identifier, metadata, etc.) is not
+//! implemented, if you have a use case for this please open an issue.
 
 #![no_std]
 extern crate alloc;
+// the cdylib needs the panic handler and allocator of std, targets
+// without an operating system only build the rlib
+#[cfg(any(feature = "std", not(target_os = "none")))]
+extern crate std;
 
 mod decodation;
+pub mod detection;
 mod encodation;
 pub mod errorcode;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod gs1;
 pub mod placement;
+pub mod quality;
+pub mod reader_programming;
+mod report;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod serial;
+mod streaming;
+mod structured_append;
 mod symbol_size;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 pub mod data;
 
-pub use encodation::EncodationType;
-pub use symbol_size::{SymbolList, SymbolSize};
+pub use data::CharsetPolicy;
+pub use encodation::{Effort, Encodation, EncodationType};
+pub use report::EncodationReport;
+pub use serial::SerialEncoder;
+pub use streaming::StreamingEncoder;
+use symbol_size::Symbols;
+pub use symbol_size::{DataKind, Robustness, SymbolList, SymbolSize, SymbolSizes};
 
-use alloc::vec::Vec;
+use alloc::{borrow::Cow, vec::Vec};
 use flagset::FlagSet;
 
-use encodation::DataEncodingError;
+use encodation::{Constraint, DataEncodingError, RandomizerRef, SliceEncoder};
 use placement::{Bitmap, MatrixMap};
 
 #[cfg(test)]
@@ -108,6 +172,7 @@ pub struct DataMatrix {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// Errors when decoding a Data Matrix.
+#[non_exhaustive]
 pub enum DecodingError {
     /// Signals that the pixels could not be mapped to a [symbol size](SymbolSize),
     /// either because no symbol with matching dimensions was found or because the
@@ -120,6 +185,26 @@ pub enum DecodingError {
     DataDecoding(decodation::DataDecodingError),
 }
 
+impl core::fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::PixelConversion(_) => write!(f, "pixels could not be converted to a symbol"),
+            Self::ErrorCorrection(_) => write!(f, "error correction failed"),
+            Self::DataDecoding(_) => write!(f, "data could not be decoded"),
+        }
+    }
+}
+
+impl core::error::Error for DecodingError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::PixelConversion(err) => Some(err),
+            Self::ErrorCorrection(err) => Some(err),
+            Self::DataDecoding(err) => Some(err),
+        }
+    }
+}
+
 impl DataMatrix {
     /// Decode a Data Matrix from its pixels representation.
     ///
@@ -129,12 +214,119 @@ impl DataMatrix {
     /// The pixels are expected to be given in row-major order, i.e., the top
     /// row of pixels comes first, then the second row and so on.
     pub fn decode(pixels: &[bool], width: usize) -> Result<Vec<u8>, DecodingError> {
+        let codewords = Self::decode_data_codewords(pixels, width)?;
+        decodation::decode_data(&codewords).map_err(DecodingError::DataDecoding)
+    }
+
+    /// Get the error corrected data codewords from the pixels.
+    fn decode_data_codewords(pixels: &[bool], width: usize) -> Result<Vec<u8>, DecodingError> {
         let (matrix_map, size) =
             MatrixMap::try_from_bits(pixels, width).map_err(DecodingError::PixelConversion)?;
         let mut codewords = matrix_map.codewords();
         errorcode::decode_error(&mut codewords, size).map_err(DecodingError::ErrorCorrection)?;
-        decodation::decode_data(&codewords[..size.num_data_codewords()])
-            .map_err(DecodingError::DataDecoding)
+        codewords.truncate(size.num_data_codewords());
+        Ok(codewords)
+    }
+
+    /// Decode a Data Matrix from a bitmap, e.g., one created by [bitmap()](Self::bitmap).
+    ///
+    /// This is the same as [decode()](Self::decode), the bitmap must be
+    /// a clean Data Matrix without quiet zone, rotation, or distortion. For rotated or
+    /// mirrored bitmaps see [decode_bitmap_any_orientation()](Self::decode_bitmap_any_orientation).
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolList};
+    /// let code = DataMatrix::encode(b"Hello, World!", SymbolList::default()).unwrap();
+    /// let data = DataMatrix::decode_bitmap(&code.bitmap()).unwrap();
+    /// assert_eq!(&data, b"Hello, World!");
+    /// ```
+    pub fn decode_bitmap(bitmap: &Bitmap<bool>) -> Result<Vec<u8>, DecodingError> {
+        Self::decode(bitmap.bits(), bitmap.width())
+    }
+
+    /// Get the bitmap with all modules of the given codewords inverted.
+    ///
+    /// This creates symbols with controlled damage, e.g., to test how many wrong
+    /// codewords a reader can repair. The indices refer to [codewords()](Self::codewords),
+    /// up to [SymbolSize::correctable_codewords] consecutive ones within the data or within
+    /// the error correction codewords can always be repaired.
+    /// For damage of single modules see [Bitmap::flip_modules].
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolSize};
+    /// let code = DataMatrix::encode(b"Hello, World!", SymbolSize::Square18).unwrap();
+    /// let n = code.size.correctable_codewords();
+    /// let damaged = code.damaged_bitmap(0..n);
+    /// assert_eq!(DataMatrix::decode_bitmap(&damaged).unwrap(), b"Hello, World!");
+    /// assert!(DataMatrix::decode_bitmap(&code.damaged_bitmap(0..n + 1)).is_err());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is not smaller than [SymbolSize::num_codewords].
+    pub fn damaged_bitmap<I: IntoIterator<Item = usize>>(&self, codewords: I) -> Bitmap<bool> {
+        let mut bitmap = self.bitmap();
+        let width = bitmap.width();
+        let modules = placement::codeword_modules(self.size);
+        let positions: Vec<(usize, usize)> = codewords
+            .into_iter()
+            .flat_map(|i| modules[i])
+            .map(|idx| (idx % width, idx / width))
+            .collect();
+        bitmap.flip_modules(positions);
+        bitmap
+    }
+
+    /// Decode a bitmap which may be rotated or mirrored.
+    ///
+    /// All eight [orientations](placement::Orientation) are tried, starting with
+    /// the bitmap as is. The one which decoded successfully is returned with the data,
+    /// applying it to the bitmap with [Bitmap::transform] gives the canonical symbol.
+    /// If all fail, the error for the bitmap as is is returned.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolList, placement::Orientation};
+    /// let code = DataMatrix::encode(b"Hello, World!", SymbolList::default()).unwrap();
+    /// let rotated = code.bitmap().rotate270().mirror();
+    /// let (data, orientation) = DataMatrix::decode_bitmap_any_orientation(&rotated).unwrap();
+    /// assert_eq!(&data, b"Hello, World!");
+    /// assert_eq!(orientation, Orientation { quarter_turns: 1, mirrored: true });
+    /// ```
+    pub fn decode_bitmap_any_orientation(
+        bitmap: &Bitmap<bool>,
+    ) -> Result<(Vec<u8>, placement::Orientation), DecodingError> {
+        let err = match Self::decode_bitmap(bitmap) {
+            Ok(data) => return Ok((data, placement::Orientation::default())),
+            Err(err) => err,
+        };
+        for orientation in &placement::Orientation::ALL[1..] {
+            if let Ok(data) = Self::decode_bitmap(&bitmap.transform(*orientation)) {
+                return Ok((data, *orientation));
+            }
+        }
+        Err(err)
+    }
+
+    /// Decode the symbols of a structured append sequence and join their data.
+    ///
+    /// The bitmaps can be given in any order, but all symbols of the sequence are needed.
+    /// See [data::decode_structured] for details.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolSize};
+    /// let data = b"Data which does not fit into one 20x20 symbol, but into two";
+    /// let symbols = DataMatrix::encode_structured(data, SymbolSize::Square20).unwrap();
+    /// let mut bitmaps: Vec<_> = symbols.iter().map(|s| s.bitmap()).collect();
+    /// bitmaps.reverse();
+    /// assert_eq!(DataMatrix::decode_structured(&bitmaps).unwrap(), data);
+    /// ```
+    pub fn decode_structured(bitmaps: &[Bitmap<bool>]) -> Result<Vec<u8>, DecodingError> {
+        let codewords = bitmaps
+            .iter()
+            .map(|bitmap| Self::decode_data_codewords(bitmap.bits(), bitmap.width()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let codewords: Vec<&[u8]> = codewords.iter().map(Vec::as_slice).collect();
+        decodation::decode_structured(&codewords).map_err(DecodingError::DataDecoding)
     }
 
     /// Get the data in encoded form.
@@ -152,11 +344,140 @@ impl DataMatrix {
         &self.codewords[..self.num_data_codewords]
     }
 
+    /// Rough score how hard the symbol is to scan, between 0 (easy) and 1 (hard).
+    ///
+    /// This is a heuristic, see [SymbolSize::scan_difficulty].
+    pub fn estimate_scan_difficulty(&self) -> f32 {
+        self.size.scan_difficulty()
+    }
+
+    /// Count how often each value occurs in the [data codewords](Self::data_codewords).
+    ///
+    /// This is a diagnostic, e.g., to see why a symbol contains large areas
+    /// of identical modules. The padding codewords are included.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolSize};
+    /// let code = DataMatrix::encode(b"aaaa", SymbolSize::Square12).unwrap();
+    /// let histogram = code.codeword_histogram();
+    /// assert_eq!(histogram[b'a' as usize + 1], 4);
+    /// ```
+    pub fn codeword_histogram(&self) -> [usize; 256] {
+        let mut histogram = [0; 256];
+        for cw in self.data_codewords() {
+            histogram[*cw as usize] += 1;
+        }
+        histogram
+    }
+
     /// Create an abstract bitmap representing the Data Matrix.
     pub fn bitmap(&self) -> Bitmap<bool> {
         MatrixMap::new_with_codewords(&self.codewords, self.size).bitmap()
     }
 
+    /// Get the role of the module at `row` and `col` of the [bitmap](Self::bitmap).
+    ///
+    /// Renderers can use this to draw the finder and timing patterns differently
+    /// from the data, the dark and light state comes from the bitmap.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolSize, placement::ModuleRole};
+    /// let code = DataMatrix::encode(b"Foo", SymbolSize::Square12).unwrap();
+    /// for (row, col, dark) in code.bitmap().modules(0) {
+    ///     let color = match code.module_role(row, col) {
+    ///         ModuleRole::Finder | ModuleRole::Timing if dark => "blue",
+    ///         _ if dark => "black",
+    ///         _ => "white",
+    ///     };
+    /// }
+    /// assert_eq!(code.module_role(11, 0), ModuleRole::Finder);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is outside of the symbol.
+    pub fn module_role(&self, row: usize, col: usize) -> placement::ModuleRole {
+        placement::module_role(self.size, row, col)
+    }
+
+    /// Create a Data Matrix from already encoded data codewords.
+    ///
+    /// This can be used to render codewords produced by another encoder. The
+    /// error correction codewords are computed and appended.
+    ///
+    /// The number of codewords must match the data capacity of `size` exactly,
+    /// so padding must already be included. Otherwise
+    /// [DataEncodingError::WrongNumberOfCodewords] is returned. Illegal combinations
+    /// of leading FNC1, structured append, reader programming, and macro codewords
+    /// are rejected with [DataEncodingError::InvalidOptions].
+    pub fn from_codewords(
+        data_codewords: &[u8],
+        size: SymbolSize,
+    ) -> Result<DataMatrix, DataEncodingError> {
+        let num_data_codewords = size.num_data_codewords();
+        if data_codewords.len() != num_data_codewords {
+            return Err(DataEncodingError::WrongNumberOfCodewords);
+        }
+        encodation::validate_header(data_codewords)?;
+        let mut codewords = data_codewords.to_vec();
+        let ecc = errorcode::encode_error(&codewords, size);
+        codewords.extend_from_slice(&ecc);
+        Ok(DataMatrix {
+            size,
+            codewords,
+            num_data_codewords,
+        })
+    }
+
+    /// Encode data into buffers of the caller without allocating.
+    ///
+    /// This is for targets without a heap, or where encoding must not allocate.
+    /// All modes are used to find a minimal encodation for the fixed `size`, like
+    /// [DataMatrix::encode] with this size, but without macros. The planner keeps
+    /// its state on the stack, which needs a few dozen kilobytes.
+    ///
+    /// The data and error correction codewords are written to `codewords` which must
+    /// have the length [SymbolSize::num_codewords]. The bitmap is written row by row to
+    /// `modules`, `1` for a dark and `0` for a light module, the length must be the
+    /// product of the numbers of rows and columns of `size`. Otherwise
+    /// [DataEncodingError::InvalidOptions] is returned.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolSize};
+    /// let size = SymbolSize::Square12;
+    /// let mut codewords = [0; 12];
+    /// let mut modules = [0; 12 * 12];
+    /// DataMatrix::encode_without_alloc(b"12345", size, &mut codewords, &mut modules).unwrap();
+    /// assert_eq!(&codewords[..5], &[142, 164, 54, 129, 115]);
+    /// ```
+    ///
+    /// Fails with [DataEncodingError::TooMuchOrIllegalData] if the data does not
+    /// fit into `size`.
+    pub fn encode_without_alloc(
+        data: &[u8],
+        size: SymbolSize,
+        codewords: &mut [u8],
+        modules: &mut [u8],
+    ) -> Result<(), DataEncodingError> {
+        let setup = size.block_setup();
+        if codewords.len() != size.num_codewords() {
+            return Err(DataEncodingError::InvalidOptions(
+                "the codeword buffer does not have the number of codewords of the size",
+            ));
+        }
+        if modules.len() != setup.width * setup.height {
+            return Err(DataEncodingError::InvalidOptions(
+                "the module buffer does not have the number of modules of the size",
+            ));
+        }
+        let (data_part, ecc_part) = codewords.split_at_mut(size.num_data_codewords());
+        SliceEncoder::new(data, Symbols::Size(size), EncodationType::all(), data_part).encode()?;
+        ecc_part.fill(0);
+        errorcode::encode_error_into(data_part, size, ecc_part);
+        placement::place_into(codewords, size, modules);
+        Ok(())
+    }
+
     /// Encode data as a Data Matrix (ECC200).
     ///
     /// This is wrapper for [DataMatrixBuilder::encode].
@@ -169,6 +490,22 @@ impl DataMatrix {
             .encode(data)
     }
 
+    /// Encode several inputs as Data Matrix (ECC200).
+    ///
+    /// This is wrapper for [DataMatrixBuilder::encode_batch].
+    pub fn encode_batch<'a, I, L>(
+        inputs: I,
+        symbol_list: L,
+    ) -> Vec<Result<DataMatrix, DataEncodingError>>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+        L: Into<SymbolList>,
+    {
+        DataMatrixBuilder::new()
+            .with_symbol_list(symbol_list)
+            .encode_batch(inputs)
+    }
+
     /// Encode a string as a Data Matrix (ECC200).
     ///
     /// This is wrapper for [DataMatrixBuilder::encode_str].
@@ -180,28 +517,101 @@ impl DataMatrix {
             .with_symbol_list(symbol_list)
             .encode_str(text)
     }
+
+    /// Encode data split over up to 16 symbols using structured append.
+    ///
+    /// This is wrapper for [DataMatrixBuilder::encode_structured].
+    pub fn encode_structured<I: Into<SymbolList>>(
+        data: &[u8],
+        symbol_list: I,
+    ) -> Result<Vec<DataMatrix>, DataEncodingError> {
+        DataMatrixBuilder::new()
+            .with_symbol_list(symbol_list)
+            .encode_structured(data)
+    }
+
+    /// Encode a command as a reader programming symbol.
+    ///
+    /// This is wrapper for [reader_programming::Command::encode].
+    pub fn encode_reader_programming<I: Into<SymbolList>>(
+        command: &[u8],
+        symbol_list: I,
+    ) -> Result<DataMatrix, DataEncodingError> {
+        reader_programming::Command::new(command)?.encode(symbol_list)
+    }
+
+    /// Encode GS1 elements, pairs of an AI and its value, as a GS1 Data Matrix.
+    ///
+    /// This is wrapper for [DataMatrixBuilder::encode_gs1].
+    pub fn encode_gs1<I: Into<SymbolList>>(
+        elements: &[(&str, &str)],
+        symbol_list: I,
+    ) -> Result<DataMatrix, DataEncodingError> {
+        DataMatrixBuilder::new()
+            .with_symbol_list(symbol_list)
+            .encode_gs1(elements)
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Builder for encoding a Data Matrix with more control.
 pub struct DataMatrixBuilder {
+    #[cfg_attr(feature = "serde", serde(with = "serde_impl::encodation_types"))]
     encodation_types: FlagSet<EncodationType>,
     symbol_list: SymbolList,
     use_macros: bool,
+    gs1: bool,
+    eci: Option<u32>,
+    reader_programming: bool,
+    strict: bool,
+    prefer_text: bool,
+    effort: Effort,
+    start_mode: Option<EncodationType>,
+    exclusive: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "serde_impl::deserialize_headroom")
+    )]
+    headroom: f32,
+    plan: Option<Vec<(usize, EncodationType)>>,
+    file_id: Option<(u8, u8)>,
+    robustness: Robustness,
+    charset_policy: CharsetPolicy,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    randomizer: RandomizerRef,
 }
 
+// headroom is never NaN, see data::headroom_or_invalid
+impl Eq for DataMatrixBuilder {}
+
 impl DataMatrixBuilder {
     pub fn new() -> Self {
         Self {
             encodation_types: EncodationType::all(),
             symbol_list: SymbolList::default(),
             use_macros: true,
+            gs1: false,
+            eci: None,
+            reader_programming: false,
+            strict: false,
+            prefer_text: false,
+            effort: Effort::Exhaustive,
+            start_mode: None,
+            exclusive: false,
+            headroom: 0.0,
+            plan: None,
+            file_id: None,
+            robustness: Robustness::Min,
+            charset_policy: CharsetPolicy::Eci,
+            randomizer: RandomizerRef::default(),
         }
     }
 
     /// Specify which encodation can be used.
     ///
-    /// By default all encodation types are enabled.
+    /// By default all encodation types are enabled. This resets a start mode
+    /// set with [Encodation::StartWith].
     ///
     /// # Example
     ///
@@ -215,17 +625,326 @@ impl DataMatrixBuilder {
     pub fn with_encodation_types(self, types: impl Into<FlagSet<EncodationType>>) -> Self {
         Self {
             encodation_types: types.into(),
+            start_mode: None,
             ..self
         }
     }
 
     /// Whether to use macros or not.
     ///
+    /// Data in the envelope of ISO/IEC 15434 for format 05 or 06, i.e., starting with
+    /// `[)>` RS `05` GS (or `06`) and ending with RS EOT, is then encoded with the
+    /// Macro 05 or Macro 06 codeword instead of the header and trailer.
+    /// The decoder puts them back.
+    ///
     /// This is enabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, DataMatrixBuilder};
+    /// let data = b"[)>\x1E05\x1D17V1234\x1E\x04";
+    /// let code = DataMatrixBuilder::new().encode(data).unwrap();
+    /// assert_eq!(code.data_codewords()[0], 236);
+    /// assert_eq!(DataMatrix::decode_bitmap(&code.bitmap()).unwrap(), data);
+    /// ```
     pub fn with_macros(self, use_macros: bool) -> Self {
         Self { use_macros, ..self }
     }
 
+    /// Mark the data as GS1 by an FNC1 in the first position.
+    ///
+    /// The data must already be formatted with the application identifiers,
+    /// see [gs1::element_string]. Group separators (byte 29) in the data are
+    /// encoded as FNC1. If an ECI is needed for the character set, e.g., by [encode_str](Self::encode_str),
+    /// it is placed before the FNC1. Macros are not used for GS1 data.
+    ///
+    /// This is disabled by default.
+    pub fn with_gs1(self, gs1: bool) -> Self {
+        Self { gs1, ..self }
+    }
+
+    /// Mark the data as encoded in the character set of the given ECI.
+    ///
+    /// The ECI designator is written before the data, the bytes are encoded
+    /// unchanged. For example, 26 is UTF-8, 20 Shift JIS, and 3 Latin-1. This applies
+    /// to all methods taking bytes, e.g., [encode](Self::encode) and
+    /// [encode_structured](Self::encode_structured), where the ECI is repeated
+    /// in every symbol.
+    ///
+    /// [encode_str](Self::encode_str) picks the ECI itself if none is set, see
+    /// [with_charset_policy](Self::with_charset_policy). With
+    /// the UTF-8 ECI it always encodes the UTF-8 bytes, even if the text is
+    /// Latin-1, and with any other ECI it fails.
+    ///
+    /// This is `None` by default, i.e., no ECI and the data is Latin-1.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::DataMatrixBuilder;
+    /// // "テスト" in Shift JIS
+    /// let code = DataMatrixBuilder::new()
+    ///     .with_eci(Some(20))
+    ///     .encode(b"\x83\x65\x83\x58\x83\x67")
+    ///     .unwrap();
+    /// assert_eq!(code.data_codewords()[..2], [241, 21]);
+    /// ```
+    pub fn with_eci(self, eci: Option<u32>) -> Self {
+        Self { eci, ..self }
+    }
+
+    /// Set how [encode_str](Self::encode_str) handles text which is not Latin-1.
+    ///
+    /// Text which can be converted to Latin-1 is always encoded as such. For other
+    /// text the UTF-8 ECI is used by default, which not all decoders support. The
+    /// encoding can instead fail, or the text can be approximated in Latin-1, e.g.,
+    /// for names with accents on a label. The policy is ignored if an
+    /// [ECI is set](Self::with_eci).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{CharsetPolicy, DataMatrix, DataMatrixBuilder};
+    /// let code = DataMatrixBuilder::new()
+    ///     .with_charset_policy(CharsetPolicy::Transliterate)
+    ///     .encode_str("Paweł Dvořák")
+    ///     .unwrap();
+    /// let data = DataMatrix::decode_bitmap(&code.bitmap()).unwrap();
+    /// assert_eq!(data, b"Pawel Dvor\xe1k");
+    /// ```
+    pub fn with_charset_policy(self, charset_policy: CharsetPolicy) -> Self {
+        Self {
+            charset_policy,
+            ..self
+        }
+    }
+
+    /// Mark the symbol as reader programming.
+    ///
+    /// Such a symbol configures the scanner instead of transmitting data,
+    /// the content is a command of the scanner's manufacturer. See the
+    /// [reader_programming] module. It can not be combined with GS1 or structured append,
+    /// and macros are not used.
+    ///
+    /// This is disabled by default.
+    pub fn with_reader_programming(self, reader_programming: bool) -> Self {
+        Self {
+            reader_programming,
+            ..self
+        }
+    }
+
+    /// Only use the regular end of data encodation in every mode.
+    ///
+    /// The specification allows to leave out the UNLATCH at the end of C40, Text
+    /// and X12 if the last codeword of the symbol is encoded in ASCII. Not all
+    /// decoders handle this correctly, so the strict mode always writes the UNLATCH.
+    /// This may result in a bigger symbol.
+    ///
+    /// EDIFACT also ends without UNLATCH if the rest fits into the last one or
+    /// two codewords of the symbol, which are then read as ASCII. The strict mode
+    /// writes the UNLATCH and uses a symbol with at least two more codewords.
+    ///
+    /// This is disabled by default.
+    pub fn with_strict(self, strict: bool) -> Self {
+        Self { strict, ..self }
+    }
+
+    /// Prefer Text over C40 for data like natural language.
+    ///
+    /// The encodation is always chosen to minimize the number of codewords,
+    /// so Text already wins for mostly lowercase data. This only changes which of
+    /// C40 and Text is picked if both result in the same size, e.g., for a
+    /// mix of upper and lowercase letters.
+    ///
+    /// This is disabled by default.
+    pub fn with_text_preference(self, prefer_text: bool) -> Self {
+        Self {
+            prefer_text,
+            ..self
+        }
+    }
+
+    /// Limit the work spent on finding a small encodation.
+    ///
+    /// With less [Effort] the modes are chosen by heuristics, the symbols
+    /// may be bigger then. The default is [Effort::Exhaustive], which finds
+    /// a minimal encodation.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, Effort, SymbolList, SymbolSize};
+    /// let builder = DataMatrixBuilder::new()
+    ///     .with_symbol_list(SymbolList::default().enforce_square());
+    /// let data = b"ABC>DEF>GHI>JKL>MNO>PQR>STU>VWX>";
+    /// let fast = builder.clone().with_effort(Effort::Fast).encode(data);
+    /// assert_eq!(fast.unwrap().size, SymbolSize::Square24);
+    /// let minimal = builder.encode(data);
+    /// assert_eq!(minimal.unwrap().size, SymbolSize::Square22);
+    /// ```
+    pub fn with_effort(self, effort: Effort) -> Self {
+        Self { effort, ..self }
+    }
+
+    /// Leave a fraction of the data codewords unused.
+    ///
+    /// A symbol size is picked where at least `headroom` (between 0 and 1) of
+    /// the data capacity is free, e.g., 0.2 for 20% spare codewords. If the
+    /// largest allowed symbol can not provide this, encoding fails with
+    /// [DataEncodingError::TooMuchData]. The modes are also chosen for the
+    /// reduced capacity.
+    ///
+    /// The default is 0. Encoding fails with [DataEncodingError::InvalidOptions]
+    /// if `headroom` is not in the range `0.0..1.0`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, SymbolSize};
+    /// let code = DataMatrixBuilder::new()
+    ///     .with_headroom(0.5)
+    ///     .encode(b"123456")
+    ///     .unwrap();
+    /// // three codewords are needed, so at least six are required
+    /// assert_eq!(code.size, SymbolSize::Square14);
+    /// ```
+    pub fn with_headroom(self, headroom: f32) -> Self {
+        Self {
+            headroom: data::headroom_or_invalid(headroom),
+            ..self
+        }
+    }
+
+    /// Use another source for the padding codewords after the first PAD.
+    ///
+    /// The default is [Randomize253](data::Randomize253) of the specification. With
+    /// [NoRandomization](data::NoRandomization) the padding consists of plain PAD
+    /// codewords, which helps to compare symbols with the debug output of other tools.
+    ///
+    /// **Warning:** Symbols with another padding do not conform to the specification
+    /// and should not be printed. The randomizer is not serialized.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, SymbolSize, data::NoRandomization};
+    /// let code = DataMatrixBuilder::new()
+    ///     .with_randomizer(&NoRandomization)
+    ///     .with_symbol_list(SymbolSize::Square12)
+    ///     .encode(b"A")
+    ///     .unwrap();
+    /// assert_eq!(code.data_codewords(), [66, 129, 129, 129, 129]);
+    /// ```
+    pub fn with_randomizer(self, randomizer: &'static dyn data::Randomizer) -> Self {
+        Self {
+            randomizer: RandomizerRef(randomizer),
+            ..self
+        }
+    }
+
+    /// Pick a bigger symbol size for more error correction.
+    ///
+    /// The error correction is fixed for each symbol size, so with a higher
+    /// [Robustness] a bigger symbol than necessary is chosen from the
+    /// [symbol list](Self::with_symbol_list). The unused space is filled with padding.
+    ///
+    /// The default is [Robustness::Min].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, Robustness, SymbolSize};
+    /// let code = DataMatrixBuilder::new()
+    ///     .with_robustness(Robustness::High)
+    ///     .encode(b"12")
+    ///     .unwrap();
+    /// assert_eq!(code.size, SymbolSize::Square12);
+    /// ```
+    pub fn with_robustness(self, robustness: Robustness) -> Self {
+        Self { robustness, ..self }
+    }
+
+    /// Follow the given encodation plan instead of computing an optimal one.
+    ///
+    /// This can be used to reproduce the codewords of another encoder. The plan has
+    /// the format returned by [data::encodation_plan], the enabled
+    /// [encodation types](Self::with_encodation_types) are ignored. If macros are used,
+    /// the characters left are counted without the macro header and trailer.
+    ///
+    /// Encoding fails with [DataEncodingError::InvalidOptions] if the plan is malformed
+    /// or asks for a mode switch where it is not possible, e.g., inside of
+    /// an X12 triple. If a mode can not encode the data assigned to it,
+    /// [DataEncodingError::TooMuchOrIllegalData] is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, data::EncodationType};
+    /// let plan = vec![(6, EncodationType::C40), (0, EncodationType::C40)];
+    /// let datamatrix = DataMatrixBuilder::new()
+    ///     .with_encodation_plan(plan)
+    ///     .encode(b"ABCDEF")
+    ///     .unwrap();
+    /// ```
+    pub fn with_encodation_plan(self, plan: Vec<(usize, EncodationType)>) -> Self {
+        Self {
+            plan: Some(plan),
+            start_mode: None,
+            ..self
+        }
+    }
+
+    /// Choose how the encodation modes are selected.
+    ///
+    /// This replaces the settings of [with_encodation_types](Self::with_encodation_types)
+    /// and [with_encodation_plan](Self::with_encodation_plan). With
+    /// [Encodation::StartWith] the rest is planned with all modes, if the mode can not
+    /// encode the start of the data, [DataEncodingError::TooMuchOrIllegalData] is returned.
+    ///
+    /// The default is [Encodation::Auto].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, Encodation, EncodationType};
+    /// let (_, segments) = DataMatrixBuilder::new()
+    ///     .with_encodation(Encodation::StartWith(EncodationType::Base256))
+    ///     .encode_with_segments(b"ABCDEFGHIJKL")
+    ///     .unwrap();
+    /// assert_eq!(segments[0], (0, EncodationType::Base256));
+    /// ```
+    pub fn with_encodation(self, encodation: Encodation) -> Self {
+        let exclusive = matches!(encodation, Encodation::Exclusive(_));
+        let (encodation_types, start_mode, plan) = match encodation {
+            Encodation::Auto => (EncodationType::all(), None, None),
+            Encodation::Only(mode) | Encodation::Exclusive(mode) => (mode.into(), None, None),
+            Encodation::StartWith(mode) => (EncodationType::all(), Some(mode), None),
+            Encodation::Plan(plan) => (EncodationType::all(), None, Some(plan)),
+        };
+        Self {
+            encodation_types,
+            start_mode,
+            exclusive,
+            plan,
+            ..self
+        }
+    }
+
+    /// Set the file identification used by [encode_structured](Self::encode_structured).
+    ///
+    /// Both codewords must be between 1 and 254. By default the identification
+    /// is derived from the data with a hash, so the same data always gets the same one
+    /// and re-encoding gives identical symbols. Different data gets different
+    /// identifications with high probability, but there are only 254 * 254 of them.
+    pub fn with_file_id(self, file_id: (u8, u8)) -> Self {
+        Self {
+            file_id: Some(file_id),
+            ..self
+        }
+    }
+
     /// Specify the list of allowed symbols sizes.
     ///
     /// Uses [SymbolList::default()] by default.
@@ -254,41 +973,607 @@ impl DataMatrixBuilder {
     ///
     /// If the string can be converted to Latin-1, no ECI is used, otherwise
     /// an initial UTF8 ECI is inserted. Please check if your decoder has support
-    /// for that. See the notes on the [module documentation](crate) for more details.
+    /// for that, or pick another [charset policy](Self::with_charset_policy).
+    /// See the notes on the [module documentation](crate) for more details.
+    ///
+    /// With the ECI the UTF-8 bytes are encoded unchanged, multi-byte sequences
+    /// are not split or reinterpreted. The encoder usually picks Base256 for them,
+    /// but any of the [enabled encodation types](Self::with_encodation_types) can be
+    /// used since all of them can represent the upper byte range (except EDIFACT and X12).
+    /// The original text can be recovered with [data::decode_str].
     pub fn encode_str(self, text: &str) -> Result<DataMatrix, DataEncodingError> {
-        if let Some(data) = data::utf8_to_latin1(text) {
-            // string is latin1
-            self.encode_eci(&data, None)
-        } else {
-            // encode with UTF8 ECI
-            self.encode_eci(text.as_bytes(), Some(decodation::ECI_UTF8))
-        }
+        let (data, eci) = self.str_data(text)?;
+        self.encode_eci(&data, eci)
+            .map_err(|err| str_error(text, eci, err))
     }
 
-    #[doc(hidden)]
-    pub fn encode_eci(
-        self,
-        data: &[u8],
+    /// Encode GS1 elements, pairs of an AI and its value, as a GS1 Data Matrix.
+    ///
+    /// The elements are validated and joined by [gs1::element_string], and
+    /// encoded with [GS1 enabled](Self::with_gs1).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, DataMatrix};
+    /// let code = DataMatrixBuilder::new()
+    ///     .encode_gs1(&[("01", "09501101530003"), ("10", "AB-123"), ("17", "270630")])
+    ///     .unwrap();
+    /// let data = DataMatrix::decode_bitmap(&code.bitmap()).unwrap();
+    /// assert_eq!(data, b"010950110153000310AB-123\x1d17270630");
+    /// ```
+    pub fn encode_gs1(self, elements: &[(&str, &str)]) -> Result<DataMatrix, DataEncodingError> {
+        let data = gs1::element_string(elements)?;
+        self.with_gs1(true).encode(&data)
+    }
+
+    /// Encode data into a caller provided buffer.
+    ///
+    /// This works like [encode()](Self::encode), but all codewords, including
+    /// error correction, are written to `out`. The buffer is cleared first and
+    /// contains exactly the codewords of the symbol afterwards, so its allocation
+    /// can be reused when encoding many symbols. The chosen symbol size
+    /// is returned, use [MatrixMap::new_with_codewords] to create the bitmap.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, placement::MatrixMap};
+    /// let builder = DataMatrixBuilder::new();
+    /// let mut codewords = Vec::new();
+    /// for data in [b"first", b"other"] {
+    ///     let size = builder.encode_into(data, &mut codewords).unwrap();
+    ///     let bitmap = MatrixMap::new_with_codewords(&codewords, size).bitmap();
+    /// }
+    /// ```
+    pub fn encode_into(
+        &self,
+        data: &[u8],
+        out: &mut Vec<u8>,
+    ) -> Result<SymbolSize, DataEncodingError> {
+        self.encode_eci_into(data, None, out)
+    }
+
+    /// Encode the data codewords into a vector with fixed capacity, without allocating.
+    ///
+    /// The codewords before the error correction are written to `out`, including the
+    /// padding, like [encode_data_codewords](Self::encode_data_codewords). Only symbol
+    /// sizes with at most `N` data codewords are used, see [SymbolSize::num_data_codewords],
+    /// e.g., `N = 1558` allows all sizes up to 144x144, `N = 114` up to 40x40, and
+    /// `N = 44` up to 26x26.
+    ///
+    /// The encodation is minimal as with [Effort::Exhaustive], the encodation types,
+    /// the symbol list, the headroom, and the options for Text and the end of data
+    /// are respected. Macros, the ECI, and the FNC1 for GS1 are not written. The
+    /// planner keeps its state on the stack, which needs a few dozen kilobytes.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, SymbolSize};
+    /// let mut codewords = heapless::Vec::<u8, 1558>::new();
+    /// let size = DataMatrixBuilder::new().encode_into_heapless(b"Hello", &mut codewords).unwrap();
+    /// assert_eq!(size, SymbolSize::Square12);
+    /// assert_eq!(codewords[..5], [73, 102, 109, 109, 112]);
+    /// ```
+    #[cfg(feature = "heapless")]
+    pub fn encode_into_heapless<const N: usize>(
+        &self,
+        data: &[u8],
+        out: &mut heapless::Vec<u8, N>,
+    ) -> Result<SymbolSize, DataEncodingError> {
+        data::check_headroom(self.headroom)?;
+        if self.symbol_list.is_empty() {
+            return Err(DataEncodingError::SymbolListEmpty);
+        }
+        out.clear();
+        out.resize(N, 0)
+            .map_err(|_| DataEncodingError::InternalError("codewords exceed the capacity"))?;
+        let symbols = Symbols::List(&self.symbol_list, N);
+        let mut encoder = SliceEncoder::new(data, symbols, self.encodation_types, out);
+        encoder.set_strict(self.strict);
+        encoder.set_prefer_text(self.prefer_text);
+        encoder.set_headroom(self.headroom);
+        encoder.set_randomizer(self.randomizer.0);
+        let result = encoder.encode();
+        match result {
+            Ok(size) => {
+                out.truncate(size.num_data_codewords());
+                Ok(size)
+            }
+            Err(DataEncodingError::TooMuchOrIllegalData) => {
+                out.clear();
+                Err(data::encoding_failure_without_alloc(
+                    data,
+                    symbols,
+                    self.encodation_types,
+                    self.headroom,
+                ))
+            }
+            Err(err) => {
+                out.clear();
+                Err(err)
+            }
+        }
+    }
+
+    /// Find the smallest symbol size which fits all of the `inputs`.
+    ///
+    /// This is useful if a batch of labels should all have the same size. Restrict
+    /// the symbol list first, e.g., with [SymbolList::enforce_square], and then
+    /// encode every input with the returned size.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, SymbolList, SymbolSize};
+    /// let builder = DataMatrixBuilder::new().with_symbol_list(SymbolList::default().enforce_square());
+    /// let inputs: [&[u8]; 3] = [b"1", b"Hello, World!", b"ABC"];
+    /// let size = builder.uniform_size(&inputs).unwrap();
+    /// assert_eq!(size, SymbolSize::Square16);
+    /// for data in inputs {
+    ///     let code = builder.clone().with_symbol_list(size).encode(data).unwrap();
+    /// }
+    /// ```
+    ///
+    /// Fails if one of the inputs can not be encoded, an empty batch yields
+    /// the smallest symbol size.
+    pub fn uniform_size(&self, inputs: &[&[u8]]) -> Result<SymbolSize, DataEncodingError> {
+        let mut codewords = Vec::new();
+        let mut uniform = self
+            .symbol_list
+            .iter()
+            .next()
+            .ok_or(DataEncodingError::SymbolListEmpty)?;
+        for data in inputs {
+            uniform = uniform.max(self.encode_into(data, &mut codewords)?);
+        }
+        Ok(uniform)
+    }
+
+    /// Encode each of the `inputs` with the options of this builder.
+    ///
+    /// The results are in the order of the inputs and match [encode](Self::encode),
+    /// a failing input does not stop the batch. A scratch buffer is reused for the
+    /// codewords, so each symbol is allocated with its final size only. With the
+    /// feature `rayon` the inputs are encoded in parallel.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, SymbolSize};
+    /// let labels = ["PKG-0001", "PKG-0002", "PKG-0003"];
+    /// let codes = DataMatrixBuilder::new()
+    ///     .with_symbol_list(SymbolSize::Square14)
+    ///     .encode_batch(labels.iter().map(|label| label.as_bytes()));
+    /// assert_eq!(codes.len(), 3);
+    /// assert!(codes.iter().all(Result::is_ok));
+    /// ```
+    pub fn encode_batch<'a, I>(&self, inputs: I) -> Vec<Result<DataMatrix, DataEncodingError>>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+
+            let inputs: Vec<&[u8]> = inputs.into_iter().collect();
+            inputs
+                .par_iter()
+                .map_init(Vec::new, |scratch, data| {
+                    self.encode_with_scratch(data, scratch)
+                })
+                .collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            let mut scratch = Vec::new();
+            inputs
+                .into_iter()
+                .map(|data| self.encode_with_scratch(data, &mut scratch))
+                .collect()
+        }
+    }
+
+    fn encode_with_scratch(
+        &self,
+        data: &[u8],
+        scratch: &mut Vec<u8>,
+    ) -> Result<DataMatrix, DataEncodingError> {
+        let size = self.encode_into(data, scratch)?;
+        Ok(DataMatrix {
+            size,
+            codewords: scratch.as_slice().into(),
+            num_data_codewords: size.num_data_codewords(),
+        })
+    }
+
+    /// Encode `data` and return the modes used for it.
+    ///
+    /// Every entry contains the position in `data` where a mode starts, and the mode.
+    /// This can be used to format a human readable text, e.g., to show
+    /// Base256 segments as hex. A macro header at the start is not included in any segment.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, EncodationType};
+    /// let (code, segments) = DataMatrixBuilder::new()
+    ///     .encode_with_segments(b"ab\xe4\xe4\xe4\xe4\xe4ABCDEFGHIJKL")
+    ///     .unwrap();
+    /// assert_eq!(segments, [(0, EncodationType::Base256), (7, EncodationType::C40)]);
+    /// ```
+    pub fn encode_with_segments(
+        self,
+        data: &[u8],
+    ) -> Result<(DataMatrix, Vec<(usize, EncodationType)>), DataEncodingError> {
+        let mut codewords = Vec::new();
+        let encoded = self.encode_part_into(data, None, None, &mut codewords)?;
+        let code = DataMatrix {
+            size: encoded.size,
+            codewords,
+            num_data_codewords: encoded.size.num_data_codewords(),
+        };
+        Ok((code, encoded.segments))
+    }
+
+    /// Encode `data` and report the modes and how the symbol is filled.
+    ///
+    /// This helps to tune a data format, e.g., to see why a symbol got bigger.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, EncodationType, SymbolSize};
+    /// let (code, report) = DataMatrixBuilder::new()
+    ///     .encode_with_report(b"1234567890abcdefghij")
+    ///     .unwrap();
+    /// assert_eq!(report.size(), SymbolSize::Rect12x26);
+    /// assert_eq!(
+    ///     report.segments(),
+    ///     [(0..10, EncodationType::Ascii), (10..20, EncodationType::Text)]
+    /// );
+    /// assert_eq!(report.num_encoded_codewords(), 15);
+    /// assert_eq!(report.num_padding_codewords(), 1);
+    /// assert_eq!(report.num_error_codewords(), 14);
+    /// ```
+    pub fn encode_with_report(
+        self,
+        data: &[u8],
+    ) -> Result<(DataMatrix, EncodationReport), DataEncodingError> {
+        let mut codewords = Vec::new();
+        let encoded = self.encode_part_into(data, None, None, &mut codewords)?;
+        let end = match codewords.first() {
+            Some(&(encodation::MACRO05 | encodation::MACRO06)) => {
+                data.len() - encodation::MACRO_TRAIL.len()
+            }
+            _ => data.len(),
+        };
+        let ends = encoded.segments.iter().skip(1).map(|(start, _)| *start);
+        let segments = encoded
+            .segments
+            .iter()
+            .zip(ends.chain([end]))
+            .map(|((start, mode), end)| (*start..end, *mode))
+            .collect();
+        let report = EncodationReport {
+            size: encoded.size,
+            segments,
+            num_unpadded: encoded.num_unpadded,
+        };
+        let code = DataMatrix {
+            size: encoded.size,
+            codewords,
+            num_data_codewords: encoded.size.num_data_codewords(),
+        };
+        Ok((code, report))
+    }
+
+    /// Compute the number of data codewords needed to encode `data`.
+    ///
+    /// This is the length of the encoding before padding, the smallest symbol
+    /// with at least this many data codewords is picked when encoding. The symbol
+    /// list only matters for the end of data rules, which may save an UNLATCH
+    /// if a symbol is filled completely. Error correction is not included.
+    /// [EncodationType::estimate_codewords] computes this for a single mode.
+    ///
+    /// If the data does not fit into the symbol list or the headroom, the count
+    /// is computed for [SymbolList::all] without headroom instead. This fails with
+    /// [DataEncodingError::TooMuchData] only if not even the biggest symbol is enough.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, SymbolSize};
+    /// // two digits per codeword
+    /// assert_eq!(DataMatrixBuilder::new().required_data_codewords(b"123456"), Ok(3));
+    /// // more than the 3 data codewords of 10x10
+    /// let builder = DataMatrixBuilder::new().with_symbol_list(SymbolSize::Square10);
+    /// assert_eq!(builder.required_data_codewords(b"ABCDEFGHIJ"), Ok(8));
+    /// ```
+    pub fn required_data_codewords(&self, data: &[u8]) -> Result<usize, DataEncodingError> {
+        let mut codewords = Vec::new();
+        let encoded = match self.encode_data_into(data, None, None, &mut codewords) {
+            Err(DataEncodingError::TooMuchData { constraint, .. })
+                if constraint != Constraint::EncodationTypes =>
+            {
+                let unbounded = Self {
+                    symbol_list: SymbolList::all(),
+                    headroom: 0.0,
+                    ..self.clone()
+                };
+                unbounded.encode_data_into(data, None, None, &mut codewords)?
+            }
+            result => result?,
+        };
+        Ok(encoded.num_unpadded)
+    }
+
+    /// Encode `data` into the data codewords of a symbol, including the padding.
+    ///
+    /// This is the first stage of [encode](Self::encode), use
+    /// [errorcode::encode_error] and [placement::place_codewords] for the others,
+    /// e.g., to inspect or change the codewords between the stages.
+    ///
+    /// ```rust
+    /// # use datamatrix::{errorcode, placement, DataMatrixBuilder};
+    /// let builder = DataMatrixBuilder::new();
+    /// let (mut codewords, size) = builder.encode_data_codewords(b"Hello").unwrap();
+    /// assert_eq!(codewords.len(), size.num_data_codewords());
+    /// let ecc = errorcode::encode_error(&codewords, size);
+    /// codewords.extend_from_slice(&ecc);
+    /// let bitmap = placement::place_codewords(&codewords, size);
+    /// assert_eq!(bitmap.bits(), builder.encode(b"Hello").unwrap().bitmap().bits());
+    /// ```
+    pub fn encode_data_codewords(
+        &self,
+        data: &[u8],
+    ) -> Result<(Vec<u8>, SymbolSize), DataEncodingError> {
+        let mut codewords = Vec::new();
+        let size = self.encode_into(data, &mut codewords)?;
+        codewords.truncate(size.num_data_codewords());
+        Ok((codewords, size))
+    }
+
+    /// Create an encoder for serial numbers of the given width following `prefix`.
+    ///
+    /// See [SerialEncoder] for details. Fails if the data does not fit into any of
+    /// the symbols, or if `width` is not between 1 and 20.
+    pub fn serial_encoder(
+        self,
+        prefix: &[u8],
+        width: usize,
+    ) -> Result<SerialEncoder, DataEncodingError> {
+        SerialEncoder::new(self, prefix, width)
+    }
+
+    /// Create an encoder for data which is fed in chunks.
+    ///
+    /// See [StreamingEncoder] for details.
+    pub fn streaming_encoder(self) -> StreamingEncoder {
+        StreamingEncoder::new(self)
+    }
+
+    /// Encode data split over up to 16 symbols using structured append.
+    ///
+    /// Each symbol is filled as much as possible, and the size is picked
+    /// independently for every symbol, so the last one is usually smaller. The
+    /// headers are the same for all sizes. All symbols carry the same
+    /// [file identification](Self::with_file_id). If the data fits into one symbol,
+    /// a single symbol without structured append header is returned.
+    /// With [GS1](Self::with_gs1) the FNC1 is only written in the first symbol.
+    ///
+    /// Macros and a given [encodation plan](Self::with_encodation_plan) can not be used.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, SymbolSize};
+    /// let symbols = DataMatrixBuilder::new()
+    ///     .with_symbol_list(SymbolSize::Square20)
+    ///     .encode_structured(b"Data which does not fit into one 20x20 symbol")
+    ///     .unwrap();
+    /// assert_eq!(symbols.len(), 2);
+    /// ```
+    pub fn encode_structured(self, data: &[u8]) -> Result<Vec<DataMatrix>, DataEncodingError> {
+        structured_append::encode(&self, data, self.eci)
+    }
+
+    /// Read data from `reader` and split it into at most `max_symbols` symbols.
+    ///
+    /// Returns the size and the data codewords of each symbol, use
+    /// [DataMatrix::from_codewords] to get the symbols.
+    ///
+    /// Data up to the [capacity](SymbolList::max_capacity) of one symbol is split like
+    /// with [encode_structured](Self::encode_structured). Longer data is read one symbol
+    /// at a time and each symbol holds one Base256 segment, so the whole input is
+    /// never kept in memory. Only as many bytes as `max_symbols` can hold are read
+    /// before encoding fails with [DataEncodingError::TooMuchOrIllegalData].
+    /// The structured append headers are completed at the end, the derived
+    /// [file identification](Self::with_file_id) depends on all the data.
+    ///
+    /// `max_symbols` must be between 1 and 16. Errors of the reader are returned
+    /// as [DataEncodingError::Io].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, DataMatrixBuilder};
+    /// let file = std::io::Cursor::new(vec![0xab; 4000]);
+    /// let parts = DataMatrixBuilder::new().encode_reader(file, 16).unwrap();
+    /// assert_eq!(parts.len(), 3);
+    /// let (size, codewords) = &parts[0];
+    /// let symbol = DataMatrix::from_codewords(codewords, *size).unwrap();
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn encode_reader<R: std::io::Read>(
+        self,
+        reader: R,
+        max_symbols: usize,
+    ) -> Result<Vec<(SymbolSize, Vec<u8>)>, DataEncodingError> {
+        structured_append::encode_reader(&self, reader, max_symbols)
+    }
+
+    /// Encode a string split over up to 16 symbols using structured append.
+    ///
+    /// This works like [encode_str](Self::encode_str) and [encode_structured](Self::encode_structured).
+    /// Each symbol is decoded on its own, so if the text is not Latin 1 the UTF-8 ECI
+    /// is repeated after the header in every symbol. The text is split
+    /// between characters then.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrixBuilder, SymbolSize};
+    /// let symbols = DataMatrixBuilder::new()
+    ///     .with_symbol_list(SymbolSize::Square26)
+    ///     .encode_str_structured("Ελληνικό κείμενο σε δύο σύμβολα")
+    ///     .unwrap();
+    /// assert_eq!(symbols.len(), 2);
+    /// ```
+    pub fn encode_str_structured(self, text: &str) -> Result<Vec<DataMatrix>, DataEncodingError> {
+        let (data, eci) = self.str_data(text)?;
+        structured_append::encode(&self, &data, eci).map_err(|err| str_error(text, eci, err))
+    }
+
+    /// Get the bytes and the ECI to encode a string with.
+    fn str_data<'a>(
+        &self,
+        text: &'a str,
+    ) -> Result<(Cow<'a, [u8]>, Option<u32>), DataEncodingError> {
+        match self.eci {
+            None => match (data::utf8_to_latin1(text), self.charset_policy) {
+                // string is latin1
+                (Some(data), _) => Ok((Cow::Owned(data), None)),
+                // encode with UTF8 ECI
+                (None, CharsetPolicy::Eci) => {
+                    Ok((Cow::Borrowed(text.as_bytes()), Some(decodation::ECI_UTF8)))
+                }
+                (None, CharsetPolicy::Error) => {
+                    let mut buf = [0; 4];
+                    let (position, character) = text
+                        .char_indices()
+                        .find(|(_, ch)| data::utf8_to_latin1(ch.encode_utf8(&mut buf)).is_none())
+                        .ok_or(DataEncodingError::InternalError("text is Latin-1"))?;
+                    Err(DataEncodingError::IllegalCharacter {
+                        position,
+                        character,
+                    })
+                }
+                (None, CharsetPolicy::Transliterate) => {
+                    Ok((Cow::Owned(data::utf8_to_latin1_lossy(text)), None))
+                }
+            },
+            Some(decodation::ECI_UTF8) => {
+                Ok((Cow::Borrowed(text.as_bytes()), Some(decodation::ECI_UTF8)))
+            }
+            Some(_) => Err(DataEncodingError::InvalidOptions(
+                "a string can only be encoded with the UTF-8 ECI",
+            )),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn encode_eci(
+        self,
+        data: &[u8],
         eci: Option<u32>,
     ) -> Result<DataMatrix, DataEncodingError> {
-        let (mut codewords, size) = data::encode_data(
+        let mut codewords = Vec::new();
+        let size = self.encode_eci_into(data, eci, &mut codewords)?;
+        Ok(DataMatrix {
+            size,
+            codewords,
+            num_data_codewords: size.num_data_codewords(),
+        })
+    }
+
+    fn encode_eci_into(
+        &self,
+        data: &[u8],
+        eci: Option<u32>,
+        out: &mut Vec<u8>,
+    ) -> Result<SymbolSize, DataEncodingError> {
+        Ok(self.encode_part_into(data, eci, None, out)?.size)
+    }
+
+    /// Encode one symbol, optionally with a structured append header.
+    fn encode_part_into(
+        &self,
+        data: &[u8],
+        eci: Option<u32>,
+        structured_append: Option<[u8; 3]>,
+        out: &mut Vec<u8>,
+    ) -> Result<data::EncodedData, DataEncodingError> {
+        let encoded = self.encode_data_into(data, eci, structured_append, out)?;
+        let size = encoded.size;
+        let target = self.robustness.pick(size, &self.symbol_list);
+        if target != size {
+            let builder = Self {
+                symbol_list: target.into(),
+                robustness: Robustness::Min,
+                ..self.clone()
+            };
+            return builder.encode_part_into(data, eci, structured_append, out);
+        }
+        let num_data_codewords = out.len();
+        out.resize(size.num_codewords(), 0);
+        let (data, ecc) = out.split_at_mut(num_data_codewords);
+        errorcode::encode_error_into(data, size, ecc);
+        Ok(encoded)
+    }
+
+    /// Encode the data codewords with the options of the builder.
+    fn encode_data_into(
+        &self,
+        data: &[u8],
+        eci: Option<u32>,
+        structured_append: Option<[u8; 3]>,
+        out: &mut Vec<u8>,
+    ) -> Result<data::EncodedData, DataEncodingError> {
+        let encoded = data::encode_data_into(
             data,
             &self.symbol_list,
-            eci,
+            structured_append,
+            self.reader_programming,
+            eci.or(self.eci),
             self.encodation_types,
             self.use_macros,
+            self.gs1,
+            self.strict,
+            self.prefer_text,
+            self.effort,
+            self.start_mode,
+            self.headroom,
+            self.plan.as_deref(),
+            self.randomizer.0,
+            out,
         )?;
-        let ecc = errorcode::encode_error(&codewords, size);
-        let num_data_codewords = codewords.len();
-        codewords.extend_from_slice(&ecc);
-        Ok(DataMatrix {
-            size,
-            codewords,
-            num_data_codewords,
-        })
+        let other_mode =
+            |(_, mode): &(usize, EncodationType)| !self.encodation_types.contains(*mode);
+        if self.exclusive && encoded.segments.iter().any(other_mode) {
+            return Err(match data::validate_input(data, self.encodation_types) {
+                Err(invalid) => DataEncodingError::IllegalCharacter {
+                    position: invalid[0].0,
+                    character: invalid[0].1 as char,
+                },
+                Ok(()) => DataEncodingError::TooMuchOrIllegalData,
+            });
+        }
+        Ok(encoded)
     }
 }
 
+/// Get the error for `text` if encoding the bytes from `str_data` failed.
+///
+/// The position of an illegal character is changed to its offset in `text`.
+fn str_error(text: &str, eci: Option<u32>, err: DataEncodingError) -> DataEncodingError {
+    let DataEncodingError::IllegalCharacter { position, .. } = err else {
+        return err;
+    };
+    let mut buf = [0; 4];
+    let mut end = 0;
+    for (offset, ch) in text.char_indices() {
+        end += match eci {
+            Some(_) => ch.len_utf8(),
+            None => data::utf8_to_latin1_lossy(ch.encode_utf8(&mut buf)).len(),
+        };
+        if position < end {
+            return DataEncodingError::IllegalCharacter {
+                position: offset,
+                character: ch,
+            };
+        }
+    }
+    err
+}
+
 impl Default for DataMatrixBuilder {
     fn default() -> Self {
         Self::new()
@@ -303,6 +1588,1039 @@ fn utf8_eci_test() {
     assert_eq!(decoded, data);
 }
 
+#[test]
+fn utf8_eci_round_trip() {
+    let texts = [
+        "🥸🤘 ok",
+        "日本語のテキスト",
+        "漢字 and ASCII 123",
+        "Grüße, 世界 👋",
+    ];
+    let modes = [
+        EncodationType::all(),
+        EncodationType::Ascii.into(),
+        EncodationType::Ascii | EncodationType::C40,
+        EncodationType::Ascii | EncodationType::Text | EncodationType::Edifact,
+        EncodationType::Base256 | EncodationType::X12,
+    ];
+    for text in texts {
+        for types in modes {
+            let code = DataMatrixBuilder::new()
+                .with_encodation_types(types)
+                .encode_str(text)
+                .unwrap();
+            let codewords = code.data_codewords();
+            assert_eq!(
+                &codewords[..2],
+                &[encodation::ascii::ECI, decodation::ECI_UTF8 as u8 + 1]
+            );
+            assert_eq!(data::decode_str(codewords).unwrap(), text);
+        }
+    }
+}
+
+#[test]
+fn gs1_eci_test() {
+    // batch number (AI 10) with cyrillic characters
+    let text = "10ПАРТИЯ-7";
+    let code = DataMatrixBuilder::new()
+        .with_gs1(true)
+        .encode_str(text)
+        .unwrap();
+    let codewords = code.data_codewords();
+    assert_eq!(
+        &codewords[..3],
+        &[
+            encodation::ascii::ECI,
+            decodation::ECI_UTF8 as u8 + 1,
+            encodation::ascii::FNC1
+        ]
+    );
+    assert_eq!(data::decode_str(codewords).unwrap(), text);
+
+    // Latin-1 data does not need the ECI
+    let code = DataMatrixBuilder::new()
+        .with_gs1(true)
+        .encode_str("10ÄB12")
+        .unwrap();
+    assert_eq!(code.data_codewords()[0], encodation::ascii::FNC1);
+    assert_eq!(data::decode_str(code.data_codewords()).unwrap(), "10ÄB12");
+}
+
+#[test]
+fn test_bitmap_placement() {
+    for size in SymbolList::all() {
+        let code = DataMatrix::encode(b"42", size).unwrap();
+        assert!(
+            placement::placement_matches(&code.bitmap(), code.codewords()),
+            "{:?}",
+            size
+        );
+    }
+}
+
+#[test]
+fn test_damaged_bitmap() {
+    let data = b"Damage test with some more text in it";
+    for size in SymbolList::all() {
+        let Ok(code) = DataMatrix::encode(data, size) else {
+            continue;
+        };
+        let n = size.correctable_codewords();
+        let damaged = code.damaged_bitmap(0..n);
+        let (map, _) = MatrixMap::<bool>::try_from_bits(damaged.bits(), damaged.width()).unwrap();
+        let read = map.codewords();
+        let num_wrong = read
+            .iter()
+            .zip(code.codewords())
+            .filter(|(a, b)| a != b)
+            .count();
+        assert_eq!(num_wrong, n, "{:?}", size);
+        assert_eq!(
+            DataMatrix::decode_bitmap(&damaged).unwrap(),
+            data,
+            "{:?}",
+            size
+        );
+    }
+}
+
+#[test]
+fn test_damaged_bitmap_runs() {
+    let data = b"Damage test with some more text in it";
+    for size in SymbolList::all() {
+        let Ok(code) = DataMatrix::encode(data, size) else {
+            continue;
+        };
+        let n = size.correctable_codewords();
+        let num_data = size.num_data_codewords();
+        let num = size.num_codewords();
+        for start in [0, num_data - n, num_data, num - n] {
+            let damaged = code.damaged_bitmap(start..start + n);
+            assert_eq!(
+                DataMatrix::decode_bitmap(&damaged).unwrap(),
+                data,
+                "{:?} {}",
+                size,
+                start
+            );
+        }
+    }
+
+    // the last data codewords of 144x144 are in the first eight blocks only,
+    // so a run across the end of the data puts too many errors into one block
+    let code = DataMatrix::encode(data, SymbolSize::Square144).unwrap();
+    for start in [1540, 1550, 1553, 1555] {
+        assert!(DataMatrix::decode_bitmap(&code.damaged_bitmap(start..start + 310)).is_err());
+    }
+}
+
+#[test]
+fn test_decode_any_orientation() {
+    use placement::Orientation;
+
+    for size in [SymbolSize::Square16, SymbolSize::Rect12x36] {
+        let code = DataMatrix::encode(b"Turn me", size).unwrap();
+        let bitmap = code.bitmap();
+        for orientation in Orientation::ALL {
+            let transformed = bitmap.transform(orientation);
+            let (data, found) = DataMatrix::decode_bitmap_any_orientation(&transformed).unwrap();
+            assert_eq!(data, b"Turn me");
+            assert_eq!(found, orientation.inverse(), "{:?}", size);
+            assert_eq!(transformed.transform(found).bits(), bitmap.bits());
+        }
+    }
+    let noise = Bitmap::new((0..100).map(|i| i % 3 == 0), 10);
+    assert!(matches!(
+        DataMatrix::decode_bitmap_any_orientation(&noise),
+        Err(DecodingError::PixelConversion(_))
+    ));
+}
+
+#[test]
+fn test_charset_policy() {
+    let builder = |policy| DataMatrixBuilder::new().with_charset_policy(policy);
+    for policy in [
+        CharsetPolicy::Eci,
+        CharsetPolicy::Error,
+        CharsetPolicy::Transliterate,
+    ] {
+        let code = builder(policy).encode_str("Zoë").unwrap();
+        assert_eq!(
+            code.data_codewords()[..4],
+            [b'Z' + 1, b'o' + 1, 235, 0xeb - 127]
+        );
+    }
+    assert_eq!(
+        builder(CharsetPolicy::Error).encode_str("Zoë Łoś"),
+        Err(DataEncodingError::IllegalCharacter {
+            position: 5,
+            character: 'Ł'
+        })
+    );
+    // the position is the offset in the string, not in the Latin-1 bytes
+    assert_eq!(
+        builder(CharsetPolicy::Transliterate)
+            .with_encodation(Encodation::Exclusive(EncodationType::Edifact))
+            .encode_str("ŁA~"),
+        Err(DataEncodingError::IllegalCharacter {
+            position: 3,
+            character: '~'
+        })
+    );
+    let code = builder(CharsetPolicy::Transliterate)
+        .encode_str("Zoë Łoś")
+        .unwrap();
+    assert_eq!(
+        DataMatrix::decode_bitmap(&code.bitmap()).unwrap(),
+        b"Zo\xeb Los"
+    );
+    // an explicit ECI wins
+    let code = builder(CharsetPolicy::Error)
+        .with_eci(Some(decodation::ECI_UTF8))
+        .encode_str("Łoś")
+        .unwrap();
+    assert_eq!(data::decode_str(code.data_codewords()).unwrap(), "Łoś");
+}
+
+#[test]
+fn gs1_eci_digit_pairs() {
+    use encodation::ascii::{ECI, FNC1};
+
+    // the AI digits after the ECI and FNC1 are still paired
+    let code = DataMatrixBuilder::new()
+        .with_gs1(true)
+        .encode_str("10ПАРТИЯ")
+        .unwrap();
+    assert_eq!(code.data_codewords()[2..4], [FNC1, 130 + 10]);
+
+    // ISO 8859-5 (ECI 7), the first AI value starts with a high byte
+    let data = b"21\xbf\xb0\xc0\xc2\xb8\xcf1234";
+    let code = DataMatrixBuilder::new()
+        .with_gs1(true)
+        .with_encodation_types(EncodationType::Ascii)
+        .encode_eci(data, Some(7))
+        .unwrap();
+    let codewords = code.data_codewords();
+    assert_eq!(codewords[..4], [ECI, 7 + 1, FNC1, 130 + 21]);
+    // the upper shift for the first high byte follows directly
+    assert_eq!(
+        codewords[4..6],
+        [encodation::ascii::UPPER_SHIFT, 0xbf - 127]
+    );
+    // "1234" as two digit pairs at the end
+    assert_eq!(codewords[16..18], [130 + 12, 130 + 34]);
+}
+
+#[test]
+fn gs1_separator_is_fnc1() {
+    use encodation::ascii::FNC1;
+
+    let code = DataMatrix::encode_gs1(&[("10", "A"), ("21", "B")], SymbolList::default()).unwrap();
+    assert_eq!(
+        code.data_codewords()[..6],
+        [FNC1, 130 + 10, b'A' + 1, FNC1, 130 + 21, b'B' + 1]
+    );
+
+    // in C40 and Text the separator is the FNC1 of the shift 2 set
+    let data = b"10ABCDEFGH\x1d21IJKLMNOP";
+    for modes in [EncodationType::C40, EncodationType::Text] {
+        let builder = DataMatrixBuilder::new().with_encodation(Encodation::Only(modes));
+        let gs1 = builder.clone().with_gs1(true).encode(data).unwrap();
+        let plain = builder.encode(data).unwrap();
+        assert_eq!(gs1.data_codewords()[0], FNC1);
+        assert_ne!(
+            gs1.data_codewords()[1..plain.data_codewords().len() - 1],
+            plain.data_codewords()[..plain.data_codewords().len() - 2]
+        );
+        assert_eq!(DataMatrix::decode_bitmap(&gs1.bitmap()).unwrap(), data);
+    }
+
+    assert!(matches!(
+        DataMatrixBuilder::new().encode_gs1(&[("01", "123")]),
+        Err(DataEncodingError::InvalidOptions(_))
+    ));
+}
+
+#[test]
+fn test_codeword_histogram() {
+    let max_count = |data: &[u8]| {
+        let code = DataMatrix::encode(data, SymbolSize::Square32).unwrap();
+        let histogram = code.codeword_histogram();
+        assert_eq!(histogram.iter().sum::<usize>(), 62);
+        *histogram.iter().max().unwrap()
+    };
+    // a repetitive input gives spikes
+    assert!(max_count(&[b'!'; 50]) >= 12);
+    assert!(max_count(b"Lorem ipsum dolor sit amet, consectetur.") <= 4);
+}
+
+#[test]
+fn test_encode_with_segments() {
+    use EncodationType::*;
+
+    let segments = |data: &[u8]| {
+        let (code, segments) = DataMatrixBuilder::new().encode_with_segments(data).unwrap();
+        assert_eq!(
+            code,
+            DataMatrix::encode(data, SymbolList::default()).unwrap()
+        );
+        segments
+    };
+    assert_eq!(
+        segments(b"ABCDEFGHIJKLMNOP1234567890123456abc\xe4\xe4\xe4\xe4"),
+        [(0, C40), (18, Ascii), (35, Base256)]
+    );
+    // ASCII at the end of data without UNLATCH
+    assert_eq!(
+        segments(b".-/+?:'=,;.-/+?:'=,;ab"),
+        [(0, Edifact), (20, Ascii)]
+    );
+    // positions count the macro header
+    assert_eq!(
+        segments(b"[)>\x1e05\x1dABCDEFGHIJ\x1e\x04"),
+        [(7, Edifact), (15, Ascii)]
+    );
+    assert_eq!(segments(b""), []);
+}
+
+#[test]
+fn test_required_data_codewords() {
+    use crate::encodation::{ascii::PAD, UNLATCH};
+
+    let builder = DataMatrixBuilder::new().with_symbol_list(SymbolList::all());
+    for (data, mode, expected) in [
+        (&b"Hello"[..], EncodationType::Ascii, 5),
+        (&b"12345678"[..], EncodationType::Ascii, 4),
+        (&b"ABCDEFGHI"[..], EncodationType::C40, 8),
+        (&b"abcdefghi"[..], EncodationType::Text, 8),
+        (&b"ABC>*12 3"[..], EncodationType::X12, 8),
+        (&b".-/+?:'=.-/+?:'=."[..], EncodationType::Edifact, 15),
+        (&[0xe4; 10][..], EncodationType::Base256, 12),
+    ] {
+        let builder = builder.clone().with_encodation_types(mode);
+        let len = builder.required_data_codewords(data).unwrap();
+        assert_eq!(len, expected, "{:?}", mode);
+        let code = builder.clone().encode(data).unwrap();
+        assert_eq!(
+            code.size,
+            SymbolList::all().first_symbol_big_enough_for(len).unwrap()
+        );
+        // the padding starts directly after, possibly with an UNLATCH
+        let codewords = code.data_codewords();
+        if len < codewords.len() {
+            assert!([PAD, UNLATCH].contains(&codewords[len]), "{:?}", mode);
+        }
+    }
+    // the ECC200 header counts
+    let gs1 = DataMatrixBuilder::new().with_gs1(true);
+    assert_eq!(gs1.required_data_codewords(b"01"), Ok(2));
+    // not limited by the symbol list or the headroom
+    let small = DataMatrixBuilder::new().with_symbol_list(SymbolSize::Square10);
+    assert!(small.clone().encode(b"ABCDEFGHIJ").is_err());
+    assert_eq!(small.required_data_codewords(b"ABCDEFGHIJ"), Ok(8));
+    assert_eq!(
+        small
+            .with_headroom(0.5)
+            .required_data_codewords(&[b'1'; 3000]),
+        Ok(1500)
+    );
+    // C40 needs two codewords for three characters
+    assert!(matches!(
+        DataMatrixBuilder::new().required_data_codewords(&[b'A'; 5000]),
+        Err(DataEncodingError::TooMuchData {
+            needed: 3300..=3400,
+            available: 1558,
+            constraint: Constraint::Capacity
+        })
+    ));
+}
+
+#[test]
+fn test_uniform_size() {
+    let builder = DataMatrixBuilder::new().with_symbol_list(SymbolList::default().enforce_square());
+    let inputs: [&[u8]; 4] = [
+        b"A",
+        b"123456789012345678901234567890",
+        b"Hello, World!",
+        b"abc",
+    ];
+    let size = builder.uniform_size(&inputs).unwrap();
+    assert_eq!(size, SymbolSize::Square18);
+    for data in inputs {
+        let code = builder.clone().with_symbol_list(size).encode(data).unwrap();
+        assert_eq!(code.size, size);
+    }
+    assert_eq!(builder.uniform_size(&[]), Ok(SymbolSize::Square10));
+    assert_eq!(
+        builder.uniform_size(&[b"A", &[b'A'; 5000]]).unwrap_err(),
+        DataMatrixBuilder::new()
+            .required_data_codewords(&[b'A'; 5000])
+            .unwrap_err()
+    );
+}
+
+#[test]
+#[cfg(feature = "heapless")]
+fn test_encode_into_heapless() {
+    let builder = DataMatrixBuilder::new().with_macros(false);
+    let mut rnd = test::random_data();
+    let mut data: Vec<u8> = rnd(2800).iter().map(|b| b'0' + b % 10).collect();
+    data.extend_from_slice(b"Hello, World!");
+    let mut codewords = heapless::Vec::<u8, 1560>::new();
+    let size = DataMatrixBuilder::new()
+        .encode_into_heapless(&data, &mut codewords)
+        .unwrap();
+    assert_eq!(size, SymbolSize::Square144);
+    let (expected, _) = builder.encode_data_codewords(&data).unwrap();
+    assert_eq!(&codewords[..], expected);
+
+    // all modes are used
+    for _ in 0..50 {
+        let len = rnd(1)[0] as usize;
+        let data: Vec<u8> = rnd(len)
+            .iter()
+            .map(|b| b"AB01a> *\r\xe4"[*b as usize % 10])
+            .collect();
+        let expected = builder.encode_data_codewords(&data).map(|(cw, _)| cw);
+        let result = builder.encode_into_heapless(&data, &mut codewords);
+        assert_eq!(result.map(|_| codewords.to_vec()), expected, "{:?}", data);
+    }
+
+    // only sizes which fit into the capacity are used
+    let mut codewords = heapless::Vec::<u8, 1050>::new();
+    assert_eq!(
+        DataMatrixBuilder::new().encode_into_heapless(&data, &mut codewords),
+        Err(DataEncodingError::TooMuchData {
+            needed: 1413,
+            available: 1050,
+            constraint: Constraint::SymbolList
+        })
+    );
+    let size = DataMatrixBuilder::new()
+        .encode_into_heapless(&data[..2000], &mut codewords)
+        .unwrap();
+    assert_eq!(size, SymbolSize::Square120);
+    let mut codewords = heapless::Vec::<u8, 8>::new();
+    let size = DataMatrixBuilder::new()
+        .encode_into_heapless(b"Hello", &mut codewords)
+        .unwrap();
+    assert_eq!((size, codewords.len()), (SymbolSize::Square12, 5));
+    assert_eq!(
+        DataMatrixBuilder::new()
+            .with_headroom(0.5)
+            .encode_into_heapless(b"Hello", &mut codewords),
+        Err(DataEncodingError::TooMuchData {
+            needed: 5,
+            available: 4,
+            constraint: Constraint::Headroom
+        })
+    );
+    let c40 = DataMatrixBuilder::new().with_encodation_types(EncodationType::C40);
+    let size = c40.encode_into_heapless(b"HELLO", &mut codewords).unwrap();
+    assert_eq!(
+        Ok((codewords.to_vec(), size)),
+        c40.encode_data_codewords(b"HELLO")
+    );
+}
+
+#[test]
+fn test_randomizer() {
+    use data::{NoRandomization, Randomize253};
+
+    let size = SymbolSize::Square16;
+    let plain = DataMatrixBuilder::new()
+        .with_randomizer(&NoRandomization)
+        .with_symbol_list(size);
+    let code = plain.clone().encode(b"ABCDEF").unwrap();
+    let regular = DataMatrix::encode(b"ABCDEF", size).unwrap();
+    // the latch, 4 codewords for C40, and the UNLATCH, then the padding with PAD first
+    assert_eq!(code.data_codewords()[..7], regular.data_codewords()[..7]);
+    assert!(code.data_codewords()[6..].iter().all(|cw| *cw == 129));
+    assert_ne!(code.data_codewords(), regular.data_codewords());
+
+    assert_ne!(plain, DataMatrixBuilder::new());
+    assert_eq!(
+        DataMatrixBuilder::new().with_randomizer(&Randomize253),
+        DataMatrixBuilder::new()
+    );
+
+    #[cfg(feature = "heapless")]
+    {
+        let mut codewords = heapless::Vec::<u8, 12>::new();
+        plain
+            .encode_into_heapless(b"ABCDEF", &mut codewords)
+            .unwrap();
+        assert_eq!(&codewords[..], code.data_codewords());
+    }
+}
+
+#[test]
+fn test_headroom() {
+    // 15 codewords, Square18 would be 83% full
+    let input = b"123456789012345678901234567890";
+    let squares = SymbolList::default().enforce_square();
+    let code = DataMatrixBuilder::new()
+        .with_symbol_list(squares.clone())
+        .encode(input)
+        .unwrap();
+    assert_eq!(code.size, SymbolSize::Square18);
+    let code = DataMatrixBuilder::new()
+        .with_symbol_list(squares)
+        .with_headroom(0.2)
+        .encode(input)
+        .unwrap();
+    assert_eq!(code.size, SymbolSize::Square20);
+    assert!(15.0 / code.size.num_data_codewords() as f32 <= 0.8);
+    assert_eq!(data::decode_data(code.data_codewords()).unwrap(), input);
+
+    let builder = DataMatrixBuilder::new()
+        .with_headroom(0.2)
+        .with_symbol_list(SymbolSize::Square18);
+    assert_eq!(
+        builder.encode(input),
+        Err(DataEncodingError::TooMuchData {
+            needed: 15,
+            available: 14,
+            constraint: Constraint::Headroom
+        })
+    );
+
+    // the modes are chosen for the reduced capacity, the Text encoding would
+    // only fit into the bigger Rect12x26
+    let code = DataMatrixBuilder::new()
+        .with_headroom(0.25)
+        .encode(b"1b 1 2Aab")
+        .unwrap();
+    assert_eq!(code.size, SymbolSize::Square16);
+
+    // the end of data rules must not use the spare codewords
+    let mut rnd = test::random_data();
+    for headroom in [0.1, 0.25, 0.5] {
+        for len in 1..40 {
+            let input: Vec<u8> = rnd(len).into_iter().map(|ch| ch % 96 + 32).collect();
+            let full = DataMatrixBuilder::new().encode(&input).unwrap();
+            let code = DataMatrixBuilder::new()
+                .with_headroom(headroom)
+                .encode(&input)
+                .unwrap();
+            assert!(code.size >= full.size);
+            assert_eq!(data::decode_data(code.data_codewords()).unwrap(), input);
+        }
+    }
+}
+
+#[test]
+fn test_headroom_invalid() {
+    for headroom in [1.0, -0.1, f32::NAN] {
+        let builder = DataMatrixBuilder::new().with_headroom(headroom);
+        assert_eq!(builder, builder.clone());
+        assert!(matches!(
+            builder.clone().encode(b"A"),
+            Err(DataEncodingError::InvalidOptions(_))
+        ));
+        assert!(matches!(
+            builder.serial_encoder(b"SN", 4),
+            Err(DataEncodingError::InvalidOptions(_))
+        ));
+    }
+}
+
+#[test]
+fn test_robustness() {
+    let input = b"Hello, World!";
+    let squares = SymbolList::default().enforce_square();
+    let encode = |robustness| {
+        DataMatrixBuilder::new()
+            .with_symbol_list(squares.clone())
+            .with_robustness(robustness)
+            .encode(input)
+            .unwrap()
+    };
+    let min = encode(Robustness::Min);
+    let high = encode(Robustness::High);
+    let max = encode(Robustness::Max);
+    assert_eq!(min.size, SymbolSize::Square16);
+    assert_eq!(high.size, SymbolSize::Square18);
+    assert_eq!(max.size, SymbolSize::Square144);
+    for code in [min, high, max] {
+        assert_eq!(DataMatrix::decode_bitmap(&code.bitmap()).unwrap(), input);
+    }
+
+    // nothing bigger allowed
+    let code = DataMatrixBuilder::new()
+        .with_symbol_list(SymbolSize::Square16)
+        .with_robustness(Robustness::Max)
+        .encode(input)
+        .unwrap();
+    assert_eq!(code.size, SymbolSize::Square16);
+}
+
+#[test]
+fn test_text_preference() {
+    let builder = DataMatrixBuilder::new().with_text_preference(true);
+    let sentence = b"the quick brown fox jumps over the lazy dog";
+    let (code, segments) = builder.clone().encode_with_segments(sentence).unwrap();
+    assert_eq!(
+        segments,
+        [(0, EncodationType::Text), (42, EncodationType::Ascii)]
+    );
+    assert_eq!(code, DataMatrixBuilder::new().encode(sentence).unwrap());
+    // minimal compared to the single modes which can encode it
+    let size = builder.required_data_codewords(sentence).unwrap();
+    for mode in [EncodationType::Ascii, EncodationType::C40] {
+        let single = DataMatrixBuilder::new()
+            .with_encodation_types(mode)
+            .required_data_codewords(sentence)
+            .unwrap();
+        assert!(size < single);
+    }
+
+    // a tie between C40 and Text
+    let data = b"Bbabc  bA AC";
+    let (_, segments) = DataMatrixBuilder::new().encode_with_segments(data).unwrap();
+    assert_eq!(
+        segments,
+        [(0, EncodationType::Ascii), (5, EncodationType::C40)]
+    );
+    let (_, segments) = builder.clone().encode_with_segments(data).unwrap();
+    assert_eq!(
+        segments,
+        [(0, EncodationType::Ascii), (1, EncodationType::Text)]
+    );
+    assert_eq!(
+        builder.required_data_codewords(data),
+        DataMatrixBuilder::new().required_data_codewords(data)
+    );
+
+    // no effect on data which is not text-like
+    for data in [
+        &b"HELLO WORLD ABCDEF"[..],
+        b"1234567890",
+        b"ABC>ABC123>ABCDE",
+    ] {
+        assert_eq!(
+            builder.clone().encode_with_segments(data),
+            DataMatrixBuilder::new().encode_with_segments(data),
+        );
+    }
+}
+
+#[test]
+fn test_effort() {
+    let count = |effort, data: &[u8]| {
+        DataMatrixBuilder::new()
+            .with_effort(effort)
+            .required_data_codewords(data)
+            .unwrap()
+    };
+    // X12 is best, the look-ahead picks EDIFACT and the greedy selection stays in ASCII
+    let data = b" ABC>DEF*345.";
+    assert_eq!(
+        (
+            count(Effort::Exhaustive, data),
+            count(Effort::Balanced, data),
+            count(Effort::Fast, data),
+        ),
+        (10, 11, 12)
+    );
+
+    let mut rnd = test::random_data();
+    for i in 0..300 {
+        let data: Vec<u8> = rnd(i % 60 + 1)
+            .iter()
+            .map(|b| b"ABC>*123 .-/abc\xe4"[(*b % 16) as usize])
+            .collect();
+        for effort in [Effort::Fast, Effort::Balanced] {
+            let code = DataMatrixBuilder::new()
+                .with_effort(effort)
+                .encode(&data)
+                .unwrap();
+            assert_eq!(data::decode_data(code.data_codewords()), Ok(data.clone()));
+        }
+    }
+
+    // binary data is planned with ASCII and Base256 only, without loss
+    for i in 0..200 {
+        let mut data = rnd(i * 5 + 1);
+        if i % 2 == 0 {
+            // upper range bytes with short runs of text
+            for (pos, ch) in data.iter_mut().enumerate() {
+                *ch = match pos % 5 {
+                    0 | 1 => b"AB1 a9"[(*ch % 6) as usize],
+                    _ => *ch | 0x80,
+                };
+            }
+        }
+        assert_eq!(
+            count(Effort::Balanced, &data),
+            count(Effort::Exhaustive, &data),
+            "{:?}",
+            data
+        );
+    }
+
+    // longer runs of text in binary data still use the other modes
+    let mut data = alloc::vec![0xe4; 100];
+    for _ in 0..3 {
+        data.extend(b'A'..=b'Z');
+    }
+    assert_eq!(
+        count(Effort::Balanced, &data),
+        count(Effort::Exhaustive, &data)
+    );
+
+    // without ASCII the fast mode still has to plan
+    let code = DataMatrixBuilder::new()
+        .with_effort(Effort::Fast)
+        .with_encodation_types(EncodationType::Edifact)
+        .encode(b"ABCD")
+        .unwrap();
+    assert_eq!(code.data_codewords()[0], 240);
+}
+
+#[test]
+fn test_encodation() {
+    let segments = |encodation: Encodation, data: &[u8]| {
+        let (code, segments) = DataMatrixBuilder::new()
+            .with_encodation(encodation.clone())
+            .encode_with_segments(data)
+            .unwrap_or_else(|err| panic!("{:?} {:?} {:?}", err, encodation, data));
+        assert_eq!(data::decode_data(code.data_codewords()), Ok(data.to_vec()));
+        segments
+    };
+    let data = b"ABCDEFGHIJKL";
+    assert_eq!(segments(Encodation::Auto, data), [(0, EncodationType::C40)]);
+    assert_eq!(
+        segments(Encodation::Only(EncodationType::X12), data),
+        [(0, EncodationType::X12)]
+    );
+    assert_eq!(
+        segments(Encodation::StartWith(EncodationType::Ascii), data),
+        [(0, EncodationType::Ascii), (1, EncodationType::C40)]
+    );
+    assert_eq!(
+        segments(Encodation::StartWith(EncodationType::Text), data)[0],
+        (0, EncodationType::Text)
+    );
+    let plan = alloc::vec![(12, EncodationType::Edifact), (0, EncodationType::Edifact)];
+    assert_eq!(
+        segments(Encodation::Plan(plan), data),
+        [(0, EncodationType::Edifact)]
+    );
+    assert_eq!(
+        segments(Encodation::Exclusive(EncodationType::C40), b"ABCDE"),
+        [(0, EncodationType::C40)]
+    );
+
+    // Only ends with ASCII, Exclusive fails instead
+    let c40 = EncodationType::C40;
+    assert_eq!(
+        segments(Encodation::Only(c40), b"ABCD"),
+        [(0, c40), (3, EncodationType::Ascii)]
+    );
+    for data in [&b"ABCD"[..], b"A"] {
+        let result = DataMatrixBuilder::new()
+            .with_encodation(Encodation::Exclusive(c40))
+            .encode(data);
+        assert_eq!(result, Err(DataEncodingError::TooMuchOrIllegalData));
+    }
+
+    // Auto resets the other options
+    let builder = DataMatrixBuilder::new()
+        .with_encodation_types(EncodationType::Ascii)
+        .with_encodation_plan(alloc::vec![(0, EncodationType::Ascii)]);
+    assert_eq!(
+        builder.with_encodation(Encodation::Auto),
+        DataMatrixBuilder::new()
+    );
+    assert_eq!(
+        DataMatrixBuilder::new().with_encodation(Encodation::Only(EncodationType::Base256)),
+        DataMatrixBuilder::new().with_encodation_types(EncodationType::Base256)
+    );
+
+    // a later restriction of the modes also drops the start mode
+    let (_, used) = DataMatrixBuilder::new()
+        .with_encodation(Encodation::StartWith(EncodationType::Base256))
+        .with_encodation_types(EncodationType::Ascii)
+        .encode_with_segments(b"ABCDEF")
+        .unwrap();
+    assert_eq!(used, [(0, EncodationType::Ascii)]);
+
+    let mut rnd = test::random_data();
+    for i in 0..300 {
+        let data: Vec<u8> = rnd(i % 40 + 5)
+            .iter()
+            .map(|b| b"ABC>*123 .-/abc\xe4"[(*b % 16) as usize])
+            .collect();
+        let auto = DataMatrixBuilder::new()
+            .required_data_codewords(&data)
+            .unwrap();
+        for mode in EncodationType::all() {
+            let builder = DataMatrixBuilder::new().with_encodation(Encodation::StartWith(mode));
+            // X12 can only be left after a full triple
+            let start = if mode == EncodationType::X12 { 3 } else { 1 };
+            if !data[..start].iter().all(|ch| mode.can_encode(*ch)) {
+                assert!(builder.encode(&data).is_err(), "{:?} {:?}", mode, data);
+                continue;
+            }
+            assert_eq!(segments(Encodation::StartWith(mode), &data)[0], (0, mode));
+            assert!(builder.required_data_codewords(&data).unwrap() >= auto);
+            let code = builder.with_effort(Effort::Fast).encode(&data).unwrap();
+            assert_eq!(data::decode_data(code.data_codewords()), Ok(data.clone()));
+        }
+    }
+}
+
+#[test]
+fn test_with_eci() {
+    use encodation::ascii::ECI;
+
+    let builder = DataMatrixBuilder::new().with_eci(Some(20));
+    let code = builder.clone().encode(b"\x83\x65").unwrap();
+    assert_eq!(code.data_codewords()[..2], [ECI, 21]);
+    // the ECI of the builder is also used by the other methods
+    let mut codewords = Vec::new();
+    builder.encode_into(b"\x83\x65", &mut codewords).unwrap();
+    assert_eq!(codewords, code.codewords());
+    assert_eq!(builder.required_data_codewords(b"AB"), Ok(4));
+    let symbols = builder
+        .clone()
+        .with_symbol_list(SymbolSize::Square14)
+        .encode_structured(&[b'A'; 20])
+        .unwrap();
+    assert!(symbols.len() > 1);
+    for symbol in &symbols {
+        assert_eq!(symbol.data_codewords()[4..6], [ECI, 21]);
+    }
+
+    // strings only with UTF-8
+    let utf8 = DataMatrixBuilder::new().with_eci(Some(decodation::ECI_UTF8));
+    let code = utf8.clone().encode_str("Grüße").unwrap();
+    assert_eq!(code.data_codewords()[..2], [ECI, 27]);
+    assert_eq!(data::decode_str(code.data_codewords()).unwrap(), "Grüße");
+    assert!(matches!(
+        builder.clone().encode_str("ABC"),
+        Err(DataEncodingError::InvalidOptions(_))
+    ));
+    assert!(matches!(
+        builder.encode_str_structured("ABC"),
+        Err(DataEncodingError::InvalidOptions(_))
+    ));
+}
+
+#[test]
+fn test_eci_out_of_range() {
+    assert!(DataMatrixBuilder::new()
+        .encode_eci(b"A", Some(999999))
+        .is_ok());
+    assert_eq!(
+        DataMatrixBuilder::new().encode_eci(b"A", Some(1000000)),
+        Err(DataEncodingError::InvalidOptions(
+            "the ECI must be at most 999999"
+        ))
+    );
+}
+
+#[test]
+fn test_errors_box_dyn() {
+    use alloc::{boxed::Box, string::ToString};
+    use core::error::Error;
+
+    let encoding = [
+        DataEncodingError::TooMuchOrIllegalData,
+        DataEncodingError::SymbolListEmpty,
+        DataEncodingError::WrongNumberOfCodewords,
+        DataEncodingError::InvalidOptions("test"),
+        DataEncodingError::InternalError("test"),
+    ];
+    for err in encoding {
+        let boxed: Box<dyn Error> = err.into();
+        assert!(!boxed.to_string().is_empty());
+        assert!(boxed.source().is_none());
+    }
+
+    let decoding = [
+        DecodingError::PixelConversion(placement::BitmapConversionError::Alignment),
+        DecodingError::ErrorCorrection(errorcode::ErrorDecodingError::TooManyErrors),
+        DecodingError::DataDecoding(data::DataDecodingError::UnexpectedEnd),
+    ];
+    for err in decoding {
+        let inner = match &err {
+            DecodingError::PixelConversion(e) => e.to_string(),
+            DecodingError::ErrorCorrection(e) => e.to_string(),
+            DecodingError::DataDecoding(e) => e.to_string(),
+        };
+        let boxed: Box<dyn Error> = err.into();
+        assert_eq!(boxed.source().unwrap().to_string(), inner);
+    }
+
+    let result: Result<(), Box<dyn Error>> = (|| {
+        DataMatrix::decode(&[true; 10], 5)?;
+        Ok(())
+    })();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_encode_without_alloc() {
+    let inputs: [&[u8]; 8] = [
+        b"",
+        b"Hello, World! 0123456789",
+        b"\xe4\x0012a",
+        b"9",
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+        b"abcdefghijklmnopqrstuvwxyz",
+        b"ABC>DEF*GHI\rJKL>MNO*PQR",
+        b"@ABC^DEFGHI;JKL[MNO",
+    ];
+    for size in SymbolList::with_extended_rectangles().iter() {
+        let setup = size.block_setup();
+        let mut codewords = alloc::vec![0xff; size.num_codewords()];
+        let mut modules = alloc::vec![7; setup.width * setup.height];
+        for data in inputs {
+            let expected = DataMatrixBuilder::new()
+                .with_macros(false)
+                .with_symbol_list(size)
+                .encode(data);
+            let result = DataMatrix::encode_without_alloc(data, size, &mut codewords, &mut modules);
+            let Ok(expected) = expected else {
+                assert_eq!(
+                    result,
+                    Err(DataEncodingError::TooMuchOrIllegalData),
+                    "{:?} {:?}",
+                    size,
+                    data
+                );
+                continue;
+            };
+            result.unwrap();
+            assert_eq!(codewords, expected.codewords(), "{:?}", size);
+            let bits: Vec<u8> = expected.bitmap().bits().iter().map(|b| *b as u8).collect();
+            assert_eq!(modules, bits, "{:?}", size);
+        }
+    }
+
+    let size = SymbolSize::Square10;
+    let mut codewords = [0; 8];
+    let mut modules = [0; 100];
+    assert!(matches!(
+        DataMatrix::encode_without_alloc(b"1", size, &mut codewords[..7], &mut modules),
+        Err(DataEncodingError::InvalidOptions(_))
+    ));
+    assert!(matches!(
+        DataMatrix::encode_without_alloc(b"1", size, &mut codewords, &mut modules[..99]),
+        Err(DataEncodingError::InvalidOptions(_))
+    ));
+    assert_eq!(
+        DataMatrix::encode_without_alloc(b"\xe4\xe4\xe4", size, &mut codewords, &mut modules),
+        Err(DataEncodingError::TooMuchOrIllegalData)
+    );
+    // Base256 fits where ASCII does not
+    let mut codewords = [0; 12];
+    DataMatrix::encode_without_alloc(
+        b"\xe4\xe4\xe4",
+        SymbolSize::Square12,
+        &mut codewords,
+        &mut [0; 144],
+    )
+    .unwrap();
+    assert_eq!(
+        codewords[..],
+        DataMatrix::encode(b"\xe4\xe4\xe4", SymbolSize::Square12)
+            .unwrap()
+            .codewords()[..]
+    );
+}
+
+#[test]
+fn test_encode_batch() {
+    let inputs: Vec<Vec<u8>> = (0..40)
+        .map(|i| alloc::format!("LABEL-{:04}-{}", i * 37, "x".repeat(i)).into_bytes())
+        .collect();
+    let builder = DataMatrixBuilder::new().with_symbol_list(
+        SymbolList::default()
+            .enforce_square()
+            .enforce_width_in(..=22),
+    );
+    let codes = builder.encode_batch(inputs.iter().map(Vec::as_slice));
+    assert_eq!(codes.len(), inputs.len());
+    for (data, code) in inputs.iter().zip(&codes) {
+        assert_eq!(*code, builder.clone().encode(data));
+    }
+    // the long inputs do not fit
+    assert!(codes[0].is_ok());
+    assert_eq!(
+        codes[39],
+        Err(DataEncodingError::TooMuchData {
+            needed: 36,
+            available: 30,
+            constraint: Constraint::SymbolList
+        })
+    );
+
+    let codes = DataMatrix::encode_batch([&b"1"[..], b"22"], SymbolSize::Square10);
+    assert_eq!(codes[1].as_ref().unwrap().data_codewords()[0], 152);
+    assert!(DataMatrix::encode_batch([], SymbolList::default()).is_empty());
+}
+
+#[test]
+fn test_encode_with_report() {
+    let data = b"[)>\x1e05\x1dABC\x1e\x04";
+    let (code, report) = DataMatrixBuilder::new().encode_with_report(data).unwrap();
+    assert_eq!(
+        code,
+        DataMatrix::encode(data, SymbolList::default()).unwrap()
+    );
+    assert_eq!(report.segments(), [(7..10, EncodationType::Ascii)]);
+    assert_eq!(report.size(), SymbolSize::Square12);
+    assert_eq!(report.num_encoded_codewords(), 4);
+    assert_eq!(report.num_data_codewords(), 5);
+    assert_eq!(report.utilization(), 0.8);
+
+    let (code, report) = DataMatrixBuilder::new()
+        .with_symbol_list(SymbolSize::Square16)
+        .encode_with_report(b"")
+        .unwrap();
+    assert!(report.segments().is_empty());
+    assert_eq!(report.num_padding_codewords(), 12);
+    assert_eq!(
+        report.num_data_codewords() + report.num_error_codewords(),
+        code.codewords().len()
+    );
+}
+
+#[test]
+fn test_raw_codeword_stages() {
+    let builder = DataMatrixBuilder::new().with_symbol_list(SymbolList::default().enforce_square());
+    let data = b"Stage by stage";
+    let (mut codewords, size) = builder.encode_data_codewords(data).unwrap();
+    let code = builder.encode(data).unwrap();
+    assert_eq!(size, code.size);
+    assert_eq!(codewords, code.data_codewords());
+    codewords.extend(errorcode::encode_error(&codewords, size));
+    assert_eq!(codewords, code.codewords());
+    let bitmap = placement::place_codewords(&codewords, size);
+    assert_eq!(bitmap.bits(), code.bitmap().bits());
+}
+
+#[test]
+fn test_encode_into_reuses_buffer() {
+    let builder = DataMatrixBuilder::new();
+    let mut out = Vec::new();
+    let size = builder.encode_into(b"Hello, World!", &mut out).unwrap();
+    let code = DataMatrix::encode(b"Hello, World!", SymbolList::default()).unwrap();
+    assert_eq!(size, code.size);
+    assert_eq!(out, code.codewords());
+
+    let ptr = out.as_ptr();
+    let size = builder.encode_into(b"123", &mut out).unwrap();
+    assert_eq!(size, SymbolSize::Square10);
+    assert_eq!(out.len(), 8);
+    assert_eq!(out.as_ptr(), ptr);
+    assert_eq!(data::decode_data(&out[..3]).unwrap(), b"123");
+}
+
 #[test]
 fn test_tile_placement_forth_and_back() {
     let mut rnd_data = test::random_data();
@@ -316,6 +2634,49 @@ fn test_tile_placement_forth_and_back() {
     }
 }
 
+#[test]
+fn test_decode_bitmap_round_trip() {
+    let mut rnd = test::random_data();
+    for size in SymbolList::all() {
+        for len in [0, size.num_data_codewords() / 3] {
+            let input = rnd(len);
+            let code = DataMatrix::encode(&input, size).unwrap();
+            assert_eq!(DataMatrix::decode_bitmap(&code.bitmap()), Ok(input));
+        }
+    }
+    let code = DataMatrix::encode(b"Hi", SymbolSize::Square10).unwrap();
+    let mut bits = code.bitmap().bits().to_vec();
+    bits[0] = false;
+    let broken = Bitmap::new(bits, 10);
+    assert!(matches!(
+        DataMatrix::decode_bitmap(&broken),
+        Err(DecodingError::PixelConversion(_))
+    ));
+}
+
+#[test]
+fn test_decode_bitmap_every_mode() {
+    let inputs: [(EncodationType, &[u8]); 6] = [
+        (EncodationType::Ascii, b"Hello, World! 1234 \xe4"),
+        (EncodationType::C40, b"HELLO WORLD 1234 abc"),
+        (EncodationType::Text, b"hello world 1234 ABC"),
+        (EncodationType::X12, b"ABC*123>XYZ 789"),
+        (EncodationType::Edifact, b"ABC.-/+?:'=,;1234"),
+        (EncodationType::Base256, b"\x00\x01\xfe\xff binary"),
+    ];
+    for (mode, input) in inputs {
+        let (code, segments) = DataMatrixBuilder::new()
+            .with_encodation(Encodation::Only(mode))
+            .encode_with_segments(input)
+            .unwrap();
+        assert_eq!(segments[0], (0, mode));
+        assert_eq!(
+            DataMatrix::decode_bitmap(&code.bitmap()).as_deref(),
+            Ok(input)
+        );
+    }
+}
+
 #[test]
 fn test_macro_str() {
     let data = "[)>\x1E05\x1D🤘\x1E\x04";
@@ -341,12 +2702,179 @@ fn test_macro_str() {
     assert_eq!(data, out);
 }
 
+#[test]
+fn test_macro_round_trip() {
+    // the end of data rules of C40 and EDIFACT go back in the input
+    for content in [
+        &b"ABCDE"[..],
+        b"ABCDEFGHIJKLMNO",
+        b"ABCDEFGHIJK\xe4",
+        b"AAAAAAAAAAAAAAAAAAA.A.A.A.",
+    ] {
+        let mut input = encodation::MACRO05_HEAD.to_vec();
+        input.extend_from_slice(content);
+        input.extend_from_slice(encodation::MACRO_TRAIL);
+        for types in [
+            EncodationType::all(),
+            EncodationType::Ascii | EncodationType::C40,
+            EncodationType::Ascii | EncodationType::Edifact,
+        ] {
+            let code = DataMatrixBuilder::new()
+                .with_encodation_types(types)
+                .encode(&input)
+                .unwrap();
+            assert_eq!(data::decode_data(code.data_codewords()).unwrap(), input);
+        }
+    }
+}
+
+#[test]
+fn test_macro_needs_trailer() {
+    // the header alone is encoded as data
+    for input in [
+        &b"[)>\x1E05\x1DAB"[..],
+        b"[)>\x1E06\x1D",
+        b"[)>\x1E05\x1DAB\x1E",
+    ] {
+        let code = DataMatrix::encode(input, SymbolList::default()).unwrap();
+        assert_ne!(code.data_codewords()[0], encodation::MACRO05);
+        assert_ne!(code.data_codewords()[0], encodation::MACRO06);
+        assert_eq!(DataMatrix::decode_bitmap(&code.bitmap()).unwrap(), input);
+    }
+    let code = DataMatrix::encode(b"[)>\x1E06\x1D\x1E\x04", SymbolList::default()).unwrap();
+    assert_eq!(code.data_codewords()[0], encodation::MACRO06);
+    assert_eq!(
+        data::decode_data(code.data_codewords()).unwrap(),
+        b"[)>\x1E06\x1D\x1E\x04"
+    );
+}
+
+#[test]
+fn test_too_much_data_for_list() {
+    // more than the minimal capacity of all symbols, but the list is not empty
+    let data = test::random_data()(1500);
+    assert!(matches!(
+        DataMatrix::encode(&data, SymbolSize::Square120),
+        Err(DataEncodingError::TooMuchData {
+            needed: 1051..,
+            available: 1050,
+            constraint: Constraint::SymbolList
+        })
+    ));
+}
+
 #[test]
 fn test_too_much_data() {
     let mut rnd_data = test::random_data();
     let data = rnd_data(5000);
     let result = DataMatrix::encode(&data, SymbolList::default());
-    assert_eq!(result, Err(DataEncodingError::TooMuchOrIllegalData));
+    // random bytes need about one codeword each
+    assert!(matches!(
+        result,
+        Err(DataEncodingError::TooMuchData {
+            needed: 5000..=5100,
+            available: 1558,
+            constraint: Constraint::Capacity
+        })
+    ));
+}
+
+#[test]
+fn test_smallest_symbol_boundary() {
+    // 10x10 has 3 data codewords, the next size is 12x12 with 5
+    let check = |builder: DataMatrixBuilder, data: &[u8], eci: Option<u32>, size| {
+        let code = builder.clone().encode_eci(data, eci).unwrap();
+        assert_eq!(code.size, size, "{:?}", data);
+        // the symbol is valid
+        if eci.is_none() {
+            let bitmap = code.bitmap();
+            let decoded = DataMatrix::decode(bitmap.bits(), bitmap.width()).unwrap();
+            if !builder.gs1 {
+                assert_eq!(decoded, data);
+            }
+        } else {
+            let decoded = data::decode_str(code.data_codewords()).unwrap();
+            assert_eq!(decoded.as_bytes(), data);
+        }
+        // and too big for a list with 10x10 only
+        if size != SymbolSize::Square10 {
+            assert!(matches!(
+                builder
+                    .with_symbol_list(SymbolSize::Square10)
+                    .encode_eci(data, eci),
+                Err(DataEncodingError::TooMuchData {
+                    needed: 4..,
+                    available: 3,
+                    constraint: Constraint::SymbolList
+                })
+            ));
+        }
+    };
+    let plain = DataMatrixBuilder::new();
+    let gs1 = DataMatrixBuilder::new().with_gs1(true);
+    use SymbolSize::{Square10, Square12};
+
+    check(plain.clone(), b"123456", None, Square10);
+    check(plain.clone(), b"1234567", None, Square12);
+    check(plain.clone(), b"ABC", None, Square10);
+    check(plain.clone(), b"ABCD", None, Square12);
+    // FNC1 takes one codeword
+    check(gs1.clone(), b"0112", None, Square10);
+    check(gs1.clone(), b"01123", None, Square12);
+    check(gs1.clone(), b"011234", None, Square12);
+    // the ECI takes two codewords
+    check(plain.clone(), b"12", Some(26), Square10);
+    check(plain.clone(), b"123", Some(26), Square12);
+    // ECI before FNC1 leaves nothing for data in 10x10
+    check(gs1.clone(), b"01", Some(26), Square12);
+    // a macro takes one codeword for header and trailer
+    check(plain.clone(), b"[)>\x1e05\x1d1234\x1e\x04", None, Square10);
+    check(plain, b"[)>\x1e05\x1d12345\x1e\x04", None, Square12);
+
+    // digit pairs let the data exceed the Base256 capacity of every size in the list
+    let small = DataMatrixBuilder::new().with_symbol_list(SymbolList::with_whitelist([
+        SymbolSize::Square10,
+        SymbolSize::Square12,
+    ]));
+    let code = small.clone().encode(b"1234567890").unwrap();
+    assert_eq!(code.size, SymbolSize::Square12);
+    assert_eq!(
+        small.encode(b"123456789012"),
+        Err(DataEncodingError::TooMuchData {
+            needed: 6,
+            available: 5,
+            constraint: Constraint::SymbolList
+        })
+    );
+}
+
+#[test]
+fn test_from_codewords() {
+    let codewords = [
+        73, 239, 116, 130, 175, 52, 19, 40, 179, 242, 106, 105, 97, 98, 35, 165, 137, 102, 203,
+        106, 207, 48, 186, 66,
+    ];
+    let code = DataMatrix::from_codewords(&codewords[..12], SymbolSize::Square16).unwrap();
+    assert_eq!(code.codewords(), &codewords);
+    assert_eq!(code.data_codewords(), &codewords[..12]);
+    let encoded = DataMatrix::encode(b"Hello, World!", SymbolSize::Square16).unwrap();
+    assert_eq!(code.bitmap().bits(), encoded.bitmap().bits());
+    let bitmap = code.bitmap();
+    let decoded = DataMatrix::decode(bitmap.bits(), bitmap.width()).unwrap();
+    assert_eq!(decoded, b"Hello, World!");
+}
+
+#[test]
+fn test_from_codewords_wrong_length() {
+    let codewords = [73, 239, 116, 130, 175, 52, 19, 40, 179, 242, 106];
+    assert_eq!(
+        DataMatrix::from_codewords(&codewords, SymbolSize::Square16),
+        Err(DataEncodingError::WrongNumberOfCodewords)
+    );
+    assert_eq!(
+        DataMatrix::from_codewords(&[], SymbolSize::Square10),
+        Err(DataEncodingError::WrongNumberOfCodewords)
+    );
 }
 
 #[cfg(test)]
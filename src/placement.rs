@@ -15,11 +15,20 @@ use crate::symbol_size::{SymbolList, SymbolSize};
 #[cfg(test)]
 use pretty_assertions::assert_eq;
 
+#[cfg(feature = "embedded-graphics")]
+mod graphics;
 mod path;
+#[cfg(feature = "image")]
+mod raster;
+mod svg;
 
+#[cfg(feature = "embedded-graphics")]
+pub use graphics::BitmapImage;
 pub use path::PathSegment;
+pub use svg::SvgOptions;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum BitmapConversionError {
     /// The alignment pattern is not correct.
     Alignment,
@@ -33,6 +42,105 @@ pub enum BitmapConversionError {
     SymbolSize,
 }
 
+impl core::fmt::Display for BitmapConversionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Alignment => write!(f, "alignment pattern is not correct"),
+            Self::Padding => write!(f, "padding pattern is not correct"),
+            Self::ZeroWidth => write!(f, "width is zero"),
+            Self::DataSize => write!(f, "number of pixels does not fit the width"),
+            Self::SymbolSize => write!(f, "no symbol size matches the dimensions"),
+        }
+    }
+}
+
+impl core::error::Error for BitmapConversionError {}
+
+/// Error of [Bitmap::draw_into].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DrawError {
+    /// The width of the grid was zero.
+    ZeroWidth,
+    /// The length of the grid is not a multiple of its width.
+    DataSize,
+    /// The bitmap with `width` times `height` modules does not fit into the grid
+    /// with the top left corner at `(x, y)`.
+    OutOfBounds {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+}
+
+impl core::fmt::Display for DrawError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::ZeroWidth => write!(f, "grid width is zero"),
+            Self::DataSize => write!(f, "grid length is not a multiple of the width"),
+            Self::OutOfBounds {
+                x,
+                y,
+                width,
+                height,
+            } => write!(
+                f,
+                "bitmap of {}x{} modules does not fit into the grid at ({}, {})",
+                width, height, x, y
+            ),
+        }
+    }
+}
+
+impl core::error::Error for DrawError {}
+
+/// Function of a module in a symbol, see [DataMatrix::module_role](crate::DataMatrix::module_role).
+///
+/// Every data region is surrounded by a solid L shaped finder pattern on the left
+/// and bottom side, and an alternating timing pattern on the top and right side.
+/// In symbols with several data regions, the alignment patterns between them
+/// are made of these, too.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ModuleRole {
+    /// Part of a solid line, always dark.
+    Finder,
+    /// Part of an alternating line.
+    Timing,
+    /// Carries a bit of a codeword.
+    Data,
+    /// Fixed module in the corner of some sizes which is not used by any codeword.
+    Padding,
+}
+
+/// Get the role of the module at `row` and `col` in the bitmap of `size`.
+pub(crate) fn module_role(size: SymbolSize, row: usize, col: usize) -> ModuleRole {
+    let setup = size.block_setup();
+    assert!(
+        row < setup.height && col < setup.width,
+        "module ({}, {}) outside of {:?}",
+        row,
+        col,
+        size
+    );
+    // each region has the data and a border of one module on each side
+    let region_h = setup.height / (setup.extra_horizontal_alignments + 1);
+    let region_w = setup.width / (setup.extra_vertical_alignments + 1);
+    let (r, c) = (row % region_h, col % region_w);
+    if c == 0 || r == region_h - 1 {
+        ModuleRole::Finder
+    } else if r == 0 || c == region_w - 1 {
+        ModuleRole::Timing
+    } else if size.has_padding_modules()
+        && (setup.height - 3..setup.height - 1).contains(&row)
+        && (setup.width - 3..setup.width - 1).contains(&col)
+    {
+        ModuleRole::Padding
+    } else {
+        ModuleRole::Data
+    }
+}
+
 /// Abstract "bit" type used in [MatrixMap].
 pub trait Bit: Clone + Copy + PartialEq + core::fmt::Debug {
     const LOW: Self;
@@ -80,7 +188,7 @@ impl<M: Bit> MatrixMap<M> {
         if width == 0 {
             return Err(BitmapConversionError::ZeroWidth);
         }
-        if bits.len() % width != 0 {
+        if !bits.len().is_multiple_of(width) {
             return Err(BitmapConversionError::DataSize);
         }
         let height = bits.len() / width;
@@ -167,65 +275,24 @@ impl<M: Bit> MatrixMap<M> {
 
     /// Get the content of the matrix as a bitmap with alignment patterns added.
     pub fn bitmap(&self) -> Bitmap<M> {
-        let h = self.height + 2 + 2 * self.extra_horizontal_alignments;
-        let w = self.width + 2 + 2 * self.extra_vertical_alignments;
-        let mut bits = vec![M::LOW; h * w];
-
-        let idx = |i: usize, j: usize| i * w + j;
-
-        // draw horizontal alignments
-        let extra_hor = self.extra_horizontal_alignments;
-        let blk_h = (h - 2 * (extra_hor + 1)) / (extra_hor + 1);
-        for i in 0..extra_hor {
-            let rows_before = 1 + (blk_h + 2) * i + blk_h;
-            for j in 0..w {
-                bits[idx(rows_before, j)] = M::HIGH;
-            }
-            for j in (0..w).step_by(2) {
-                bits[idx(rows_before + 1, j)] = M::HIGH;
-            }
-        }
-
-        // draw vertical alignments
-        let extra_ver = self.extra_vertical_alignments;
-        let blk_w = (w - 2 * (extra_ver + 1)) / (extra_ver + 1);
-        for j in 0..extra_ver {
-            let cols_before = 1 + (blk_w + 2) * j + blk_w;
-            for i in 1..h {
-                bits[idx(i, cols_before + 1)] = M::HIGH;
-            }
-            for i in (1..h).step_by(2) {
-                bits[idx(i, cols_before)] = M::HIGH;
-            }
-        }
-
-        for j in 0..w {
-            // draw bottom alignment
-            bits[idx(h - 1, j)] = M::HIGH;
-        }
-        for j in (0..w).step_by(2) {
-            // draw top alignment
-            bits[idx(0, j)] = M::HIGH;
-        }
-        for i in 0..h {
-            // draw left alignment
-            bits[idx(i, 0)] = M::HIGH;
-        }
-        for i in (1..h).step_by(2) {
-            // draw right alignment
-            bits[idx(i, w - 1)] = M::HIGH;
-        }
+        let frame = Frame::new(
+            self.width,
+            self.height,
+            self.extra_vertical_alignments,
+            self.extra_horizontal_alignments,
+        );
+        let mut bits = vec![M::LOW; frame.height * frame.width];
+        frame.draw_alignments(|idx| bits[idx] = M::HIGH);
 
         // copy the data
         for (b_i, b) in self.entries.iter().enumerate() {
-            let mut i = b_i / self.width;
-            i += 1 + (i / blk_h) * 2;
-            let mut j = b_i % self.width;
-            j += 1 + (j / blk_w) * 2;
-            bits[idx(i, j)] = *b;
+            bits[frame.bitmap_index(b_i)] = *b;
         }
 
-        Bitmap { width: w, bits }
+        Bitmap {
+            width: frame.width,
+            bits,
+        }
     }
 
     /// Traverse the symbol in codeword order and call the function for each position.
@@ -314,10 +381,32 @@ impl IndexTraversal {
     fn run<F>(&self, mut visit_fn: F)
     where
         F: FnMut(usize, [usize; 8]),
+    {
+        let mut visited = vec![false; self.width * self.height];
+        self.run_with(
+            &mut visited,
+            |visited, idx| visited[idx],
+            |visited, codeword_idx, indices| {
+                for v in indices {
+                    visited[v] = true;
+                }
+                visit_fn(codeword_idx, indices);
+            },
+        );
+    }
+
+    /// Like [run](Self::run), but the caller keeps track of the visited positions in `state`.
+    ///
+    /// `visit_fn` must mark all the given positions such that `is_visited`
+    /// returns `true` for them afterwards.
+    fn run_with<S, V, F>(&self, state: &mut S, is_visited: V, mut visit_fn: F)
+    where
+        S: ?Sized,
+        V: Fn(&S, usize) -> bool,
+        F: FnMut(&mut S, usize, [usize; 8]),
     {
         let nrow = self.height as isize;
         let ncol = self.width as isize;
-        let mut visited = vec![false; (nrow * ncol) as usize];
 
         // starting in the correct location for first character, bit 8
         let mut i = 4;
@@ -326,11 +415,7 @@ impl IndexTraversal {
 
         macro_rules! visit {
             ($indices:expr) => {
-                let ii = $indices;
-                for v in ii {
-                    visited[v] = true;
-                }
-                visit_fn(codeword_idx, ii);
+                visit_fn(state, codeword_idx, $indices);
                 codeword_idx += 1;
             };
         }
@@ -351,7 +436,7 @@ impl IndexTraversal {
             }
             // sweep upward diagonally
             loop {
-                if i < nrow && j >= 0 && !visited[(i * ncol + j) as usize] {
+                if i < nrow && j >= 0 && !is_visited(state, (i * ncol + j) as usize) {
                     visit!(self.utah(i, j));
                 }
                 i -= 2;
@@ -365,7 +450,7 @@ impl IndexTraversal {
 
             // sweep downward diagonally
             loop {
-                if i >= 0 && j < ncol && !visited[(i * ncol + j) as usize] {
+                if i >= 0 && j < ncol && !is_visited(state, (i * ncol + j) as usize) {
                     visit!(self.utah(i, j));
                 }
                 i += 2;
@@ -480,6 +565,179 @@ impl IndexTraversal {
     }
 }
 
+/// Geometry of a bitmap with the alignment patterns around the data regions.
+struct Frame {
+    width: usize,
+    height: usize,
+    content_width: usize,
+    blk_w: usize,
+    blk_h: usize,
+    extra_vertical_alignments: usize,
+    extra_horizontal_alignments: usize,
+}
+
+impl Frame {
+    fn new(
+        content_width: usize,
+        content_height: usize,
+        extra_vertical_alignments: usize,
+        extra_horizontal_alignments: usize,
+    ) -> Self {
+        Self {
+            width: content_width + 2 + 2 * extra_vertical_alignments,
+            height: content_height + 2 + 2 * extra_horizontal_alignments,
+            content_width,
+            blk_w: content_width / (extra_vertical_alignments + 1),
+            blk_h: content_height / (extra_horizontal_alignments + 1),
+            extra_vertical_alignments,
+            extra_horizontal_alignments,
+        }
+    }
+
+    fn for_size(size: SymbolSize) -> Self {
+        let setup = size.block_setup();
+        Self::new(
+            setup.content_width(),
+            setup.content_height(),
+            setup.extra_vertical_alignments,
+            setup.extra_horizontal_alignments,
+        )
+    }
+
+    /// Call `set_high` with the bitmap index of every dark module of the alignment patterns.
+    fn draw_alignments<F: FnMut(usize)>(&self, mut set_high: F) {
+        let (h, w) = (self.height, self.width);
+        let idx = |i: usize, j: usize| i * w + j;
+
+        // draw horizontal alignments
+        for i in 0..self.extra_horizontal_alignments {
+            let rows_before = 1 + (self.blk_h + 2) * i + self.blk_h;
+            for j in 0..w {
+                set_high(idx(rows_before, j));
+            }
+            for j in (0..w).step_by(2) {
+                set_high(idx(rows_before + 1, j));
+            }
+        }
+
+        // draw vertical alignments
+        for j in 0..self.extra_vertical_alignments {
+            let cols_before = 1 + (self.blk_w + 2) * j + self.blk_w;
+            for i in 1..h {
+                set_high(idx(i, cols_before + 1));
+            }
+            for i in (1..h).step_by(2) {
+                set_high(idx(i, cols_before));
+            }
+        }
+
+        for j in 0..w {
+            // draw bottom alignment
+            set_high(idx(h - 1, j));
+        }
+        for j in (0..w).step_by(2) {
+            // draw top alignment
+            set_high(idx(0, j));
+        }
+        for i in 0..h {
+            // draw left alignment
+            set_high(idx(i, 0));
+        }
+        for i in (1..h).step_by(2) {
+            // draw right alignment
+            set_high(idx(i, w - 1));
+        }
+    }
+
+    /// Get the bitmap index of the entry `content_idx` of a [MatrixMap].
+    fn bitmap_index(&self, content_idx: usize) -> usize {
+        let mut i = content_idx / self.content_width;
+        i += 1 + (i / self.blk_h) * 2;
+        let mut j = content_idx % self.content_width;
+        j += 1 + (j / self.blk_w) * 2;
+        i * self.width + j
+    }
+}
+
+/// Place all codewords of a symbol, data and error correction, into a bitmap.
+///
+/// This is the last stage of encoding, see
+/// [DataMatrixBuilder::encode_data_codewords](crate::DataMatrixBuilder::encode_data_codewords).
+/// Unlike [DataMatrix::from_codewords](crate::DataMatrix::from_codewords) the codewords
+/// are not checked, so also invalid symbols can be created, e.g., for testing a reader.
+///
+/// # Panics
+///
+/// Panics if the number of codewords is not [SymbolSize::num_codewords].
+pub fn place_codewords(codewords: &[u8], size: SymbolSize) -> Bitmap<bool> {
+    assert_eq!(
+        codewords.len(),
+        size.num_codewords(),
+        "wrong number of codewords for {:?}",
+        size
+    );
+    MatrixMap::new_with_codewords(codewords, size).bitmap()
+}
+
+/// Get the bitmap indices of the modules of each codeword, most significant bit first.
+pub(crate) fn codeword_modules(size: SymbolSize) -> Vec<[usize; 8]> {
+    let frame = Frame::for_size(size);
+    let setup = size.block_setup();
+    let mut modules = Vec::with_capacity(size.num_codewords());
+    IndexTraversal {
+        width: setup.content_width(),
+        height: setup.content_height(),
+    }
+    .run(|_, indices| modules.push(indices.map(|idx| frame.bitmap_index(idx))));
+    modules
+}
+
+/// Write the bitmap for `codewords` into `modules` without allocating.
+///
+/// The modules are stored row by row, `1` is dark and `0` light. The
+/// lengths of the slices must fit `size`.
+pub(crate) fn place_into(codewords: &[u8], size: SymbolSize, modules: &mut [u8]) {
+    /// Marker for data modules which are not written yet.
+    const FREE: u8 = 2;
+
+    let frame = Frame::for_size(size);
+    let setup = size.block_setup();
+    let (w, h) = (setup.content_width(), setup.content_height());
+    assert_eq!(codewords.len(), size.num_codewords());
+    assert_eq!(modules.len(), frame.width * frame.height);
+
+    modules.fill(0);
+    frame.draw_alignments(|idx| modules[idx] = 1);
+    for content_idx in 0..w * h {
+        modules[frame.bitmap_index(content_idx)] = FREE;
+    }
+    IndexTraversal {
+        width: w,
+        height: h,
+    }
+    .run_with(
+        modules,
+        |modules, idx| modules[frame.bitmap_index(idx)] != FREE,
+        |modules, codeword_idx, indices| {
+            let codeword = codewords[codeword_idx];
+            for (bit, idx) in indices.into_iter().enumerate() {
+                modules[frame.bitmap_index(idx)] = (codeword >> (7 - bit)) & 1;
+            }
+        },
+    );
+    // only the padding modules in the lower right corner are left
+    for content_idx in 0..w * h {
+        let module = &mut modules[frame.bitmap_index(content_idx)];
+        if *module == FREE {
+            *module = 0;
+        }
+    }
+    if size.has_padding_modules() {
+        modules[frame.bitmap_index((h - 2) * w + w - 2)] = 1;
+        modules[frame.bitmap_index((h - 1) * w + w - 1)] = 1;
+    }
+}
+
 impl MatrixMap<bool> {
     /// Create a MatrixMap and fills with codewords.
     pub fn new_with_codewords(data: &[u8], symbol_size: SymbolSize) -> Self {
@@ -522,6 +780,24 @@ impl MatrixMap<bool> {
     }
 }
 
+/// Check if reading `bitmap` back in placement order gives `codewords`.
+///
+/// This is a self-check to debug the placement, it is independent of the
+/// error correction and data decoding. Available with the feature `placement-check`.
+///
+/// ```rust
+/// # use datamatrix::{DataMatrix, SymbolSize, placement::placement_matches};
+/// let code = DataMatrix::encode(b"42", SymbolSize::Square144).unwrap();
+/// assert!(placement_matches(&code.bitmap(), code.codewords()));
+/// ```
+#[cfg(any(test, feature = "placement-check"))]
+pub fn placement_matches(bitmap: &Bitmap<bool>, codewords: &[u8]) -> bool {
+    match MatrixMap::try_from_bits(bitmap.bits(), bitmap.width()) {
+        Ok((map, _)) => map.codewords() == codewords,
+        Err(_) => false,
+    }
+}
+
 /// An abstract bitmap.
 ///
 /// Contains helpers for rendering the content. For rendering targets which
@@ -580,32 +856,74 @@ impl<B: Bit> Bitmap<B> {
     ///
     /// This is intended as a demo functionality. It might look weird
     /// if the line height is wrong or if you are not using a monospaced font.
+    ///
+    /// Same as [to_unicode_string()](Self::to_unicode_string) with a quiet zone of one module.
     pub fn unicode(&self) -> String {
-        const BORDER: usize = 1;
-        const INVERT: bool = false;
+        self.to_unicode_string(1)
+    }
+
+    /// Is the module at row `i` and column `j` dark, counted from the top left
+    /// corner of a quiet zone of width `quiet_zone`?
+    fn is_dark_in_quiet_zone(&self, i: usize, j: usize, quiet_zone: usize) -> bool {
+        i >= quiet_zone
+            && i < quiet_zone + self.height()
+            && j >= quiet_zone
+            && j < quiet_zone + self.width
+            && self.bits[(i - quiet_zone) * self.width + (j - quiet_zone)] == B::HIGH
+    }
+
+    /// Render the symbol with half block characters (`▀`, `▄`, `█`), two rows per line.
+    ///
+    /// The symbol is surrounded by `quiet_zone` light modules on each side, the
+    /// dark modules are drawn with the foreground color of a terminal. Use a terminal
+    /// with a light background, otherwise the symbol is inverted and harder to scan.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::placement::Bitmap;
+    /// let bitmap = Bitmap::new([true, false, true, true], 2);
+    /// assert_eq!(bitmap.to_unicode_string(0), "█▄\n");
+    /// ```
+    pub fn to_unicode_string(&self, quiet_zone: usize) -> String {
         const CHAR: [char; 4] = [' ', '▄', '▀', '█'];
-        let height = self.height();
-        let get = |i: usize, j: usize| -> usize {
-            let res =
-                if i < BORDER || i >= BORDER + height || j < BORDER || j >= BORDER + self.width {
-                    B::LOW
-                } else if i - BORDER < height && j - BORDER < self.width {
-                    self.bits[(i - BORDER) * self.width + (j - BORDER)]
-                } else {
-                    B::LOW
-                };
-            if res == B::HIGH {
-                1
-            } else {
-                0
+        let height = self.height() + 2 * quiet_zone;
+        let width = self.width + 2 * quiet_zone;
+        let mut out = String::with_capacity(height.div_ceil(2) * (width * 3 + 1));
+        for i in (0..height).step_by(2) {
+            for j in 0..width {
+                let top = self.is_dark_in_quiet_zone(i, j, quiet_zone) as usize;
+                let bottom = self.is_dark_in_quiet_zone(i + 1, j, quiet_zone) as usize;
+                out.push(CHAR[(top << 1) | bottom]);
             }
-        };
-        let mut out =
-            String::with_capacity((height + 2 * BORDER) * (self.width + 1 + 2 * BORDER) * 3 / 2);
-        for i in (0..height + 2 * BORDER).step_by(2) {
-            for j in 0..(self.width + 2 * BORDER) {
-                let idx = (get(i, j) << 1) | get(i + 1, j);
-                out.push(CHAR[if INVERT { (!idx) & 0b11 } else { idx }]);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Render the symbol with `##` for dark and two spaces for light modules, one row per line.
+    ///
+    /// The symbol is surrounded by `quiet_zone` light modules on each side. This only
+    /// uses ASCII, e.g., for log files.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::placement::Bitmap;
+    /// let bitmap = Bitmap::new([true, false, true, true], 2);
+    /// assert_eq!(bitmap.to_ascii_string(0), "##  \n####\n");
+    /// ```
+    pub fn to_ascii_string(&self, quiet_zone: usize) -> String {
+        let height = self.height() + 2 * quiet_zone;
+        let width = self.width + 2 * quiet_zone;
+        let mut out = String::with_capacity(height * (2 * width + 1));
+        for i in 0..height {
+            for j in 0..width {
+                out.push_str(if self.is_dark_in_quiet_zone(i, j, quiet_zone) {
+                    "##"
+                } else {
+                    "  "
+                });
             }
             out.push('\n');
         }
@@ -626,7 +944,8 @@ impl<B: Bit> Bitmap<B> {
     /// A quiet zone is not included in the coordinates but one must
     /// be added when rendering: The minimum free space required around the Data Matrix
     /// has to have the width/height of one "black" pixel.
-    /// The quiet zone should have the background's color.
+    /// The quiet zone should have the background's color. [pixels_scaled()](Self::pixels_scaled)
+    /// includes it in the coordinates.
     ///
     /// A Data Matrix can be either rendered using dark color on a light background,
     /// or the other way around. More details on contrast, size, etc. can be found in the referenced
@@ -650,10 +969,261 @@ impl<B: Bit> Bitmap<B> {
             .map(move |(i, _b)| (i % w, i / w))
     }
 
+    /// Get the device pixel of each dark module when rendering with a quiet zone.
+    ///
+    /// Each module is a square of `module_size` times `module_size` pixels, and
+    /// `quiet_zone` light modules are added on each side. The top left pixel
+    /// `(x, y)` of every dark module is returned in the order of [pixels()](Self::pixels).
+    /// The size of the whole image is [scaled_size()](Self::scaled_size).
+    ///
+    /// The specification requires a quiet zone of at least one module.
+    ///
+    /// Returns `None` if the size of the image does not fit into `u32`, then
+    /// [scaled_size()](Self::scaled_size) is also `None`.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolSize};
+    /// let bitmap = DataMatrix::encode(b"Foo", SymbolSize::Square10).unwrap().bitmap();
+    /// assert_eq!(bitmap.scaled_size(4, 1), Some((48, 48)));
+    /// for (x, y) in bitmap.pixels_scaled(4, 1).unwrap() {
+    ///     // fill the rectangle from (x, y) to (x + 4, y + 4)
+    /// }
+    /// assert_eq!(bitmap.pixels_scaled(4, 1).unwrap().next(), Some((4, 4)));
+    /// ```
+    pub fn pixels_scaled(
+        &self,
+        module_size: u32,
+        quiet_zone: u32,
+    ) -> Option<impl Iterator<Item = (u32, u32)> + '_> {
+        // every pixel is inside the image, so this can not overflow
+        self.scaled_size(module_size, quiet_zone)?;
+        Some(self.pixels().map(move |(x, y)| {
+            (
+                (x as u32 + quiet_zone) * module_size,
+                (y as u32 + quiet_zone) * module_size,
+            )
+        }))
+    }
+
+    /// Get the `(width, height)` in pixels of an image for [pixels_scaled()](Self::pixels_scaled).
+    ///
+    /// Returns `None` if a side does not fit into `u32`.
+    pub fn scaled_size(&self, module_size: u32, quiet_zone: u32) -> Option<(u32, u32)> {
+        let size = |modules: usize| {
+            u32::try_from(modules)
+                .ok()?
+                .checked_add(quiet_zone.checked_mul(2)?)?
+                .checked_mul(module_size)
+        };
+        Some((size(self.width())?, size(self.height())?))
+    }
+
+    /// Get an iterator over all modules as `(row, column, is_dark)`.
+    ///
+    /// In contrast to [pixels()](Self::pixels) also the light modules are
+    /// returned, row by row, and a quiet zone of `quiet_zone` light modules
+    /// is added on each side. The coordinates include the quiet zone.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolSize};
+    /// let code = DataMatrix::encode(b"Foo", SymbolSize::Square10).unwrap();
+    /// let bitmap = code.bitmap();
+    /// assert_eq!(bitmap.modules(1).count(), 12 * 12);
+    /// assert_eq!(bitmap.modules(1).next(), Some((0, 0, false)));
+    /// ```
+    pub fn modules(&self, quiet_zone: usize) -> impl Iterator<Item = (usize, usize, bool)> + '_ {
+        let width = self.width + 2 * quiet_zone;
+        let height = self.height() + 2 * quiet_zone;
+        (0..height).flat_map(move |row| {
+            (0..width).map(move |col| {
+                let inside = (quiet_zone..quiet_zone + self.height()).contains(&row)
+                    && (quiet_zone..quiet_zone + self.width).contains(&col);
+                let dark = inside
+                    && self.bits[(row - quiet_zone) * self.width + col - quiet_zone] == B::HIGH;
+                (row, col, dark)
+            })
+        })
+    }
+
+    /// Copy the modules into a bigger grid with the top left corner at `(x, y)`.
+    ///
+    /// The `grid` is in row-major order with `grid_width` entries per row. All
+    /// modules of the bitmap are written, the rest of the grid is not touched,
+    /// so a quiet zone must already be there.
+    ///
+    /// Fails with [DrawError::OutOfBounds] if the bitmap does not fit at the position.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolSize};
+    /// let code = DataMatrix::encode(b"Foo", SymbolSize::Square10).unwrap();
+    /// let mut page = vec![false; 40 * 30];
+    /// code.bitmap().draw_into(&mut page, 40, 25, 12).unwrap();
+    /// ```
+    pub fn draw_into(
+        &self,
+        grid: &mut [B],
+        grid_width: usize,
+        x: usize,
+        y: usize,
+    ) -> Result<(), DrawError> {
+        if grid_width == 0 {
+            return Err(DrawError::ZeroWidth);
+        }
+        if !grid.len().is_multiple_of(grid_width) {
+            return Err(DrawError::DataSize);
+        }
+        let grid_height = grid.len() / grid_width;
+        let fits =
+            |pos: usize, len: usize, max: usize| pos.checked_add(len).is_some_and(|end| end <= max);
+        if !fits(x, self.width, grid_width) || !fits(y, self.height(), grid_height) {
+            return Err(DrawError::OutOfBounds {
+                x,
+                y,
+                width: self.width,
+                height: self.height(),
+            });
+        }
+        for (i, row) in self.bits.chunks(self.width).enumerate() {
+            let start = (y + i) * grid_width + x;
+            grid[start..start + self.width].copy_from_slice(row);
+        }
+        Ok(())
+    }
+
     #[doc(hidden)]
     pub fn bits(&self) -> &[B] {
         &self.bits
     }
+
+    /// Invert the modules at the given `(x, y)` positions, e.g., to simulate damage.
+    ///
+    /// A position given twice is inverted twice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a position is outside of the bitmap.
+    pub fn flip_modules<I: IntoIterator<Item = (usize, usize)>>(&mut self, positions: I) {
+        let height = self.height();
+        for (x, y) in positions {
+            assert!(
+                x < self.width && y < height,
+                "module ({x}, {y}) out of bounds"
+            );
+            let bit = &mut self.bits[y * self.width + x];
+            *bit = if *bit == B::HIGH { B::LOW } else { B::HIGH };
+        }
+    }
+
+    /// Rotate the bitmap by 90 degrees clockwise.
+    pub fn rotate90(&self) -> Self {
+        let (width, height) = (self.width, self.height());
+        let bits = (0..width)
+            .flat_map(|x| (0..height).rev().map(move |y| self.bits[y * width + x]))
+            .collect();
+        Self {
+            width: height,
+            bits,
+        }
+    }
+
+    /// Rotate the bitmap by 180 degrees.
+    pub fn rotate180(&self) -> Self {
+        Self {
+            width: self.width,
+            bits: self.bits.iter().rev().copied().collect(),
+        }
+    }
+
+    /// Rotate the bitmap by 270 degrees clockwise, i.e., 90 degrees counterclockwise.
+    pub fn rotate270(&self) -> Self {
+        let (width, height) = (self.width, self.height());
+        let bits = (0..width)
+            .rev()
+            .flat_map(|x| (0..height).map(move |y| self.bits[y * width + x]))
+            .collect();
+        Self {
+            width: height,
+            bits,
+        }
+    }
+
+    /// Mirror the bitmap left to right.
+    pub fn mirror(&self) -> Self {
+        let bits = self
+            .bits
+            .chunks(self.width)
+            .flat_map(|row| row.iter().rev().copied())
+            .collect();
+        Self {
+            width: self.width,
+            bits,
+        }
+    }
+
+    /// Apply an orientation, first the mirroring and then the rotation.
+    pub fn transform(&self, orientation: Orientation) -> Self {
+        let mirrored;
+        let bitmap = if orientation.mirrored {
+            mirrored = self.mirror();
+            &mirrored
+        } else {
+            self
+        };
+        match orientation.quarter_turns % 4 {
+            0 => Self {
+                width: bitmap.width,
+                bits: bitmap.bits.clone(),
+            },
+            1 => bitmap.rotate90(),
+            2 => bitmap.rotate180(),
+            _ => bitmap.rotate270(),
+        }
+    }
+}
+
+/// A rotation and reflection of a [Bitmap], see [Bitmap::transform].
+///
+/// The default is the identity.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Orientation {
+    /// Number of clockwise quarter turns, taken modulo 4.
+    pub quarter_turns: u8,
+    /// Mirror left to right before rotating.
+    pub mirrored: bool,
+}
+
+impl Orientation {
+    /// All eight distinct orientations, starting with the identity.
+    pub const ALL: [Orientation; 8] = {
+        let mut all = [Orientation {
+            quarter_turns: 0,
+            mirrored: false,
+        }; 8];
+        let mut i = 0;
+        while i < 8 {
+            all[i].quarter_turns = (i % 4) as u8;
+            all[i].mirrored = i >= 4;
+            i += 1;
+        }
+        all
+    };
+
+    /// Get the orientation which undoes this one.
+    pub fn inverse(self) -> Self {
+        if self.mirrored {
+            // mirroring reverses the direction of the rotation
+            self
+        } else {
+            Self {
+                quarter_turns: (4 - self.quarter_turns % 4) % 4,
+                mirrored: false,
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -739,6 +1309,19 @@ fn test_from_bits_all() {
     }
 }
 
+#[test]
+fn test_placement_self_check() {
+    let mut rnd = crate::test::random_data();
+    for size in SymbolList::all() {
+        let mut codewords = rnd(size.num_codewords());
+        let bitmap = MatrixMap::new_with_codewords(&codewords, size).bitmap();
+        assert!(placement_matches(&bitmap, &codewords), "{:?}", size);
+        let last = codewords.len() - 1;
+        codewords[last] ^= 0b1000_0000;
+        assert!(!placement_matches(&bitmap, &codewords), "{:?}", size);
+    }
+}
+
 #[test]
 fn test_bitmap_new() {
     Bitmap::new(vec![true, false], 2);
@@ -746,3 +1329,235 @@ fn test_bitmap_new() {
     let data = &[true, false];
     Bitmap::new(data.iter().cloned(), 2);
 }
+
+#[test]
+fn test_flip_modules() {
+    let mut bitmap = Bitmap::new([false, true, false, false, true, true], 3);
+    bitmap.flip_modules([(0, 0), (1, 0), (2, 1), (2, 1), (1, 1)]);
+    assert_eq!(bitmap.bits(), [true, false, false, false, false, true]);
+}
+
+#[test]
+fn test_transforms() {
+    // 3x2:
+    // 1 2 3
+    // 4 5 6
+    let bitmap = Bitmap::new([1u8, 2, 3, 4, 5, 6].map(|i| (0, i)), 3);
+    let bits = |b: &Bitmap<(u16, u8)>| (b.width(), b.bits().iter().map(|(_, i)| *i).collect());
+    assert_eq!(bits(&bitmap.rotate90()), (2, vec![4, 1, 5, 2, 6, 3]));
+    assert_eq!(bits(&bitmap.rotate180()), (3, vec![6, 5, 4, 3, 2, 1]));
+    assert_eq!(bits(&bitmap.rotate270()), (2, vec![3, 6, 2, 5, 1, 4]));
+    assert_eq!(bits(&bitmap.mirror()), (3, vec![3, 2, 1, 6, 5, 4]));
+    for orientation in Orientation::ALL {
+        let back = bitmap
+            .transform(orientation)
+            .transform(orientation.inverse());
+        assert_eq!(bits(&back), bits(&bitmap), "{:?}", orientation);
+    }
+    let mut distinct: Vec<_> = Orientation::ALL
+        .iter()
+        .map(|o| bits(&bitmap.transform(*o)))
+        .collect();
+    distinct.sort();
+    distinct.dedup();
+    assert_eq!(distinct.len(), 8);
+}
+
+#[test]
+fn test_modules() {
+    let code = crate::DataMatrix::encode(b"Hello World!", SymbolSize::Square16).unwrap();
+    let bitmap = code.bitmap();
+    for quiet_zone in [0, 1, 4] {
+        let modules: Vec<_> = bitmap.modules(quiet_zone).collect();
+        let size = 16 + 2 * quiet_zone;
+        assert_eq!(modules.len(), size * size);
+        assert_eq!(modules.iter().filter(|m| m.2).count(), 142);
+        let dark: Vec<_> = modules
+            .iter()
+            .filter(|m| m.2)
+            .map(|&(row, col, _)| (col - quiet_zone, row - quiet_zone))
+            .collect();
+        assert_eq!(dark, bitmap.pixels().collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn test_pixels_scaled() {
+    let bitmap = Bitmap::new([true, false, false, true, true, true], 2);
+    assert_eq!(bitmap.scaled_size(3, 1), Some((12, 15)));
+    assert_eq!(bitmap.scaled_size(1, 0), Some((2, 3)));
+    let pixels: Vec<_> = bitmap.pixels_scaled(3, 1).unwrap().collect();
+    assert_eq!(pixels, [(3, 3), (6, 6), (3, 9), (6, 9)]);
+    let pixels: Vec<_> = bitmap.pixels_scaled(1, 0).unwrap().collect();
+    assert_eq!(pixels, [(0, 0), (1, 1), (0, 2), (1, 2)]);
+
+    // the height of 5 modules with the quiet zone overflows first
+    assert_eq!(bitmap.scaled_size(u32::MAX, 1), None);
+    assert_eq!(bitmap.scaled_size(1, u32::MAX), None);
+    assert_eq!(
+        bitmap.scaled_size(u32::MAX / 5, 1),
+        Some((3435973836, u32::MAX))
+    );
+    assert_eq!(bitmap.scaled_size(u32::MAX / 5 + 1, 1), None);
+    assert!(bitmap.pixels_scaled(u32::MAX, 1).is_none());
+    let last = bitmap.pixels_scaled(u32::MAX / 5, 1).unwrap().last();
+    assert_eq!(last, Some((2 * (u32::MAX / 5), 3 * (u32::MAX / 5))));
+}
+
+#[test]
+fn test_draw_into() {
+    let code = crate::DataMatrix::encode(b"Hello World!", SymbolSize::Rect12x26).unwrap();
+    let bitmap = code.bitmap();
+    let (width, height) = (40, 20);
+    let mut grid = vec![false; width * height];
+    bitmap.draw_into(&mut grid, width, 10, 5).unwrap();
+    let dark: Vec<_> = (0..grid.len())
+        .filter(|i| grid[*i])
+        .map(|i| (i % width - 10, i / width - 5))
+        .collect();
+    assert_eq!(dark, bitmap.pixels().collect::<Vec<_>>());
+
+    // light modules are written too
+    let mut grid = vec![true; width * height];
+    bitmap.draw_into(&mut grid, width, 14, 8).unwrap();
+    assert!(!grid[8 * width + 14 + 1]);
+    assert!(grid[8 * width + 14 - 1]);
+
+    let out_of_bounds = |x, y| DrawError::OutOfBounds {
+        x,
+        y,
+        width: 26,
+        height: 12,
+    };
+    assert_eq!(
+        bitmap.draw_into(&mut grid, width, 15, 0),
+        Err(out_of_bounds(15, 0))
+    );
+    assert_eq!(
+        bitmap.draw_into(&mut grid, width, 0, 9),
+        Err(out_of_bounds(0, 9))
+    );
+    assert_eq!(
+        bitmap.draw_into(&mut grid, width, usize::MAX, 0),
+        Err(out_of_bounds(usize::MAX, 0))
+    );
+    assert_eq!(
+        bitmap.draw_into(&mut grid, width, 0, usize::MAX - 5),
+        Err(out_of_bounds(0, usize::MAX - 5))
+    );
+    assert_eq!(
+        alloc::format!("{}", out_of_bounds(15, 0)),
+        "bitmap of 26x12 modules does not fit into the grid at (15, 0)"
+    );
+    assert_eq!(
+        bitmap.draw_into(&mut grid[1..], width, 0, 0),
+        Err(DrawError::DataSize)
+    );
+    assert_eq!(
+        bitmap.draw_into(&mut grid, 0, 0, 0),
+        Err(DrawError::ZeroWidth)
+    );
+}
+
+#[test]
+fn test_corner_cases() {
+    // The four corner patterns of the specification, with the codeword placed
+    // there and the (row, column) of its bits 1 to 8 in the mapping matrix.
+    #[rustfmt::skip]
+    let cases = [
+        // corner 1, 12x12 mapping matrix
+        (SymbolSize::Square14, 8, [(11, 0), (11, 1), (11, 2), (0, 10), (0, 11), (1, 11), (2, 11), (3, 11)]),
+        // corner 2, 14x14
+        (SymbolSize::Square16, 8, [(11, 0), (12, 0), (13, 0), (0, 10), (0, 11), (0, 12), (0, 13), (1, 13)]),
+        // corner 3, 6x28
+        (SymbolSize::Rect8x32, 1, [(3, 0), (4, 0), (5, 0), (0, 26), (0, 27), (1, 27), (2, 27), (3, 27)]),
+        // corner 4, 6x16
+        (SymbolSize::Rect8x18, 7, [(5, 0), (5, 15), (0, 13), (0, 14), (0, 15), (1, 13), (1, 14), (1, 15)]),
+    ];
+    for (size, codeword, cells) in cases {
+        let log = tests::log(size);
+        let width = size.block_setup().content_width();
+        for (bit, (i, j)) in cells.iter().enumerate() {
+            assert_eq!(
+                log[i * width + j],
+                (codeword, bit as u8 + 1),
+                "{:?} ({}, {})",
+                size,
+                i,
+                j
+            );
+        }
+    }
+
+    // every module is used by exactly one bit, except the padding pattern
+    for size in SymbolList::all() {
+        let log = tests::log(size);
+        let mut seen = alloc::collections::BTreeSet::new();
+        for entry in log.iter().filter(|e| e.0 != 0) {
+            assert!(seen.insert(*entry), "{:?} {:?}", size, entry);
+        }
+        assert_eq!(seen.len(), size.num_codewords() * 8, "{:?}", size);
+    }
+}
+
+#[test]
+fn test_module_role() {
+    use ModuleRole::*;
+
+    // 12x12 with the padding
+    let role = |i, j| module_role(SymbolSize::Square12, i, j);
+    for i in 0..12 {
+        assert_eq!(role(i, 0), Finder);
+        assert_eq!(role(11, i), Finder);
+    }
+    for i in 1..11 {
+        assert_eq!(role(0, i), Timing);
+        assert_eq!(role(i, 11), Timing);
+    }
+    assert_eq!(role(0, 11), Timing);
+    for i in 1..11 {
+        for j in 1..11 {
+            let expected = if (9..11).contains(&i) && (9..11).contains(&j) {
+                Padding
+            } else {
+                Data
+            };
+            assert_eq!(role(i, j), expected, "{} {}", i, j);
+        }
+    }
+
+    // matches the bitmap of a symbol
+    for size in SymbolList::all() {
+        let bitmap = MatrixMap::<bool>::new(size).bitmap();
+        let mut filled = MatrixMap::<bool>::new(size);
+        filled.traverse_mut(|_, bits| {
+            for bit in bits {
+                *bit = true;
+            }
+        });
+        let all_dark = filled.bitmap();
+        for (row, col, dark) in bitmap.modules(0) {
+            let role = module_role(size, row, col);
+            let data_dark = all_dark.bits[row * bitmap.width() + col];
+            match role {
+                Finder => assert!(dark),
+                Timing => assert_eq!(dark, (row + col) % 2 == 0, "{:?}", size),
+                Data => assert!(!dark && data_dark, "{:?} {} {}", size, row, col),
+                Padding => assert!(!data_dark),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_text_rendering() {
+    let bitmap = Bitmap::new([true, false, true, true, false, true], 2);
+    assert_eq!(bitmap.to_unicode_string(0), "█▄\n ▀\n");
+    assert_eq!(bitmap.to_unicode_string(1), " ▄  \n ▀█ \n    \n");
+    assert_eq!(bitmap.unicode(), bitmap.to_unicode_string(1));
+    assert_eq!(bitmap.to_ascii_string(0), "##  \n####\n  ##\n");
+    assert_eq!(
+        bitmap.to_ascii_string(1),
+        "        \n  ##    \n  ####  \n    ##  \n        \n"
+    );
+}
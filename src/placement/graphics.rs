@@ -0,0 +1,179 @@
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Dimensions, OriginDimensions, Point, Size},
+    image::{GetPixel, ImageDrawable},
+    pixelcolor::{BinaryColor, PixelColor},
+    primitives::Rectangle,
+};
+
+use super::Bitmap;
+
+/// A [Bitmap] scaled for drawing with `embedded-graphics`, see [drawable()](Bitmap::drawable).
+#[derive(Clone, Copy)]
+pub struct BitmapImage<'a, C = BinaryColor> {
+    bitmap: &'a Bitmap<bool>,
+    module_size: u32,
+    quiet_zone: u32,
+    dark: C,
+    light: C,
+}
+
+impl Bitmap<bool> {
+    /// Get an image for an `embedded-graphics` draw target, requires the feature
+    /// `embedded-graphics`.
+    ///
+    /// Each module is drawn as square of `module_size` times `module_size` pixels,
+    /// surrounded by a light quiet zone of `quiet_zone` modules on each side.
+    /// The dark modules have the color [BinaryColor::On] and the light ones
+    /// [BinaryColor::Off], other colors can be set with
+    /// [with_colors()](BitmapImage::with_colors).
+    ///
+    /// The position on the display is set when drawing it as `Image`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolSize};
+    /// use embedded_graphics::{image::Image, mock_display::MockDisplay, prelude::*};
+    ///
+    /// let code = DataMatrix::encode(b"Hi", SymbolSize::Square10).unwrap();
+    /// let bitmap = code.bitmap();
+    /// let mut display = MockDisplay::new();
+    /// Image::new(&bitmap.drawable(2, 1), Point::new(8, 4))
+    ///     .draw(&mut display)
+    ///     .unwrap();
+    /// assert_eq!(display.affected_area().size, Size::new(24, 24));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the module size is zero.
+    pub fn drawable(&self, module_size: u32, quiet_zone: u32) -> BitmapImage<'_> {
+        assert!(module_size > 0, "the module size must be positive");
+        BitmapImage {
+            bitmap: self,
+            module_size,
+            quiet_zone,
+            dark: BinaryColor::On,
+            light: BinaryColor::Off,
+        }
+    }
+}
+
+impl<'a, C> BitmapImage<'a, C> {
+    /// Set the colors of the dark and the light modules, e.g., for a color display.
+    pub fn with_colors<T: PixelColor>(self, dark: T, light: T) -> BitmapImage<'a, T> {
+        BitmapImage {
+            bitmap: self.bitmap,
+            module_size: self.module_size,
+            quiet_zone: self.quiet_zone,
+            dark,
+            light,
+        }
+    }
+}
+
+impl<C> OriginDimensions for BitmapImage<'_, C> {
+    fn size(&self) -> Size {
+        let size = |modules: usize| (modules as u32 + 2 * self.quiet_zone) * self.module_size;
+        Size::new(size(self.bitmap.width()), size(self.bitmap.height()))
+    }
+}
+
+impl<C: PixelColor> GetPixel for BitmapImage<'_, C> {
+    type Color = C;
+
+    fn pixel(&self, p: Point) -> Option<C> {
+        if p.x < 0 || p.y < 0 {
+            return None;
+        }
+        let size = self.size();
+        let (x, y) = (p.x as u32, p.y as u32);
+        if x >= size.width || y >= size.height {
+            return None;
+        }
+        let module = |pixel: u32| (pixel / self.module_size).checked_sub(self.quiet_zone);
+        let dark = match (module(x), module(y)) {
+            (Some(x), Some(y)) => {
+                let (x, y) = (x as usize, y as usize);
+                x < self.bitmap.width
+                    && y < self.bitmap.height()
+                    && self.bitmap.bits[y * self.bitmap.width + x]
+            }
+            _ => false,
+        };
+        Some(if dark { self.dark } else { self.light })
+    }
+}
+
+impl<C: PixelColor> ImageDrawable for BitmapImage<'_, C> {
+    type Color = C;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        self.draw_sub_image(target, &self.bounding_box())
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let visible = area.intersection(&self.bounding_box());
+        if visible.is_zero_sized() {
+            return Ok(());
+        }
+        let colors = visible.rows().flat_map(|y| {
+            visible
+                .columns()
+                .map(move |x| self.pixel(Point::new(x, y)).unwrap())
+        });
+        let target_area = Rectangle::new(visible.top_left - area.top_left, visible.size);
+        target.fill_contiguous(&target_area, colors)
+    }
+}
+
+#[test]
+fn test_drawable() {
+    use embedded_graphics::{
+        image::Image, mock_display::MockDisplay, pixelcolor::Gray2, prelude::*,
+    };
+
+    let bitmap = Bitmap::new([true, false, true, true, false, true], 2);
+    let image = bitmap.drawable(2, 1);
+    assert_eq!(image.size(), Size::new(8, 10));
+
+    let mut display = MockDisplay::new();
+    Image::new(&image, Point::new(1, 0))
+        .draw(&mut display)
+        .unwrap();
+    display.assert_pattern(&[
+        " ........",
+        " ........",
+        " ..##....",
+        " ..##....",
+        " ..####..",
+        " ..####..",
+        " ....##..",
+        " ....##..",
+        " ........",
+        " ........",
+    ]);
+
+    let mut display = MockDisplay::new();
+    let sub_image = image.sub_image(&Rectangle::new(Point::new(3, 3), Size::new(4, 20)));
+    Image::new(&sub_image, Point::zero())
+        .draw(&mut display)
+        .unwrap();
+    display.assert_pattern(&["#...", "###.", "###.", ".##.", ".##.", "....", "...."]);
+
+    let mut display = MockDisplay::new();
+    let colored = bitmap
+        .drawable(1, 0)
+        .with_colors(Gray2::new(1), Gray2::new(3));
+    Image::new(&colored, Point::zero())
+        .draw(&mut display)
+        .unwrap();
+    display.assert_pattern(&["13", "11", "31"]);
+}
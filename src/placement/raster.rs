@@ -0,0 +1,79 @@
+use image::{GrayImage, Luma};
+
+use super::Bitmap;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+impl Bitmap<bool> {
+    /// Render a grayscale image, requires the feature `image`.
+    ///
+    /// Each module is drawn as black square of `scale` times `scale` pixels,
+    /// surrounded by a white quiet zone of `quiet_zone` modules on each side.
+    /// The specification requires a quiet zone of at least one module.
+    ///
+    /// The image can be saved in any format enabled for the `image` crate, PNG support
+    /// is always included.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of the image does not fit into `u32`, see [scaled_size](Self::scaled_size).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolSize};
+    /// let code = DataMatrix::encode(b"Hello, PNG!", SymbolSize::Square16).unwrap();
+    /// let image = code.bitmap().to_image(5, 2);
+    /// assert_eq!(image.dimensions(), (100, 100));
+    ///
+    /// let mut png = std::io::Cursor::new(Vec::new());
+    /// image.write_to(&mut png, image::ImageFormat::Png).unwrap();
+    /// ```
+    pub fn to_image(&self, scale: u32, quiet_zone: u32) -> GrayImage {
+        let (width, height) = self
+            .scaled_size(scale, quiet_zone)
+            .expect("image size does not fit into u32");
+        let mut image = GrayImage::from_pixel(width, height, Luma([255]));
+        for (left, top) in self.pixels_scaled(scale, quiet_zone).unwrap() {
+            for j in top..top + scale {
+                for i in left..left + scale {
+                    image.put_pixel(i, j, Luma([0]));
+                }
+            }
+        }
+        image
+    }
+}
+
+#[test]
+fn test_to_image() {
+    let bitmap = Bitmap::new([true, false, false, true, true, false], 2);
+    let image = bitmap.to_image(2, 1);
+    assert_eq!(image.dimensions(), (8, 10));
+    let dark: alloc::vec::Vec<(u32, u32)> = image
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel.0 == [0])
+        .map(|(x, y, _)| (x, y))
+        .collect();
+    assert_eq!(
+        dark,
+        [
+            (2, 2),
+            (3, 2),
+            (2, 3),
+            (3, 3),
+            (4, 4),
+            (5, 4),
+            (4, 5),
+            (5, 5),
+            (2, 6),
+            (3, 6),
+            (2, 7),
+            (3, 7),
+        ]
+    );
+    let light = image.pixels().filter(|pixel| pixel.0 == [255]).count();
+    assert_eq!(light, 80 - 12);
+    assert_eq!(bitmap.to_image(1, 0).dimensions(), (2, 3));
+}
@@ -0,0 +1,186 @@
+use alloc::string::String;
+use core::fmt::Write;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+use super::{Bitmap, PathSegment};
+
+/// Options for [to_svg()](Bitmap::to_svg).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    module_size: f32,
+    quiet_zone: usize,
+    dark: String,
+    light: Option<String>,
+}
+
+impl SvgOptions {
+    /// Black modules of size 1 on white with a quiet zone of one module.
+    pub fn new() -> Self {
+        Self {
+            module_size: 1.0,
+            quiet_zone: 1,
+            dark: "#000".into(),
+            light: Some("#fff".into()),
+        }
+    }
+
+    /// Set the size of a module in SVG user units (pixels if not scaled).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is not positive.
+    pub fn with_module_size(self, module_size: f32) -> Self {
+        assert!(module_size > 0.0, "the module size must be positive");
+        Self {
+            module_size,
+            ..self
+        }
+    }
+
+    /// Set the number of light modules added on each side.
+    ///
+    /// The specification requires at least one, more is recommended for
+    /// scanners which have problems to find the symbol.
+    pub fn with_quiet_zone(self, quiet_zone: usize) -> Self {
+        Self { quiet_zone, ..self }
+    }
+
+    /// Set the colors of the dark modules and of the background including the quiet zone.
+    ///
+    /// The colors are given in SVG syntax, e.g., `"#1a1a1a"` or `"navy"`. Without a
+    /// `light` color the background is transparent, then the quiet zone must be
+    /// light where the SVG is placed.
+    pub fn with_colors(self, dark: &str, light: Option<&str>) -> Self {
+        Self {
+            dark: dark.into(),
+            light: light.map(Into::into),
+            ..self
+        }
+    }
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Write `value` as XML attribute value.
+fn write_escaped(out: &mut String, value: &str) {
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("&quot;"),
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            ch => out.push(ch),
+        }
+    }
+}
+
+impl Bitmap<bool> {
+    /// Render a standalone SVG document.
+    ///
+    /// The dark modules are drawn as one path computed by [path()](Self::path),
+    /// on a background covering the quiet zone, see [SvgOptions].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolSize, placement::SvgOptions};
+    /// let code = DataMatrix::encode(b"Hello, SVG!", SymbolSize::Square16).unwrap();
+    /// let options = SvgOptions::new().with_module_size(4.0).with_quiet_zone(2);
+    /// let svg = code.bitmap().to_svg(&options);
+    /// assert!(svg.contains("width=\"80\" height=\"80\" viewBox=\"0 0 20 20\""));
+    /// ```
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let quiet_zone = options.quiet_zone;
+        let width = self.width() + 2 * quiet_zone;
+        let height = self.height() + 2 * quiet_zone;
+        let mut svg = String::new();
+        write!(
+            svg,
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" ",
+                "width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            ),
+            width as f32 * options.module_size,
+            height as f32 * options.module_size,
+            width,
+            height,
+        )
+        .unwrap();
+        if let Some(light) = &options.light {
+            write!(
+                svg,
+                "<rect width=\"{}\" height=\"{}\" fill=\"",
+                width, height
+            )
+            .unwrap();
+            write_escaped(&mut svg, light);
+            svg.push_str("\"/>\n");
+        }
+        // the path starts at the top left corner, which is dark in a Data Matrix
+        let path = self.path();
+        if !path.is_empty() {
+            svg.push_str("<path fill=\"");
+            write_escaped(&mut svg, &options.dark);
+            write!(
+                svg,
+                "\" fill-rule=\"evenodd\" d=\"M{},{}",
+                quiet_zone, quiet_zone
+            )
+            .unwrap();
+            for segment in path {
+                match segment {
+                    PathSegment::Horizontal(n) => write!(svg, "h{}", n),
+                    PathSegment::Vertical(n) => write!(svg, "v{}", n),
+                    PathSegment::Move(dx, dy) => write!(svg, "m{},{}", dx, dy),
+                    PathSegment::Close => write!(svg, "z"),
+                }
+                .unwrap();
+            }
+            svg.push_str("\"/>\n");
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[test]
+fn test_svg() {
+    let bitmap = Bitmap::new([true, false, false, true], 2);
+    assert_eq!(
+        bitmap.to_svg(&SvgOptions::new()),
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" ",
+            "width=\"4\" height=\"4\" viewBox=\"0 0 4 4\">\n",
+            "<rect width=\"4\" height=\"4\" fill=\"#fff\"/>\n",
+            "<path fill=\"#000\" fill-rule=\"evenodd\" d=\"M1,1h1v2h1v-1h-2z\"/>\n",
+            "</svg>\n",
+        )
+    );
+
+    // transparent background
+    let bitmap = Bitmap::new([true, true, false, true], 2);
+    let options = SvgOptions::new()
+        .with_module_size(2.5)
+        .with_quiet_zone(0)
+        .with_colors("rgb(0, 0, 128)", None);
+    let svg = bitmap.to_svg(&options);
+    assert!(svg.contains("width=\"5\" height=\"5\" viewBox=\"0 0 2 2\""));
+    assert!(!svg.contains("<rect"));
+    assert!(svg.contains(
+        "<path fill=\"rgb(0, 0, 128)\" fill-rule=\"evenodd\" d=\"M0,0h2v2h-1v-1h-1z\"/>"
+    ));
+
+    // no dark modules, and escaping
+    let bitmap = Bitmap::new([false; 4], 2);
+    let options = SvgOptions::new().with_colors("#000", Some("\"><script>"));
+    let svg = bitmap.to_svg(&options);
+    assert!(!svg.contains("<path"));
+    assert!(svg.contains("fill=\"&quot;>&lt;script>\""));
+}
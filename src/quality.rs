@@ -0,0 +1,367 @@
+//! Print quality grading in the style of ISO/IEC 15415.
+//!
+//! A symbol is graded from its sampled modules with [grade_bitmap], or from one
+//! reflectance value per module with [grade_reflectance]. The latter also grades
+//! the contrast and the modulation. Each parameter gets a letter [Grade], the
+//! overall grade is the lowest of them.
+//!
+//! The grading is meant for monitoring a marking process. It is simplified compared
+//! to a certified verifier: the measurement aperture, the scan at multiple angles,
+//! axial nonuniformity, grid nonuniformity, and the quiet zone are not considered, and
+//! the fixed pattern damage is one value for all finder and timing modules instead of
+//! a grade for each segment.
+//!
+//! ```rust
+//! # use datamatrix::{placement::Bitmap, quality::{self, Grade}, DataMatrix, SymbolSize};
+//! let code = DataMatrix::encode(b"4711", SymbolSize::Square10).unwrap();
+//! let mut bits = code.bitmap().bits().to_vec();
+//! // damage a data module, one of the five error correction codewords is used twice
+//! bits[3 * 10 + 5] ^= true;
+//! let report = quality::grade_bitmap(&Bitmap::new(bits, 10)).unwrap();
+//! assert_eq!(report.fixed_pattern_damage.grade, Grade::A);
+//! assert_eq!(report.unused_error_correction.value, 0.6);
+//! assert_eq!(report.overall(), Grade::B);
+//! ```
+use alloc::vec::Vec;
+
+use crate::errorcode;
+use crate::placement::{self, Bitmap, BitmapConversionError, MatrixMap, ModuleRole};
+use crate::symbol_size::{SymbolList, SymbolSize};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Letter grade of a quality parameter, ordered from worst to best.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Grade {
+    F,
+    D,
+    C,
+    B,
+    A,
+}
+
+impl Grade {
+    /// Get the numeric grade, 4 for A down to 0 for F.
+    pub fn value(self) -> u8 {
+        self as u8
+    }
+
+    /// Grade `value` with the lower bounds of the grades A, B, C, and D.
+    fn from_bounds(value: f32, bounds: [f32; 4]) -> Self {
+        let grades = [Grade::A, Grade::B, Grade::C, Grade::D];
+        grades
+            .into_iter()
+            .zip(bounds)
+            .find(|(_, bound)| value >= *bound)
+            .map_or(Grade::F, |(grade, _)| grade)
+    }
+}
+
+/// Measured value of a parameter together with its grade.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metric {
+    pub value: f32,
+    pub grade: Grade,
+}
+
+/// Result of grading a symbol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityReport {
+    /// Size of the symbol.
+    pub size: SymbolSize,
+    /// Fraction of the error correction capacity left, the minimum of all blocks.
+    pub unused_error_correction: Metric,
+    /// Fraction of wrong modules in the finder and timing patterns.
+    pub fixed_pattern_damage: Metric,
+    /// Difference of the highest and the lowest reflectance, as fraction of 255.
+    ///
+    /// Only available from [grade_reflectance].
+    pub symbol_contrast: Option<Metric>,
+    /// Grade of the modulation of the codeword modules, together with the
+    /// error correction left for the codewords with a low modulation.
+    ///
+    /// Only available from [grade_reflectance].
+    pub modulation: Option<Grade>,
+}
+
+impl QualityReport {
+    /// Get the overall grade, the lowest grade of all parameters.
+    pub fn overall(&self) -> Grade {
+        [
+            Some(self.unused_error_correction.grade),
+            Some(self.fixed_pattern_damage.grade),
+            self.symbol_contrast.map(|metric| metric.grade),
+            self.modulation,
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap()
+    }
+}
+
+const UEC_BOUNDS: [f32; 4] = [0.62, 0.50, 0.37, 0.25];
+const CONTRAST_BOUNDS: [f32; 4] = [0.70, 0.55, 0.40, 0.20];
+const MODULATION_BOUNDS: [f32; 4] = [0.50, 0.40, 0.30, 0.20];
+/// Bounds for the damaged fraction, lower is better.
+const DAMAGE_BOUNDS: [f32; 4] = [0.0, 0.09, 0.13, 0.17];
+
+/// Grade a sampled symbol, e.g., from [detection](crate::detection).
+///
+/// The alignment patterns do not need to be intact. Fails if no symbol size
+/// has the dimensions of the bitmap.
+pub fn grade_bitmap(bitmap: &Bitmap<bool>) -> Result<QualityReport, BitmapConversionError> {
+    let size = size_of(bitmap.width(), bitmap.height())?;
+    Ok(grade(size, bitmap.bits(), None))
+}
+
+/// Grade a symbol from one reflectance value for each module, row by row.
+///
+/// The value is 0 for black up to 255 for white, e.g., the mean
+/// gray value of the image pixels in the center of a module. The modules are
+/// binarized with the global threshold, halfway between the lowest and highest value.
+pub fn grade_reflectance(
+    reflectance: &[u8],
+    width: usize,
+) -> Result<QualityReport, BitmapConversionError> {
+    if width == 0 {
+        return Err(BitmapConversionError::ZeroWidth);
+    }
+    if !reflectance.len().is_multiple_of(width) {
+        return Err(BitmapConversionError::DataSize);
+    }
+    let size = size_of(width, reflectance.len() / width)?;
+    let max = *reflectance.iter().max().unwrap();
+    let min = *reflectance.iter().min().unwrap();
+    let threshold = (max as f32 + min as f32) / 2.0;
+    let bits: Vec<bool> = reflectance
+        .iter()
+        .map(|r| (*r as f32) < threshold)
+        .collect();
+    Ok(grade(
+        size,
+        &bits,
+        Some((reflectance, threshold, max - min)),
+    ))
+}
+
+fn size_of(width: usize, height: usize) -> Result<SymbolSize, BitmapConversionError> {
+    SymbolList::with_extended_rectangles()
+        .iter()
+        .find(|size| size.module_dimensions().0 == (width, height))
+        .ok_or(BitmapConversionError::SymbolSize)
+}
+
+fn grade(size: SymbolSize, bits: &[bool], reflectance: Option<(&[u8], f32, u8)>) -> QualityReport {
+    let ((width, _), _) = size.module_dimensions();
+
+    // fixed patterns, a symbol without data has only them dark
+    let reference = MatrixMap::<bool>::new(size).bitmap();
+    let mut fixed = 0;
+    let mut damaged = 0;
+    for (i, expected) in reference.bits().iter().enumerate() {
+        match placement::module_role(size, i / width, i % width) {
+            ModuleRole::Finder | ModuleRole::Timing => (),
+            _ => continue,
+        }
+        fixed += 1;
+        if bits[i] != *expected {
+            damaged += 1;
+        }
+    }
+    let damage = damaged as f32 / fixed as f32;
+    let damage_grade = Grade::from_bounds(-damage, DAMAGE_BOUNDS.map(|bound| -bound));
+
+    // error correction
+    let modules = placement::codeword_modules(size);
+    let received: Vec<u8> = modules
+        .iter()
+        .map(|indices| {
+            indices
+                .iter()
+                .fold(0, |codeword, idx| (codeword << 1) | bits[*idx] as u8)
+        })
+        .collect();
+    let mut corrected = received.clone();
+    let errors: Option<Vec<bool>> = errorcode::decode_error(&mut corrected, size)
+        .ok()
+        .map(|()| {
+            received
+                .iter()
+                .zip(&corrected)
+                .map(|(a, b)| a != b)
+                .collect()
+        });
+    let uec = |low: &dyn Fn(usize) -> bool| match &errors {
+        Some(errors) => unused_error_correction(size, errors, low),
+        None => 0.0,
+    };
+    let uec_value = uec(&|_| false);
+
+    let (symbol_contrast, modulation) = match reflectance {
+        Some((reflectance, threshold, contrast)) => {
+            let contrast_value = contrast as f32 / 255.0;
+            let symbol_contrast = Metric {
+                value: contrast_value,
+                grade: Grade::from_bounds(contrast_value, CONTRAST_BOUNDS),
+            };
+            let codeword_grades: Vec<Grade> = modules
+                .iter()
+                .map(|indices| {
+                    indices
+                        .iter()
+                        .map(|idx| {
+                            let modulation = if contrast == 0 {
+                                0.0
+                            } else {
+                                2.0 * (reflectance[*idx] as f32 - threshold).abs() / contrast as f32
+                            };
+                            Grade::from_bounds(modulation, MODULATION_BOUNDS)
+                        })
+                        .min()
+                        .unwrap()
+                })
+                .collect();
+            // treat the codewords below each grade level as erasures
+            let modulation = [Grade::A, Grade::B, Grade::C, Grade::D]
+                .into_iter()
+                .map(|level| {
+                    let value = uec(&|i| codeword_grades[i] < level);
+                    level.min(Grade::from_bounds(value, UEC_BOUNDS))
+                })
+                .max()
+                .unwrap();
+            (Some(symbol_contrast), Some(modulation))
+        }
+        None => (None, None),
+    };
+
+    QualityReport {
+        size,
+        unused_error_correction: Metric {
+            value: uec_value,
+            grade: Grade::from_bounds(uec_value, UEC_BOUNDS),
+        },
+        fixed_pattern_damage: Metric {
+            value: damage,
+            grade: damage_grade,
+        },
+        symbol_contrast,
+        modulation,
+    }
+}
+
+/// Compute the error correction left in the worst block.
+///
+/// `errors` marks the wrong codewords, and `low` the codewords treated as erasures.
+fn unused_error_correction(size: SymbolSize, errors: &[bool], low: &dyn Fn(usize) -> bool) -> f32 {
+    let setup = size.block_setup();
+    let num_data = size.num_data_codewords();
+    let stride = setup.num_ecc_blocks;
+    let mut used = alloc::vec![0; stride];
+    for (i, error) in errors.iter().enumerate() {
+        let block = if i < num_data {
+            i % stride
+        } else {
+            (i - num_data) % stride
+        };
+        if *error {
+            used[block] += 2;
+        } else if low(i) {
+            used[block] += 1;
+        }
+    }
+    let capacity = setup.num_ecc_per_block as f32;
+    let worst = used.into_iter().max().unwrap() as f32;
+    (1.0 - worst / capacity).max(0.0)
+}
+
+#[test]
+fn test_grade_bitmap() {
+    let code = crate::DataMatrix::encode(b"Hello, World!", SymbolSize::Square32).unwrap();
+    let bitmap = code.bitmap();
+    let report = grade_bitmap(&bitmap).unwrap();
+    assert_eq!(report.size, SymbolSize::Square32);
+    assert_eq!(report.unused_error_correction.value, 1.0);
+    assert_eq!(report.fixed_pattern_damage.value, 0.0);
+    assert_eq!(report.overall(), Grade::A);
+    assert_eq!(report.symbol_contrast, None);
+
+    // Square32 has 4 regions with 16 * 16 - 14 * 14 = 60 fixed modules each
+    let mut bits = bitmap.bits().to_vec();
+    for i in 0..24 {
+        // the bottom finder, flip 24 of 240 modules
+        bits[31 * 32 + i] = false;
+    }
+    let report = grade_bitmap(&Bitmap::new(bits.clone(), 32)).unwrap();
+    assert_eq!(report.fixed_pattern_damage.value, 0.1);
+    assert_eq!(report.fixed_pattern_damage.grade, Grade::C);
+    assert_eq!(report.unused_error_correction.grade, Grade::A);
+
+    // too many errors
+    for bit in bits.iter_mut().skip(32 * 2).step_by(3) {
+        *bit = !*bit;
+    }
+    let report = grade_bitmap(&Bitmap::new(bits, 32)).unwrap();
+    assert_eq!(report.unused_error_correction.value, 0.0);
+    assert_eq!(report.overall(), Grade::F);
+
+    assert_eq!(
+        grade_bitmap(&Bitmap::new([true; 11 * 11], 11)),
+        Err(BitmapConversionError::SymbolSize)
+    );
+}
+
+#[test]
+fn test_grade_reflectance() {
+    let code = crate::DataMatrix::encode(b"Hello, World!", SymbolSize::Square16).unwrap();
+    let bitmap = code.bitmap();
+    let modules = placement::codeword_modules(SymbolSize::Square16);
+    let reflectance: Vec<u8> = bitmap
+        .bits()
+        .iter()
+        .map(|dark| if *dark { 20 } else { 220 })
+        .collect();
+    let report = grade_reflectance(&reflectance, 16).unwrap();
+    assert_eq!(report.symbol_contrast.unwrap().grade, Grade::A);
+    assert_eq!(report.modulation, Some(Grade::A));
+    assert_eq!(report.overall(), Grade::A);
+
+    // a gray codeword with a modulation below 0.2 is an erasure, which uses
+    // one of the 12 error correction codewords
+    let mut gray = reflectance.clone();
+    for idx in modules[0] {
+        gray[idx] = if bitmap.bits()[idx] { 110 } else { 130 };
+    }
+    let report = grade_reflectance(&gray, 16).unwrap();
+    assert_eq!(report.unused_error_correction.grade, Grade::A);
+    assert_eq!(report.modulation, Some(Grade::A));
+    for codeword in &modules[1..5] {
+        for &idx in codeword {
+            gray[idx] = if bitmap.bits()[idx] { 110 } else { 130 };
+        }
+    }
+    let report = grade_reflectance(&gray, 16).unwrap();
+    // five erasures leave 7 / 12
+    assert_eq!(report.modulation, Some(Grade::B));
+
+    // low contrast
+    let faint: Vec<u8> = bitmap
+        .bits()
+        .iter()
+        .map(|dark| if *dark { 100 } else { 160 })
+        .collect();
+    let report = grade_reflectance(&faint, 16).unwrap();
+    assert_eq!(report.symbol_contrast.unwrap().grade, Grade::D);
+    assert_eq!(report.unused_error_correction.grade, Grade::A);
+
+    assert_eq!(
+        grade_reflectance(&[0; 10], 0),
+        Err(BitmapConversionError::ZeroWidth)
+    );
+    assert_eq!(
+        grade_reflectance(&[0; 10], 3),
+        Err(BitmapConversionError::DataSize)
+    );
+}
@@ -0,0 +1,118 @@
+//! Symbols for programming a scanner.
+//!
+//! A Data Matrix starting with the reader programming codeword is not
+//! transmitted by the scanner, the content is interpreted as a command
+//! to change the scanner's configuration instead. The format of
+//! the commands is not standardized, every manufacturer has its own one.
+//! Look them up in the manual of your scanner, the helpers here only take care
+//! of the encoding. Some common commands of Honeywell scanners are
+//! in [honeywell].
+//!
+//! # Example
+//!
+//! ```rust
+//! # use datamatrix::{reader_programming::Command, SymbolList};
+//! // a made up command, use the one from your scanner's manual
+//! let code = Command::new(b"SETSUFFIX0D")
+//!     .unwrap()
+//!     .encode(SymbolList::default())
+//!     .unwrap();
+//! ```
+use alloc::vec::Vec;
+
+use crate::encodation::DataEncodingError;
+use crate::{DataMatrix, DataMatrixBuilder, SymbolList};
+
+pub mod honeywell;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// A command for a scanner given as the bytes from the manufacturer's documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    bytes: Vec<u8>,
+}
+
+impl Command {
+    /// Create a command from its bytes.
+    ///
+    /// Fails with [DataEncodingError::InvalidOptions] if the command is empty.
+    pub fn new(bytes: &[u8]) -> Result<Self, DataEncodingError> {
+        if bytes.is_empty() {
+            return Err(DataEncodingError::InvalidOptions(
+                "the reader programming command is empty",
+            ));
+        }
+        Ok(Self {
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    /// Create a command consisting of several parts, e.g., a prefix, a
+    /// setting and a terminator as they are specified for some scanners.
+    pub fn from_parts(parts: &[&[u8]]) -> Result<Self, DataEncodingError> {
+        Self::new(&parts.concat())
+    }
+
+    /// Get the bytes of the command.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Encode the command as a reader programming symbol.
+    pub fn encode<I: Into<SymbolList>>(
+        &self,
+        symbol_list: I,
+    ) -> Result<DataMatrix, DataEncodingError> {
+        DataMatrixBuilder::new()
+            .with_symbol_list(symbol_list)
+            .with_reader_programming(true)
+            .encode(&self.bytes)
+    }
+}
+
+#[test]
+fn test_command() {
+    use crate::encodation::READER_PROGRAMMING;
+
+    let command = Command::new(b"AB").unwrap();
+    let code = command.encode(SymbolList::default()).unwrap();
+    assert_eq!(code.data_codewords(), &[READER_PROGRAMMING, 66, 67]);
+
+    let command = Command::from_parts(&[b"\x1b[", b"1234", b"."]).unwrap();
+    assert_eq!(command.bytes(), b"\x1b[1234.");
+    let code = command.encode(SymbolList::default()).unwrap();
+    assert_eq!(code.data_codewords()[0], READER_PROGRAMMING);
+
+    assert!(matches!(
+        Command::new(b""),
+        Err(DataEncodingError::InvalidOptions(_))
+    ));
+
+    assert_eq!(
+        DataMatrix::encode_reader_programming(b"AB", SymbolList::default()),
+        Command::new(b"AB").unwrap().encode(SymbolList::default())
+    );
+    assert!(DataMatrix::encode_reader_programming(b"", SymbolList::default()).is_err());
+}
+
+#[test]
+fn test_combinations() {
+    let builder = DataMatrixBuilder::new().with_reader_programming(true);
+    assert!(matches!(
+        builder.clone().with_gs1(true).encode(b"01"),
+        Err(DataEncodingError::InvalidOptions(_))
+    ));
+    let data = [b'A'; 100];
+    assert!(matches!(
+        builder
+            .clone()
+            .with_symbol_list(crate::SymbolSize::Square16)
+            .encode_structured(&data),
+        Err(DataEncodingError::InvalidOptions(_))
+    ));
+    // macros are not used
+    let code = builder.encode(b"[)>\x1E05\x1DA\x1E\x04").unwrap();
+    assert_eq!(code.data_codewords()[..2], [234, b'[' + 1]);
+}
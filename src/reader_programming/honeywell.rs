@@ -0,0 +1,104 @@
+//! Commands in the menu command syntax of Honeywell scanners.
+//!
+//! A command is a tag of three characters, a sub tag of three characters,
+//! the data, and `.` to store the setting permanently. Not every model
+//! supports every setting, check the manual of your scanner.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use datamatrix::reader_programming::honeywell::{self, Symbology};
+//! # use datamatrix::SymbolList;
+//! let command = honeywell::enable(Symbology::DataMatrix, true);
+//! assert_eq!(command.bytes(), b"IDMENA1.");
+//! let code = command.encode(SymbolList::default()).unwrap();
+//! ```
+use super::Command;
+use crate::encodation::DataEncodingError;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Symbologies which can be enabled or disabled with [enable].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symbology {
+    Aztec,
+    Code39,
+    Code128,
+    DataMatrix,
+    Ean13,
+    Interleaved2of5,
+    Pdf417,
+    Qr,
+    UpcA,
+}
+
+impl Symbology {
+    /// Get the tag of the symbology's settings.
+    fn tag(&self) -> &'static [u8] {
+        match self {
+            Self::Aztec => b"AZT",
+            Self::Code39 => b"C39",
+            Self::Code128 => b"128",
+            Self::DataMatrix => b"IDM",
+            Self::Ean13 => b"E13",
+            Self::Interleaved2of5 => b"I25",
+            Self::Pdf417 => b"PDF",
+            Self::Qr => b"QRC",
+            Self::UpcA => b"UPA",
+        }
+    }
+}
+
+/// Enable or disable reading the `symbology`.
+pub fn enable(symbology: Symbology, on: bool) -> Command {
+    let value: &[u8] = if on { b"1" } else { b"0" };
+    Command {
+        bytes: [symbology.tag(), b"ENA", value, b"."].concat(),
+    }
+}
+
+/// Append `suffix` to the data of all symbologies, e.g., `b"\r"` for a carriage return.
+///
+/// The bytes are written as hexadecimal numbers. Fails with
+/// [DataEncodingError::InvalidOptions] if the suffix is empty.
+pub fn set_suffix(suffix: &[u8]) -> Result<Command, DataEncodingError> {
+    if suffix.is_empty() {
+        return Err(DataEncodingError::InvalidOptions("the suffix is empty"));
+    }
+    // 99 selects all symbologies
+    let mut bytes = b"SUFBK299".to_vec();
+    for byte in suffix {
+        bytes.extend_from_slice(&hex(*byte));
+    }
+    bytes.push(b'.');
+    Ok(Command { bytes })
+}
+
+/// Get the two uppercase hexadecimal digits of `byte`.
+fn hex(byte: u8) -> [u8; 2] {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    [DIGITS[(byte >> 4) as usize], DIGITS[(byte & 0xf) as usize]]
+}
+
+#[test]
+fn test_commands() {
+    use crate::encodation::READER_PROGRAMMING;
+
+    assert_eq!(enable(Symbology::DataMatrix, true).bytes(), b"IDMENA1.");
+    assert_eq!(enable(Symbology::Code128, false).bytes(), b"128ENA0.");
+    assert_eq!(set_suffix(b"\r").unwrap().bytes(), b"SUFBK2990D.");
+    assert_eq!(set_suffix(b"\r\n").unwrap().bytes(), b"SUFBK2990D0A.");
+    assert!(matches!(
+        set_suffix(b""),
+        Err(DataEncodingError::InvalidOptions(_))
+    ));
+
+    let command = enable(Symbology::Qr, true);
+    let code = command.encode(crate::SymbolList::default()).unwrap();
+    assert_eq!(code.data_codewords()[0], READER_PROGRAMMING);
+    assert_eq!(
+        crate::data::decode_data(&code.data_codewords()[1..]).unwrap(),
+        command.bytes()
+    );
+}
@@ -0,0 +1,55 @@
+//! Details about an encoding.
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{EncodationType, SymbolSize};
+
+/// Summary of how data was encoded, see [DataMatrixBuilder::encode_with_report](crate::DataMatrixBuilder::encode_with_report).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodationReport {
+    pub(crate) size: SymbolSize,
+    pub(crate) segments: Vec<(Range<usize>, EncodationType)>,
+    pub(crate) num_unpadded: usize,
+}
+
+impl EncodationReport {
+    /// Get the chosen symbol size.
+    pub fn size(&self) -> SymbolSize {
+        self.size
+    }
+
+    /// Get the mode of each range of the input.
+    ///
+    /// The ranges cover the input, only a macro header and trailer are
+    /// not part of any range.
+    pub fn segments(&self) -> &[(Range<usize>, EncodationType)] {
+        &self.segments
+    }
+
+    /// Get the number of data codewords used by the encoded data, without padding.
+    pub fn num_encoded_codewords(&self) -> usize {
+        self.num_unpadded
+    }
+
+    /// Get the number of padding codewords which fill up the symbol.
+    pub fn num_padding_codewords(&self) -> usize {
+        self.num_data_codewords() - self.num_unpadded
+    }
+
+    /// Get the number of data codewords of the symbol, see [SymbolSize::num_data_codewords].
+    pub fn num_data_codewords(&self) -> usize {
+        self.size.num_data_codewords()
+    }
+
+    /// Get the number of error correction codewords of the symbol.
+    pub fn num_error_codewords(&self) -> usize {
+        self.size.num_error_codewords()
+    }
+
+    /// Get the fraction of the data codewords used by the data, between 0 and 1.
+    ///
+    /// A value close to 1 means that a few more characters may need the next symbol size.
+    pub fn utilization(&self) -> f32 {
+        self.num_unpadded as f32 / self.num_data_codewords() as f32
+    }
+}
@@ -0,0 +1,331 @@
+//! Implementations for the optional `serde` feature.
+//!
+//! Symbol sizes are written as `"rowsxcolumns"`, e.g., `"22x22"` or `"8x18"`,
+//! and encodation types by their name. A [DataMatrix] is written as its size
+//! and all codewords, the number of codewords is checked when reading it back.
+//! A [Bitmap] is written as its width, height, and the modules packed into bytes,
+//! row by row and the most significant bit first, with dark modules as ones.
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+
+use flagset::FlagSet;
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+
+use crate::placement::Bitmap;
+use crate::symbol_size::SYMBOL_SIZES;
+use crate::{DataMatrix, EncodationType, SymbolSize};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+const ENCODATION_NAMES: [(EncodationType, &str); 6] = [
+    (EncodationType::Ascii, "Ascii"),
+    (EncodationType::C40, "C40"),
+    (EncodationType::Text, "Text"),
+    (EncodationType::X12, "X12"),
+    (EncodationType::Edifact, "Edifact"),
+    (EncodationType::Base256, "Base256"),
+];
+
+impl Serialize for SymbolSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let setup = self.block_setup();
+        serializer.serialize_str(&format!("{}x{}", setup.height, setup.width))
+    }
+}
+
+impl<'de> Deserialize<'de> for SymbolSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        SYMBOL_SIZES
+            .iter()
+            .copied()
+            .find(|s| {
+                let setup = s.block_setup();
+                format!("{}x{}", setup.height, setup.width) == name
+            })
+            .ok_or_else(|| de::Error::custom(format!("unknown symbol size {:?}", name)))
+    }
+}
+
+impl Serialize for EncodationType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (_, name) = ENCODATION_NAMES.iter().find(|(t, _)| t == self).unwrap();
+        serializer.serialize_str(name)
+    }
+}
+
+impl<'de> Deserialize<'de> for EncodationType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        ENCODATION_NAMES
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(t, _)| *t)
+            .ok_or_else(|| de::Error::custom(format!("unknown encodation type {:?}", name)))
+    }
+}
+
+/// (De)serialize a set of encodation types as a list of names.
+pub(crate) mod encodation_types {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        types: &FlagSet<EncodationType>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(types.into_iter().count()))?;
+        for t in types.into_iter() {
+            seq.serialize_element(&t)?;
+        }
+        seq.end()
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<FlagSet<EncodationType>, D::Error> {
+        struct TypesVisitor;
+
+        impl<'de> Visitor<'de> for TypesVisitor {
+            type Value = FlagSet<EncodationType>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a list of encodation types")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut types = FlagSet::default();
+                while let Some(t) = seq.next_element::<EncodationType>()? {
+                    types |= t;
+                }
+                Ok(types)
+            }
+        }
+
+        deserializer.deserialize_seq(TypesVisitor)
+    }
+}
+
+/// Deserialize the headroom like [with_headroom](crate::DataMatrixBuilder::with_headroom),
+/// values out of range are reported when encoding.
+pub(crate) fn deserialize_headroom<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<f32, D::Error> {
+    f32::deserialize(deserializer).map(crate::data::headroom_or_invalid)
+}
+
+impl Serialize for DataMatrix {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("DataMatrix", 2)?;
+        state.serialize_field("size", &self.size)?;
+        state.serialize_field("codewords", &self.codewords)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for DataMatrix {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "DataMatrix")]
+        struct Fields {
+            size: SymbolSize,
+            codewords: Vec<u8>,
+        }
+
+        let Fields { size, codewords } = Fields::deserialize(deserializer)?;
+        if codewords.len() != size.num_codewords() {
+            return Err(de::Error::invalid_length(
+                codewords.len(),
+                &"the number of codewords of the symbol size",
+            ));
+        }
+        Ok(DataMatrix {
+            size,
+            codewords,
+            num_data_codewords: size.num_data_codewords(),
+        })
+    }
+}
+
+/// Bytes which are written with `serialize_bytes` for compact binary formats.
+struct PackedBits(Vec<u8>);
+
+impl Serialize for PackedBits {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for PackedBits {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = PackedBits;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("packed bits as bytes")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(PackedBits(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(PackedBits(v))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(PackedBits(bytes))
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
+impl Serialize for Bitmap<bool> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut packed = alloc::vec![0u8; self.bits().len().div_ceil(8)];
+        for (i, _) in self.bits().iter().enumerate().filter(|(_, dark)| **dark) {
+            packed[i / 8] |= 0x80 >> (i % 8);
+        }
+        let mut state = serializer.serialize_struct("Bitmap", 3)?;
+        state.serialize_field("width", &self.width())?;
+        state.serialize_field("height", &self.height())?;
+        state.serialize_field("bits", &PackedBits(packed))?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Bitmap<bool> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "Bitmap")]
+        struct Fields {
+            width: usize,
+            height: usize,
+            bits: PackedBits,
+        }
+
+        let Fields {
+            width,
+            height,
+            bits: PackedBits(bits),
+        } = Fields::deserialize(deserializer)?;
+        if width == 0 {
+            return Err(de::Error::invalid_value(
+                de::Unexpected::Unsigned(0),
+                &"a positive width",
+            ));
+        }
+        let len = width
+            .checked_mul(height)
+            .ok_or_else(|| de::Error::custom("the bitmap is too big"))?;
+        if bits.len() != len.div_ceil(8) {
+            return Err(de::Error::invalid_length(
+                bits.len(),
+                &"one bit for each module",
+            ));
+        }
+        let modules = (0..len).map(|i| bits[i / 8] & (0x80 >> (i % 8)) != 0);
+        Ok(Bitmap::new(modules, width))
+    }
+}
+
+#[test]
+fn test_symbol_size() {
+    assert_eq!(
+        serde_json::to_string(&SymbolSize::Square22).unwrap(),
+        "\"22x22\""
+    );
+    assert_eq!(
+        serde_json::to_string(&SymbolSize::Rect8x18).unwrap(),
+        "\"8x18\""
+    );
+    for size in SYMBOL_SIZES {
+        let json = serde_json::to_string(size).unwrap();
+        assert_eq!(serde_json::from_str::<SymbolSize>(&json).unwrap(), *size);
+    }
+    assert!(serde_json::from_str::<SymbolSize>("\"23x23\"").is_err());
+}
+
+#[test]
+fn test_data_matrix() {
+    let code = DataMatrix::encode(b"Hello, World!", SymbolSize::Square22).unwrap();
+    let json = serde_json::to_string(&code).unwrap();
+    assert!(json.starts_with("{\"size\":\"22x22\",\"codewords\":["));
+    assert_eq!(serde_json::from_str::<DataMatrix>(&json).unwrap(), code);
+
+    let wrong = json.replace("22x22", "24x24");
+    assert!(serde_json::from_str::<DataMatrix>(&wrong).is_err());
+}
+
+#[test]
+fn test_builder() {
+    use crate::{CharsetPolicy, DataMatrixBuilder, Robustness, SymbolList};
+
+    let builder = DataMatrixBuilder::new()
+        .with_encodation_types(EncodationType::Ascii | EncodationType::Edifact)
+        .with_symbol_list(SymbolList::with_extended_rectangles().enforce_rectangular())
+        .with_gs1(true)
+        .with_eci(Some(20))
+        .with_headroom(0.25)
+        .with_robustness(Robustness::High)
+        .with_charset_policy(CharsetPolicy::Transliterate)
+        .with_encodation_plan(alloc::vec![(0, EncodationType::Edifact)])
+        .with_file_id((1, 2));
+    let json = serde_json::to_string(&builder).unwrap();
+    assert!(json.contains("\"encodation_types\":[\"Ascii\",\"Edifact\"]"));
+    assert_eq!(
+        serde_json::from_str::<DataMatrixBuilder>(&json).unwrap(),
+        builder
+    );
+
+    let default = serde_json::to_string(&DataMatrixBuilder::new()).unwrap();
+    let invalid = default.replace("\"headroom\":0.0", "\"headroom\":1.5");
+    assert_ne!(invalid, default);
+    let invalid: DataMatrixBuilder = serde_json::from_str(&invalid).unwrap();
+    assert!(matches!(
+        invalid.encode(b"A"),
+        Err(crate::DataEncodingError::InvalidOptions(_))
+    ));
+}
+
+#[test]
+fn test_bitmap() {
+    let code = DataMatrix::encode(b"Hello", SymbolSize::Rect8x32).unwrap();
+    let bitmap = code.bitmap();
+    let json = serde_json::to_string(&bitmap).unwrap();
+    assert!(json.starts_with("{\"width\":32,\"height\":8,\"bits\":[170,170,170,170,"));
+    let read: Bitmap<bool> = serde_json::from_str(&json).unwrap();
+    assert_eq!(read.width(), 32);
+    assert_eq!(read.bits(), bitmap.bits());
+
+    // width 3 needs a second byte
+    let bitmap = Bitmap::new(
+        [true, false, true, false, false, false, true, true, true],
+        3,
+    );
+    let json = serde_json::to_string(&bitmap).unwrap();
+    assert_eq!(json, "{\"width\":3,\"height\":3,\"bits\":[163,128]}");
+    let read: Bitmap<bool> = serde_json::from_str(&json).unwrap();
+    assert_eq!(read.bits(), bitmap.bits());
+
+    for wrong in [
+        "{\"width\":3,\"height\":3,\"bits\":[163]}",
+        "{\"width\":0,\"height\":3,\"bits\":[]}",
+    ] {
+        assert!(
+            serde_json::from_str::<Bitmap<bool>>(wrong).is_err(),
+            "{}",
+            wrong
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Encoding of data which arrives in chunks.
+use alloc::vec::Vec;
+
+use crate::encodation::DataEncodingError;
+use crate::{DataMatrix, DataMatrixBuilder};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Encoder which collects the data in chunks before encoding it.
+///
+/// The input is buffered and encoded with the options of the builder when
+/// [finish](Self::finish) is called, so the symbol size is picked for all
+/// the data. With the `std` feature it also implements `std::io::Write`,
+/// e.g., to serialize records directly into a symbol.
+///
+/// Create it with [DataMatrixBuilder::streaming_encoder].
+///
+/// ```rust
+/// # use datamatrix::DataMatrixBuilder;
+/// let mut encoder = DataMatrixBuilder::new().streaming_encoder();
+/// for record in [&b"ID=17;"[..], b"LOT=A4;", b"QTY=250"] {
+///     encoder.feed(record).unwrap();
+/// }
+/// let code = encoder.finish().unwrap();
+/// assert_eq!(code, DataMatrixBuilder::new().encode(b"ID=17;LOT=A4;QTY=250").unwrap());
+/// ```
+#[derive(Clone, Debug)]
+pub struct StreamingEncoder {
+    builder: DataMatrixBuilder,
+    data: Vec<u8>,
+    /// Maximal number of characters which fit into a symbol.
+    limit: usize,
+}
+
+impl StreamingEncoder {
+    pub(crate) fn new(builder: DataMatrixBuilder) -> Self {
+        let limit = builder.symbol_list.max_capacity();
+        Self {
+            builder,
+            data: Vec::new(),
+            limit,
+        }
+    }
+
+    /// Append a chunk to the data.
+    ///
+    /// Fails with [DataEncodingError::TooMuchOrIllegalData] as soon as the data
+    /// can not fit into any of the symbols, the chunk is not added then.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), DataEncodingError> {
+        if self.data.len() + chunk.len() > self.limit {
+            return Err(DataEncodingError::TooMuchOrIllegalData);
+        }
+        self.data.extend_from_slice(chunk);
+        Ok(())
+    }
+
+    /// Get the number of bytes fed so far.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Check if no data was fed so far.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Encode all the data fed so far, see [DataMatrixBuilder::encode].
+    pub fn finish(self) -> Result<DataMatrix, DataEncodingError> {
+        self.builder.encode(&self.data)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for StreamingEncoder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.feed(buf).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "the data does not fit into a symbol",
+            )
+        })?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_streaming_encoder() {
+    use crate::data::Constraint;
+    use crate::SymbolSize;
+
+    let data: Vec<u8> = (0..300).map(|i| b'0' + (i % 10) as u8).collect();
+    let builder = DataMatrixBuilder::new().with_symbol_list(SymbolSize::Square32);
+    let mut encoder = builder.clone().streaming_encoder();
+    assert!(encoder.is_empty());
+    for chunk in data[..124].chunks(7) {
+        encoder.feed(chunk).unwrap();
+    }
+    assert_eq!(encoder.len(), 124);
+    // two digits per codeword is the maximum
+    assert_eq!(
+        encoder.feed(&data[..1]),
+        Err(DataEncodingError::TooMuchOrIllegalData)
+    );
+    assert_eq!(encoder.len(), 124);
+    assert_eq!(
+        encoder.clone().finish().unwrap(),
+        builder.clone().encode(&data[..124]).unwrap()
+    );
+
+    // the limit is only an upper bound
+    let mut encoder = builder.streaming_encoder();
+    encoder.feed(&[b'a'; 124]).unwrap();
+    assert_eq!(
+        encoder.finish(),
+        Err(DataEncodingError::TooMuchData {
+            needed: 84,
+            available: 62,
+            constraint: Constraint::SymbolList
+        })
+    );
+    let empty = DataMatrixBuilder::new()
+        .streaming_encoder()
+        .finish()
+        .unwrap();
+    assert_eq!(empty.size, SymbolSize::Square10);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_streaming_encoder_write() {
+    use std::io::Write;
+
+    let mut encoder = DataMatrixBuilder::new().streaming_encoder();
+    write!(encoder, "LOT-{}", 42).unwrap();
+    assert_eq!(encoder.finish(), DataMatrixBuilder::new().encode(b"LOT-42"));
+    let mut encoder = DataMatrixBuilder::new().streaming_encoder();
+    let err = encoder.write_all(&[b'0'; 5000]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,522 @@
+//! Splitting of data into multiple symbols with structured append.
+use alloc::vec::Vec;
+
+use crate::decodation::ECI_UTF8;
+use crate::encodation::DataEncodingError;
+use crate::{DataMatrix, DataMatrixBuilder};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Maximal number of symbols in a structured append sequence.
+pub(crate) const MAX_SYMBOLS: usize = 16;
+
+/// Start value of the FNV-1a hash.
+const FNV_OFFSET: u32 = 0x811c_9dc5;
+
+/// Continue the 32 bit FNV-1a `hash` with `data`.
+fn fnv1a(mut hash: u32, data: &[u8]) -> u32 {
+    for ch in data {
+        hash ^= *ch as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+/// Map a hash to the allowed range 1 to 254 of each file identification codeword.
+fn file_id_from_hash(hash: u32) -> (u8, u8) {
+    ((hash % 254 + 1) as u8, ((hash / 254) % 254 + 1) as u8)
+}
+
+/// Derive a file identification from the data.
+///
+/// This is a 32 bit FNV-1a hash mapped to the allowed range 1 to 254 of each codeword.
+pub(crate) fn file_id_for(data: &[u8]) -> (u8, u8) {
+    file_id_from_hash(fnv1a(FNV_OFFSET, data))
+}
+
+/// Get the file identification given to `builder`, if it is valid.
+fn given_file_id(builder: &DataMatrixBuilder) -> Result<Option<(u8, u8)>, DataEncodingError> {
+    match builder.file_id {
+        Some((id1, id2)) if id1 == 0 || id1 == 255 || id2 == 0 || id2 == 255 => {
+            Err(DataEncodingError::InvalidOptions(
+                "the file identification codewords must be between 1 and 254",
+            ))
+        }
+        file_id => Ok(file_id),
+    }
+}
+
+/// Check that `builder` can split data into symbols.
+fn check_builder(builder: &DataMatrixBuilder) -> Result<(), DataEncodingError> {
+    if builder.plan.is_some() {
+        return Err(DataEncodingError::InvalidOptions(
+            "an encodation plan can not be used with structured append",
+        ));
+    }
+    given_file_id(builder).map(|_| ())
+}
+
+/// Check if the byte continues a UTF-8 sequence.
+fn is_continuation(byte: Option<&u8>) -> bool {
+    byte.is_some_and(|b| b & 0b1100_0000 == 0b1000_0000)
+}
+
+/// Symbol sequence indicator for symbol `position` (starting at 0) of `count` symbols.
+fn sequence_indicator(position: usize, count: usize) -> u8 {
+    debug_assert!(position < count && (2..=MAX_SYMBOLS).contains(&count));
+    ((position << 4) | (17 - count)) as u8
+}
+
+/// Split `data` into symbols, the `eci` is repeated in every symbol.
+///
+/// With the UTF-8 ECI the data is only split between characters.
+pub(crate) fn encode(
+    builder: &DataMatrixBuilder,
+    data: &[u8],
+    eci: Option<u32>,
+) -> Result<Vec<DataMatrix>, DataEncodingError> {
+    check_builder(builder)?;
+    let file_id = given_file_id(builder)?.unwrap_or_else(|| file_id_for(data));
+
+    if let Ok(code) = builder.clone().encode_eci(data, eci) {
+        return Ok(alloc::vec![code]);
+    }
+
+    // the count is not known yet, but it does not change the size of the header
+    let header = [sequence_indicator(0, MAX_SYMBOLS), file_id.0, file_id.1];
+    let mut parts = Vec::new();
+    let mut rest = data;
+    let mut part_builder = builder.clone();
+    let mut codewords = Vec::new();
+    while !rest.is_empty() {
+        if parts.len() == MAX_SYMBOLS {
+            return Err(DataEncodingError::TooMuchOrIllegalData);
+        }
+        let mut fits = |len: usize| {
+            part_builder.encode_part_into(&rest[..len], eci, Some(header), &mut codewords)
+        };
+        let is_boundary = |len: usize| eci != Some(ECI_UTF8) || !is_continuation(rest.get(len));
+        // find the longest prefix which fits
+        let mut first = 1;
+        while !is_boundary(first) {
+            first += 1;
+        }
+        fits(first)?;
+        let (mut lower, mut upper) = (
+            first,
+            rest.len().min(builder.symbol_list.max_capacity()) + 1,
+        );
+        while upper - lower > 1 {
+            let mid = (lower + upper) / 2;
+            if fits(mid).is_ok() {
+                lower = mid;
+            } else {
+                upper = mid;
+            }
+        }
+        while !is_boundary(lower) {
+            lower -= 1;
+        }
+        parts.push(&rest[..lower]);
+        rest = &rest[lower..];
+        // GS1 only at the start of the first symbol
+        part_builder.gs1 = false;
+    }
+
+    let mut part_builder = builder.clone();
+    let mut symbols = Vec::with_capacity(parts.len());
+    for (i, part) in parts.iter().enumerate() {
+        let header = [sequence_indicator(i, parts.len()), file_id.0, file_id.1];
+        let mut codewords = Vec::new();
+        let size = part_builder
+            .encode_part_into(part, eci, Some(header), &mut codewords)?
+            .size;
+        symbols.push(DataMatrix {
+            size,
+            codewords,
+            num_data_codewords: size.num_data_codewords(),
+        });
+        part_builder.gs1 = false;
+    }
+    Ok(symbols)
+}
+
+/// Encode the data of `reader` into at most `max_symbols` symbols, see
+/// [DataMatrixBuilder::encode_reader].
+#[cfg(feature = "std")]
+pub(crate) fn encode_reader<R: std::io::Read>(
+    builder: &DataMatrixBuilder,
+    mut reader: R,
+    max_symbols: usize,
+) -> Result<Vec<(crate::SymbolSize, Vec<u8>)>, DataEncodingError> {
+    use crate::EncodationType;
+    use std::io::Read;
+
+    if !(1..=MAX_SYMBOLS).contains(&max_symbols) {
+        return Err(DataEncodingError::InvalidOptions(
+            "the number of symbols must be between 1 and 16",
+        ));
+    }
+    check_builder(builder)?;
+    let mut read = |buf: &mut Vec<u8>, len: usize| {
+        (&mut reader)
+            .take(len as u64)
+            .read_to_end(buf)
+            .map_err(|err| DataEncodingError::Io(err.kind()))
+    };
+
+    // data which might fit into one symbol is split like in encode
+    let max_capacity = builder.symbol_list.max_capacity();
+    let mut pending = Vec::new();
+    read(&mut pending, max_capacity + 1)?;
+    if pending.len() <= max_capacity {
+        let symbols = encode(builder, &pending, builder.eci)?;
+        if symbols.len() > max_symbols {
+            return Err(DataEncodingError::TooMuchOrIllegalData);
+        }
+        return Ok(symbols
+            .into_iter()
+            .map(|code| (code.size, code.data_codewords().to_vec()))
+            .collect());
+    }
+    if !builder.encodation_types.contains(EncodationType::Base256) {
+        return Err(DataEncodingError::InvalidOptions(
+            "Base256 is needed to split data from a reader",
+        ));
+    }
+
+    // the count and the file identification are written at the end,
+    // they do not change the size of the header
+    let header = [sequence_indicator(0, MAX_SYMBOLS), 1, 1];
+    let mut part_builder = builder
+        .clone()
+        .with_encodation_types(EncodationType::Base256);
+    let mut capacity = base256_capacity(&part_builder, header)?;
+    let mut hash = FNV_OFFSET;
+    let mut symbols = Vec::new();
+    loop {
+        // only one more byte is read to check for the end if all symbols are used
+        let wanted = if symbols.len() == max_symbols {
+            1
+        } else {
+            capacity
+        };
+        if pending.len() < wanted {
+            let missing = wanted - pending.len();
+            read(&mut pending, missing)?;
+        }
+        if pending.is_empty() {
+            break;
+        }
+        if symbols.len() == max_symbols {
+            return Err(DataEncodingError::TooMuchOrIllegalData);
+        }
+        let part_len = capacity.min(pending.len());
+        let part = &pending[..part_len];
+        let mut codewords = Vec::new();
+        let size = part_builder
+            .encode_part_into(part, builder.eci, Some(header), &mut codewords)?
+            .size;
+        codewords.truncate(size.num_data_codewords());
+        symbols.push((size, codewords));
+        hash = fnv1a(hash, part);
+        pending.drain(..part_len);
+        if part_builder.gs1 {
+            // GS1 only at the start of the first symbol
+            part_builder.gs1 = false;
+            capacity = base256_capacity(&part_builder, header)?;
+        }
+    }
+
+    let file_id = given_file_id(builder)?.unwrap_or_else(|| file_id_from_hash(hash));
+    let count = symbols.len();
+    for (i, (_, codewords)) in symbols.iter_mut().enumerate() {
+        // after the structured append codeword
+        codewords[1..4].copy_from_slice(&[sequence_indicator(i, count), file_id.0, file_id.1]);
+    }
+    Ok(symbols)
+}
+
+/// Get the number of bytes which fit into the biggest symbol with `header`.
+///
+/// The size of Base256 does not depend on the bytes.
+#[cfg(feature = "std")]
+fn base256_capacity(
+    builder: &DataMatrixBuilder,
+    header: [u8; 3],
+) -> Result<usize, DataEncodingError> {
+    let bytes = alloc::vec![0; builder.symbol_list.max_capacity()];
+    let mut codewords = Vec::new();
+    let mut fits = |len: usize| {
+        builder
+            .encode_data_into(&bytes[..len], None, Some(header), &mut codewords)
+            .is_ok()
+    };
+    if !fits(1) {
+        return Err(DataEncodingError::TooMuchOrIllegalData);
+    }
+    let (mut lower, mut upper) = (1, bytes.len() + 1);
+    while upper - lower > 1 {
+        let mid = (lower + upper) / 2;
+        if fits(mid) {
+            lower = mid;
+        } else {
+            upper = mid;
+        }
+    }
+    Ok(lower)
+}
+
+#[test]
+fn test_sequence_indicator() {
+    // example from the specification, symbol 3 of 7
+    assert_eq!(sequence_indicator(2, 7), 0b0010_1010);
+    assert_eq!(sequence_indicator(0, 2), 0b0000_1111);
+    assert_eq!(sequence_indicator(15, 16), 0b1111_0001);
+}
+
+#[test]
+fn test_file_id() {
+    use crate::SymbolSize;
+
+    let data: Vec<u8> = (0..100).map(|i| b'A' + (i % 26) as u8).collect();
+    let builder = DataMatrixBuilder::new().with_symbol_list(SymbolSize::Square16);
+
+    // derived from the data
+    let symbols = builder.clone().encode_structured(&data).unwrap();
+    assert_eq!(symbols.len(), 10);
+    let (id1, id2) = file_id_for(&data);
+    assert!((1..=254).contains(&id1) && (1..=254).contains(&id2));
+    for (i, symbol) in symbols.iter().enumerate() {
+        let cw = symbol.data_codewords();
+        assert_eq!(cw[..4], [233, sequence_indicator(i, 10), id1, id2]);
+    }
+    let again = builder.clone().encode_structured(&data).unwrap();
+    assert_eq!(symbols, again);
+    assert_ne!(file_id_for(&data), file_id_for(&data[1..]));
+
+    // explicit
+    let symbols = builder
+        .clone()
+        .with_file_id((42, 254))
+        .encode_structured(&data)
+        .unwrap();
+    for (i, symbol) in symbols.iter().enumerate() {
+        assert_eq!(
+            symbol.data_codewords()[..4],
+            [233, sequence_indicator(i, 10), 42, 254]
+        );
+    }
+    for file_id in [(0, 1), (1, 0), (255, 1), (1, 255)] {
+        assert!(matches!(
+            builder
+                .clone()
+                .with_file_id(file_id)
+                .encode_structured(&data),
+            Err(DataEncodingError::InvalidOptions(_))
+        ));
+    }
+}
+
+#[test]
+fn test_split() {
+    use crate::{SymbolList, SymbolSize};
+
+    let mut rnd = crate::test::random_data();
+    let data = rnd(3000);
+    let symbols = DataMatrixBuilder::new().encode_structured(&data).unwrap();
+    assert_eq!(symbols.len(), 2);
+    assert_eq!(symbols[0].size, SymbolSize::Square144);
+
+    // fits into one symbol, no header needed
+    let symbols = DataMatrixBuilder::new()
+        .encode_structured(b"Hello")
+        .unwrap();
+    assert_eq!(symbols.len(), 1);
+    assert_ne!(symbols[0].data_codewords()[0], 233);
+
+    // too many symbols
+    let builder = DataMatrixBuilder::new()
+        .with_symbol_list(SymbolList::with_whitelist([SymbolSize::Square14]));
+    assert_eq!(
+        builder.encode_structured(&data[..100]),
+        Err(DataEncodingError::TooMuchOrIllegalData)
+    );
+    // not even the header and the first character fit
+    for size in [SymbolSize::Square10, SymbolSize::Square12] {
+        let builder = DataMatrixBuilder::new().with_symbol_list(SymbolList::with_whitelist([size]));
+        let result = builder.encode_structured(&data[..100]);
+        assert!(
+            matches!(
+                result,
+                Err(DataEncodingError::TooMuchData { needed, available, .. })
+                    if available == size.num_data_codewords() && needed > available
+            ),
+            "{:?}",
+            result
+        );
+    }
+}
+
+#[test]
+fn test_mixed_sizes() {
+    use crate::{SymbolList, SymbolSize};
+
+    // A 10x10 symbol can not hold the four header codewords, so use 18x18 and 16x16.
+    // The 20 ASCII codewords do not fit into one 18x18 symbol with 18 data codewords,
+    // the rest of the first one is small enough for 16x16.
+    let builder = DataMatrixBuilder::new()
+        .with_symbol_list(SymbolList::with_whitelist([
+            SymbolSize::Square16,
+            SymbolSize::Square18,
+        ]))
+        .with_file_id((1, 2));
+    let data = b"a!b!c!d!e!f!g!h!i!j!";
+    let symbols = builder.encode_structured(data).unwrap();
+    let sizes: Vec<SymbolSize> = symbols.iter().map(|s| s.size).collect();
+    assert_eq!(sizes, [SymbolSize::Square18, SymbolSize::Square16]);
+    let parts: Vec<&[u8]> = symbols.iter().map(|s| s.data_codewords()).collect();
+    assert_eq!(crate::data::decode_structured(&parts).unwrap(), data);
+    assert_eq!(
+        symbols[0].data_codewords()[..4],
+        [233, sequence_indicator(0, 2), 1, 2]
+    );
+    assert_eq!(
+        symbols[1].data_codewords()[..4],
+        [233, sequence_indicator(1, 2), 1, 2]
+    );
+}
+
+#[test]
+fn test_eci_in_every_symbol() {
+    use crate::SymbolSize;
+
+    let text = "Ελληνικό κείμενο σε δύο σύμβολα";
+    let symbols = DataMatrixBuilder::new()
+        .with_symbol_list(SymbolSize::Square26)
+        .with_file_id((1, 2))
+        .encode_str_structured(text)
+        .unwrap();
+    assert_eq!(symbols.len(), 2);
+    for (i, symbol) in symbols.iter().enumerate() {
+        let cw = symbol.data_codewords();
+        assert_eq!(cw[..6], [233, sequence_indicator(i, 2), 1, 2, 241, 27]);
+    }
+    let codewords: Vec<&[u8]> = symbols.iter().map(|s| s.data_codewords()).collect();
+    assert_eq!(
+        crate::data::decode_str_structured(&codewords).unwrap(),
+        text
+    );
+
+    // Latin 1 needs no ECI
+    let symbols = DataMatrixBuilder::new()
+        .with_symbol_list(SymbolSize::Square16)
+        .encode_str_structured("Grüße aus der ganzen Welt, Grüße!")
+        .unwrap();
+    assert!(symbols.len() > 1);
+    for symbol in &symbols {
+        assert_ne!(symbol.data_codewords()[4], 241);
+    }
+}
+
+#[test]
+fn test_file_id_deterministic() {
+    use alloc::collections::BTreeSet;
+
+    let mut rnd = crate::test::random_data();
+    let mut ids = BTreeSet::new();
+    for i in 0..1000 {
+        let data = rnd(i % 50 + 1);
+        let id = file_id_for(&data);
+        assert_eq!(id, file_id_for(&data.clone()));
+        assert!(![0, 255].contains(&id.0) && ![0, 255].contains(&id.1));
+        ids.insert(id);
+    }
+    // almost no collisions among 64516 possible ids
+    assert!(ids.len() > 980, "{}", ids.len());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_encode_reader() {
+    use crate::decodation::decode_structured;
+    use crate::SymbolSize;
+    use std::io::{Cursor, Read};
+
+    let parts = |symbols: &[(SymbolSize, Vec<u8>)]| -> Vec<Vec<u8>> {
+        symbols
+            .iter()
+            .map(|(_, codewords)| codewords.clone())
+            .collect()
+    };
+    let mut rnd = crate::test::random_data();
+
+    // short data is split like in encode
+    let data = rnd(3000);
+    let symbols = DataMatrixBuilder::new()
+        .encode_reader(Cursor::new(&data), 16)
+        .unwrap();
+    let expected = DataMatrixBuilder::new().encode_structured(&data).unwrap();
+    let expected: Vec<Vec<u8>> = expected
+        .iter()
+        .map(|s| s.data_codewords().to_vec())
+        .collect();
+    assert_eq!(parts(&symbols), expected);
+
+    // longer data is streamed into Base256 segments
+    let data = rnd(9000);
+    let symbols = DataMatrixBuilder::new()
+        .encode_reader(Cursor::new(&data), 16)
+        .unwrap();
+    assert_eq!(symbols.len(), 6);
+    assert!(symbols[..5]
+        .iter()
+        .all(|(size, _)| *size == SymbolSize::Square144));
+    let (id1, id2) = file_id_for(&data);
+    for (i, (size, codewords)) in symbols.iter().enumerate() {
+        assert_eq!(codewords.len(), size.num_data_codewords());
+        assert_eq!(
+            codewords[..5],
+            [233, sequence_indicator(i, 6), id1, id2, 231]
+        );
+        let code = DataMatrix::from_codewords(codewords, *size).unwrap();
+        let bitmap = code.bitmap();
+        assert!(DataMatrix::decode(bitmap.bits(), bitmap.width()).is_ok());
+    }
+    let parts = parts(&symbols);
+    let parts: Vec<&[u8]> = parts.iter().map(|p| p.as_slice()).collect();
+    assert_eq!(decode_structured(&parts).unwrap(), data);
+
+    // too many symbols, the reader is not read to the end
+    let mut reader = Cursor::new(alloc::vec![0xab; 1000]);
+    let builder = DataMatrixBuilder::new().with_symbol_list(SymbolSize::Square16);
+    assert_eq!(
+        builder.clone().encode_reader(&mut reader, 16),
+        Err(DataEncodingError::TooMuchOrIllegalData)
+    );
+    assert!(reader.position() < 1000);
+    let mut reader = Cursor::new(&data);
+    assert_eq!(
+        DataMatrixBuilder::new().encode_reader(&mut reader, 5),
+        Err(DataEncodingError::TooMuchOrIllegalData)
+    );
+    assert!(reader.position() < 9000);
+    for max_symbols in [0, 17] {
+        assert!(matches!(
+            DataMatrixBuilder::new().encode_reader(Cursor::new(b"A"), max_symbols),
+            Err(DataEncodingError::InvalidOptions(_))
+        ));
+    }
+
+    struct Failing;
+    impl Read for Failing {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+    }
+    assert_eq!(
+        DataMatrixBuilder::new().encode_reader(Failing, 16),
+        Err(DataEncodingError::Io(std::io::ErrorKind::BrokenPipe))
+    );
+}
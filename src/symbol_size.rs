@@ -1,7 +1,7 @@
 use core::cmp::{Ordering, PartialOrd};
 use core::fmt::Debug;
-use core::iter::{Extend, FromIterator, IntoIterator};
-use core::ops::RangeBounds;
+use core::iter::{Extend, FromIterator, FusedIterator, IntoIterator};
+use core::ops::{Bound, RangeBounds};
 
 use alloc::collections::BTreeSet;
 
@@ -17,6 +17,11 @@ use pretty_assertions::assert_eq;
 type SymbolCollection = BTreeSet<SymbolSize>;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 /// Set of [symbol sizes](SymbolSize) the encoder is allowed to use.
 ///
 /// Specifies a list of symbol sizes the encoder will pick from. The smallest
@@ -94,6 +99,44 @@ impl SymbolList {
         self
     }
 
+    /// Only keep symbols within the given range of sizes.
+    ///
+    /// Symbols are ordered by their data capacity, see [SymbolList::iter]. This is
+    /// useful if a layout needs a minimal symbol size but still has room to grow.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataMatrix, SymbolList, SymbolSize};
+    /// let list = SymbolList::default()
+    ///     .enforce_square()
+    ///     .enforce_size_in(SymbolSize::Square22..=SymbolSize::Square52);
+    /// let code = DataMatrix::encode(b"A", list.clone()).unwrap();
+    /// assert_eq!(code.size, SymbolSize::Square22);
+    /// let code = DataMatrix::encode(&[b'A'; 60], list).unwrap();
+    /// assert_eq!(code.size, SymbolSize::Square26);
+    /// ```
+    pub fn enforce_size_in<R: RangeBounds<SymbolSize>>(mut self, bounds: R) -> Self {
+        self.symbols.retain(|s| bounds.contains(s));
+        self
+    }
+
+    /// Only keep symbols which hold exactly `count` data codewords.
+    ///
+    /// This is useful if a template prescribes the number of data codewords
+    /// instead of a symbol size. If no symbol has this capacity the list is
+    /// empty and encoding fails with
+    /// [SymbolListEmpty](crate::data::DataEncodingError::SymbolListEmpty).
+    ///
+    /// ```rust
+    /// # use datamatrix::{SymbolList, SymbolSize};
+    /// let list = SymbolList::default().enforce_data_codewords(22);
+    /// let sizes: Vec<SymbolSize> = list.iter().collect();
+    /// assert_eq!(sizes, [SymbolSize::Square20, SymbolSize::Rect12x36]);
+    /// ```
+    pub fn enforce_data_codewords(mut self, count: usize) -> Self {
+        self.symbols.retain(|s| s.num_data_codewords() == count);
+        self
+    }
+
     /// Create a symbol list containing only the given symbols.
     ///
     /// The list does not need to be sorted.
@@ -104,8 +147,11 @@ impl SymbolList {
         Self::from_iter(whitelist)
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = SymbolSize> + '_ {
-        self.symbols.iter().copied()
+    /// Iterate over the symbol sizes, from the smallest to the biggest capacity.
+    pub fn iter(&self) -> SymbolSizes<'_> {
+        SymbolSizes {
+            inner: self.symbols.iter(),
+        }
     }
 
     pub fn is_empty(&self) -> bool {
@@ -138,6 +184,33 @@ impl SymbolList {
             .unwrap_or(0)
     }
 
+    /// Get the symbol size with the biggest capacity which fits into an area.
+    ///
+    /// The `width` and `height` of the area and the `module_size` are given in the same
+    /// unit, e.g., millimeters. A quiet zone of `quiet_zone` modules is added on each
+    /// side, the specification requires at least one. Returns `None` if no symbol fits.
+    /// See [SymbolSize::physical_dimensions] for the other direction.
+    ///
+    /// ```rust
+    /// # use datamatrix::{SymbolList, SymbolSize};
+    /// // What fits into a 10 mm square with 0.3 mm modules?
+    /// let size = SymbolList::default().largest_within(10.0, 10.0, 0.3, 1);
+    /// assert_eq!(size, Some(SymbolSize::Square26));
+    /// assert_eq!(size.unwrap().num_data_codewords(), 44);
+    /// ```
+    pub fn largest_within(
+        &self,
+        width: f32,
+        height: f32,
+        module_size: f32,
+        quiet_zone: usize,
+    ) -> Option<SymbolSize> {
+        self.iter().rev().find(|size| {
+            let (w, h) = size.physical_dimensions(module_size, quiet_zone);
+            w <= width && h <= height
+        })
+    }
+
     pub(crate) fn first_symbol_big_enough_for(&self, size_needed: usize) -> Option<SymbolSize> {
         self.symbols
             .iter()
@@ -145,6 +218,19 @@ impl SymbolList {
             .copied()
     }
 
+    /// Like [first_symbol_big_enough_for](Self::first_symbol_big_enough_for), but the
+    /// fraction `headroom` of the data codewords must stay free.
+    pub(crate) fn first_symbol_with_headroom(
+        &self,
+        size_needed: usize,
+        headroom: f32,
+    ) -> Option<SymbolSize> {
+        self.symbols
+            .iter()
+            .find(|s| s.has_room_for(size_needed, headroom))
+            .copied()
+    }
+
     pub(crate) fn upper_limit_for_number_of_codewords(&self, input_len: usize) -> Option<usize> {
         if self.symbols.len() == 1 {
             self.symbols
@@ -160,11 +246,48 @@ impl SymbolList {
                     // findest smallest symbol size to hold data with base256
                     s.capacity().min >= input_len
                 })
+                // the data might still fit into the biggest symbol, e.g., with digit pairs
+                .or_else(|| self.symbols.iter().next_back())
                 .map(SymbolSize::num_data_codewords)
         }
     }
 }
 
+/// The symbol sizes the planner and the encoder pick from.
+///
+/// Unlike a [SymbolList] a single size needs no allocation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Symbols<'a> {
+    /// The sizes of the list with at most the given number of data codewords.
+    List(&'a SymbolList, usize),
+    Size(SymbolSize),
+}
+
+impl Symbols<'_> {
+    /// Like [SymbolList::first_symbol_with_headroom].
+    pub(crate) fn first_symbol_with_headroom(
+        &self,
+        size_needed: usize,
+        headroom: f32,
+    ) -> Option<SymbolSize> {
+        match *self {
+            Self::List(list, max_codewords) => list
+                .symbols
+                .iter()
+                .filter(|s| s.num_data_codewords() <= max_codewords)
+                .find(|s| s.has_room_for(size_needed, headroom))
+                .copied(),
+            Self::Size(size) => Some(size).filter(|s| s.has_room_for(size_needed, headroom)),
+        }
+    }
+}
+
+impl<'a> From<&'a SymbolList> for Symbols<'a> {
+    fn from(list: &'a SymbolList) -> Self {
+        Self::List(list, usize::MAX)
+    }
+}
+
 impl IntoIterator for SymbolList {
     type Item = SymbolSize;
     type IntoIter = <SymbolCollection as IntoIterator>::IntoIter;
@@ -174,6 +297,55 @@ impl IntoIterator for SymbolList {
     }
 }
 
+impl<'a> IntoIterator for &'a SymbolList {
+    type Item = SymbolSize;
+    type IntoIter = SymbolSizes<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the sizes in a [SymbolList], created by [SymbolList::iter].
+///
+/// The sizes are ordered by capacity, so it can be used from both ends, e.g.,
+/// to step through the sizes in a UI.
+///
+/// ```rust
+/// # use datamatrix::{SymbolList, SymbolSize};
+/// let list = SymbolList::default().enforce_square();
+/// let mut sizes = list.iter();
+/// assert_eq!(sizes.len(), 24);
+/// assert_eq!(sizes.next(), Some(SymbolSize::Square10));
+/// assert_eq!(sizes.next_back(), Some(SymbolSize::Square144));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SymbolSizes<'a> {
+    inner: alloc::collections::btree_set::Iter<'a, SymbolSize>,
+}
+
+impl Iterator for SymbolSizes<'_> {
+    type Item = SymbolSize;
+
+    fn next(&mut self) -> Option<SymbolSize> {
+        self.inner.next().copied()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for SymbolSizes<'_> {
+    fn next_back(&mut self) -> Option<SymbolSize> {
+        self.inner.next_back().copied()
+    }
+}
+
+impl ExactSizeIterator for SymbolSizes<'_> {}
+
+impl FusedIterator for SymbolSizes<'_> {}
+
 impl FromIterator<SymbolSize> for SymbolList {
     fn from_iter<T: IntoIterator<Item = SymbolSize>>(iter: T) -> Self {
         Self {
@@ -249,6 +421,51 @@ impl BlockSetup {
     }
 }
 
+/// Kind of input for the capacity of a symbol, see [SymbolSize::max_chars].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DataKind {
+    /// Digits `0` to `9`.
+    Numeric,
+    /// Uppercase letters, digits and space.
+    Alphanumeric,
+    /// Arbitrary bytes, e.g., UTF-8 encoded text.
+    Binary,
+}
+
+/// How much error correction to aim for when picking the symbol size.
+///
+/// The number of error correction codewords is fixed for each symbol size, so
+/// more error correction means a bigger symbol. See
+/// [DataMatrixBuilder::with_robustness](crate::DataMatrixBuilder::with_robustness).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Robustness {
+    /// Use the smallest symbol the data fits into.
+    #[default]
+    Min,
+    /// Use the next allowed symbol size with more error correction codewords.
+    High,
+    /// Use the allowed symbol size with the most error correction codewords.
+    Max,
+}
+
+impl Robustness {
+    /// Pick the symbol size for data which fits into `smallest`.
+    pub(crate) fn pick(&self, smallest: SymbolSize, symbol_list: &SymbolList) -> SymbolSize {
+        let mut larger = symbol_list
+            .symbols
+            .range(smallest..)
+            .copied()
+            .filter(|s| s.num_error_codewords() > smallest.num_error_codewords());
+        match self {
+            Self::Min => None,
+            Self::High => larger.next(),
+            Self::Max => larger.max_by_key(SymbolSize::num_error_codewords),
+        }
+        .unwrap_or(smallest)
+    }
+}
+
 /// The symbol sizes supported by Data Matrix.
 ///
 /// The number behind a variant, e.g., [Square10](SymbolSize::Square10),
@@ -327,7 +544,7 @@ pub enum SymbolSize {
 }
 
 #[rustfmt::skip]
-const SYMBOL_SIZES: &[SymbolSize] = &[
+pub(crate) const SYMBOL_SIZES: &[SymbolSize] = &[
     SymbolSize::Square10, SymbolSize::Square12, SymbolSize::Rect8x18, SymbolSize::Square14,
     SymbolSize::Rect8x32, SymbolSize::Square16, SymbolSize::Rect12x26, SymbolSize::Square18,
     SymbolSize::Rect8x48, SymbolSize::Square20, SymbolSize::Rect12x36, SymbolSize::Rect8x64,
@@ -343,7 +560,8 @@ const SYMBOL_SIZES: &[SymbolSize] = &[
 ];
 
 impl SymbolSize {
-    pub(crate) fn num_data_codewords(&self) -> usize {
+    /// Get the number of data codewords (the capacity) of the symbol.
+    pub fn num_data_codewords(&self) -> usize {
         match self {
             Self::Square10 => 3,
             Self::Square12 => 5,
@@ -397,6 +615,15 @@ impl SymbolSize {
         }
     }
 
+    /// Can `size_needed` data codewords be written and still leave the fraction `headroom` free?
+    fn has_room_for(&self, size_needed: usize, headroom: f32) -> bool {
+        if headroom > 0.0 {
+            size_needed as f32 <= self.num_data_codewords() as f32 * (1.0 - headroom)
+        } else {
+            size_needed <= self.num_data_codewords()
+        }
+    }
+
     pub fn is_square(&self) -> bool {
         matches!(
             self,
@@ -452,6 +679,91 @@ impl SymbolSize {
         )
     }
 
+    /// Get the size of the symbol in modules, and the size of the mapping matrix.
+    ///
+    /// Both are given as `(width, height)`. The first one is the total size
+    /// without quiet zone. The mapping matrix is what remains of the symbol after removing
+    /// the finder and alignment patterns of all data regions, it contains the codewords.
+    ///
+    /// ```rust
+    /// # use datamatrix::SymbolSize;
+    /// assert_eq!(SymbolSize::Square32.module_dimensions(), ((32, 32), (28, 28)));
+    /// assert_eq!(SymbolSize::Rect16x48.module_dimensions(), ((48, 16), (44, 14)));
+    /// ```
+    pub fn module_dimensions(&self) -> ((usize, usize), (usize, usize)) {
+        let setup = self.block_setup();
+        (
+            (setup.width, setup.height),
+            (setup.content_width(), setup.content_height()),
+        )
+    }
+
+    /// Get the printed `(width, height)` of the symbol with a quiet zone.
+    ///
+    /// The `module_size` is the size of one module in any unit, and `quiet_zone`
+    /// is the number of light modules added on each side.
+    ///
+    /// ```rust
+    /// # use datamatrix::SymbolSize;
+    /// assert_eq!(SymbolSize::Rect8x18.physical_dimensions(0.5, 1), (10.0, 5.0));
+    /// ```
+    pub fn physical_dimensions(&self, module_size: f32, quiet_zone: usize) -> (f32, f32) {
+        let ((width, height), _) = self.module_dimensions();
+        (
+            (width + 2 * quiet_zone) as f32 * module_size,
+            (height + 2 * quiet_zone) as f32 * module_size,
+        )
+    }
+
+    /// Get the next bigger symbol size in `symbol_list`.
+    ///
+    /// Symbol sizes are ordered by their data capacity. Use, for example,
+    /// [SymbolList::enforce_square] to only step through square sizes.
+    ///
+    /// ```rust
+    /// # use datamatrix::{SymbolList, SymbolSize};
+    /// let squares = SymbolList::default().enforce_square();
+    /// assert_eq!(SymbolSize::Square10.next_larger(&squares), Some(SymbolSize::Square12));
+    /// assert_eq!(SymbolSize::Square144.next_larger(&squares), None);
+    /// ```
+    pub fn next_larger(&self, symbol_list: &SymbolList) -> Option<SymbolSize> {
+        symbol_list
+            .symbols
+            .range((Bound::Excluded(*self), Bound::Unbounded))
+            .next()
+            .copied()
+    }
+
+    /// Get the next smaller symbol size in `symbol_list`.
+    ///
+    /// See [next_larger()](Self::next_larger).
+    pub fn next_smaller(&self, symbol_list: &SymbolList) -> Option<SymbolSize> {
+        symbol_list.symbols.range(..*self).next_back().copied()
+    }
+
+    /// Suggest a symbol size in `symbol_list` for `data_len` bytes of data.
+    ///
+    /// This is only an estimate, no encodation is done. It is
+    /// conservative and assumes the bytes are encoded in Base256, so the
+    /// actual encoding might fit into a smaller symbol. Use it, e.g., to prefill
+    /// a selection in a user interface. Returns `None` if the estimate does not
+    /// fit into any symbol of the list.
+    ///
+    /// ```rust
+    /// # use datamatrix::{SymbolList, SymbolSize};
+    /// let squares = SymbolList::default().enforce_square();
+    /// assert_eq!(SymbolSize::recommended_for(20, &squares), Some(SymbolSize::Square20));
+    /// assert_eq!(SymbolSize::recommended_for(5000, &squares), None);
+    /// ```
+    pub fn recommended_for(data_len: usize, symbol_list: &SymbolList) -> Option<SymbolSize> {
+        if data_len == 0 {
+            return symbol_list.iter().next();
+        }
+        // latch and one or two length bytes
+        let header = if data_len < 250 { 2 } else { 3 };
+        symbol_list.first_symbol_big_enough_for(data_len + header)
+    }
+
     fn capacity(&self) -> Capacity {
         match self {
             Self::Square10 => Capacity::new(6, 1),
@@ -897,12 +1209,97 @@ impl SymbolSize {
         }
     }
 
-    #[cfg(test)]
-    pub(crate) fn num_codewords(&self) -> usize {
-        let num_data = self.num_data_codewords();
+    /// Get the number of error correction codewords of the symbol.
+    ///
+    /// Reed-Solomon can correct up to half as many errors, or as many erasures,
+    /// as there are error correction codewords. For the bigger symbols the
+    /// codewords are split into interleaved blocks, the limit then applies to
+    /// each block separately.
+    pub fn num_error_codewords(&self) -> usize {
+        let setup = self.block_setup();
+        setup.num_ecc_blocks * setup.num_ecc_per_block
+    }
+
+    /// Get the number of interleaved Reed-Solomon blocks of the symbol.
+    ///
+    /// The data and error correction codewords are distributed over the blocks
+    /// round-robin. Most sizes use one block, up to ten are used by 144x144.
+    pub fn error_blocks(&self) -> usize {
+        self.block_setup().num_ecc_blocks
+    }
+
+    /// Get the number of wrong codewords the error correction can repair.
+    ///
+    /// This is half the error correction codewords of each block, summed up
+    /// over the [blocks](Self::error_blocks). Since the blocks are interleaved,
+    /// any run of this many consecutive codewords within the data or within the
+    /// error correction codewords can be repaired, e.g., the first ones. For 144x144
+    /// the blocks have different lengths and a run across both parts can fail.
+    /// Otherwise only half the error correction codewords of one block are
+    /// guaranteed. See [DataMatrix::damaged_bitmap](crate::DataMatrix::damaged_bitmap)
+    /// for creating test symbols.
+    ///
+    /// ```rust
+    /// # use datamatrix::SymbolSize;
+    /// assert_eq!(SymbolSize::Square10.correctable_codewords(), 2);
+    /// assert_eq!(SymbolSize::Square144.correctable_codewords(), 310);
+    /// ```
+    pub fn correctable_codewords(&self) -> usize {
+        let setup = self.block_setup();
+        setup.num_ecc_blocks * (setup.num_ecc_per_block / 2)
+    }
+
+    /// Get the maximal number of characters of the given kind which fit into the symbol.
+    ///
+    /// These are the numbers of the capacity tables in the specification,
+    /// except for the Base256 limit in 144x144.
+    /// Digits are encoded in pairs, alphanumeric data by C40 with three characters
+    /// in two codewords, and binary data by Base256 with a latch and a length.
+    ///
+    /// ```rust
+    /// # use datamatrix::{DataKind, SymbolSize};
+    /// assert_eq!(SymbolSize::Square10.max_chars(DataKind::Numeric), 6);
+    /// assert_eq!(SymbolSize::Square10.max_chars(DataKind::Alphanumeric), 3);
+    /// assert_eq!(SymbolSize::Square10.max_chars(DataKind::Binary), 1);
+    /// ```
+    pub fn max_chars(&self, kind: DataKind) -> usize {
+        let n = self.num_data_codewords();
+        match kind {
+            DataKind::Numeric => 2 * n,
+            DataKind::Alphanumeric => {
+                // C40 latch, one ASCII character without UNLATCH in a single codeword left
+                let c40 = (n - 1) / 2 * 3 + (n - 1) % 2;
+                c40.max(n)
+            }
+            // a single length byte suffices if the data fills the symbol
+            DataKind::Binary => (n - 2).min(1555),
+        }
+    }
+
+    /// Get the total number of codewords, data and error correction, of the symbol.
+    pub fn num_codewords(&self) -> usize {
+        self.num_data_codewords() + self.num_error_codewords()
+    }
+
+    /// Rough score how hard the symbol is to scan, between 0 (easy) and 1 (hard).
+    ///
+    /// This is a heuristic and not based on any standard. It only looks at
+    /// the number of modules of the shorter side and the fraction of
+    /// error correction codewords, the print quality and size are not known.
+    /// Use it to compare symbol sizes, for a given physical size a smaller
+    /// symbol has bigger modules but less error correction.
+    ///
+    /// ```rust
+    /// # use datamatrix::SymbolSize;
+    /// assert!(SymbolSize::Square10.scan_difficulty() > SymbolSize::Square144.scan_difficulty());
+    /// ```
+    pub fn scan_difficulty(&self) -> f32 {
         let setup = self.block_setup();
-        let num_error = setup.num_ecc_blocks * setup.num_ecc_per_block;
-        num_data + num_error
+        let min_side = setup.width.min(setup.height) as f32;
+        let data_ratio = self.num_data_codewords() as f32 / self.num_codewords() as f32;
+        // the shortest side has 8 modules
+        let scale = 8.0 / min_side;
+        data_ratio * scale * scale
     }
 
     pub(crate) fn has_padding_modules(self) -> bool {
@@ -952,6 +1349,145 @@ fn test_symbol_size_order() {
     assert_eq!(&all, &all2,);
 }
 
+#[test]
+fn test_next_larger_smaller() {
+    for list in [
+        SymbolList::default(),
+        SymbolList::all(),
+        SymbolList::default().enforce_square(),
+        SymbolList::all().enforce_rectangular(),
+    ] {
+        let sizes: Vec<SymbolSize> = list.iter().collect();
+        let mut up = vec![sizes[0]];
+        while let Some(next) = up.last().unwrap().next_larger(&list) {
+            up.push(next);
+        }
+        assert_eq!(up, sizes);
+        let mut down = vec![*sizes.last().unwrap()];
+        while let Some(next) = down.last().unwrap().next_smaller(&list) {
+            down.push(next);
+        }
+        down.reverse();
+        assert_eq!(down, sizes);
+    }
+
+    // the size itself does not need to be in the list
+    let rects = SymbolList::default().enforce_rectangular();
+    assert_eq!(
+        SymbolSize::Square16.next_larger(&rects),
+        Some(SymbolSize::Rect12x26)
+    );
+    assert_eq!(
+        SymbolSize::Square16.next_smaller(&rects),
+        Some(SymbolSize::Rect8x32)
+    );
+    assert_eq!(SymbolSize::Rect8x18.next_smaller(&rects), None);
+}
+
+#[test]
+fn test_recommended_for() {
+    use crate::data::encode_data;
+    use crate::EncodationType;
+
+    let lists = [
+        SymbolList::default(),
+        SymbolList::default().enforce_square(),
+        SymbolList::with_extended_rectangles().enforce_rectangular(),
+    ];
+    let mut rnd = crate::test::random_data();
+    for symbol_list in lists {
+        for len in (0..100).chain((100..2000).step_by(37)) {
+            let data = rnd(len);
+            let recommended = SymbolSize::recommended_for(len, &symbol_list);
+            match encode_data(&data, &symbol_list, None, EncodationType::all(), false) {
+                Ok((_, size)) => assert!(recommended.unwrap() >= size, "len {}", len),
+                Err(_) => assert_eq!(recommended, None, "len {}", len),
+            }
+        }
+    }
+    assert_eq!(
+        SymbolSize::recommended_for(0, &SymbolList::default()),
+        Some(SymbolSize::Square10)
+    );
+}
+
+#[test]
+fn test_scan_difficulty() {
+    let squares: Vec<SymbolSize> = SymbolList::default().enforce_square().iter().collect();
+    for pair in squares.windows(2) {
+        assert!(
+            pair[0].scan_difficulty() > pair[1].scan_difficulty(),
+            "{:?}",
+            pair
+        );
+    }
+    for size in all::<SymbolSize>() {
+        let difficulty = size.scan_difficulty();
+        assert!(difficulty > 0.0 && difficulty <= 1.0, "{:?}", size);
+    }
+    assert!(SymbolSize::Rect8x18.scan_difficulty() > SymbolSize::Square22.scan_difficulty());
+}
+
+#[test]
+fn test_enforce_data_codewords() {
+    let sizes = |count| -> Vec<SymbolSize> {
+        SymbolList::all()
+            .enforce_data_codewords(count)
+            .iter()
+            .collect()
+    };
+    assert_eq!(sizes(3), vec![SymbolSize::Square10]);
+    assert_eq!(sizes(5), vec![SymbolSize::Square12, SymbolSize::Rect8x18]);
+    assert_eq!(sizes(32), vec![SymbolSize::Rect16x36, SymbolSize::Rect8x80]);
+    assert_eq!(sizes(1558), vec![SymbolSize::Square144]);
+    for size in SYMBOL_SIZES {
+        assert!(sizes(size.num_data_codewords()).contains(size));
+    }
+    for count in [0, 1, 4, 7, 1557, 1559] {
+        assert!(sizes(count).is_empty(), "{}", count);
+    }
+
+    let list = SymbolList::default().enforce_data_codewords(7);
+    assert_eq!(
+        crate::DataMatrix::encode(b"A", list),
+        Err(crate::data::DataEncodingError::SymbolListEmpty)
+    );
+    let list = SymbolList::default().enforce_data_codewords(12);
+    let code = crate::DataMatrix::encode(b"A", list).unwrap();
+    assert_eq!(code.size, SymbolSize::Square16);
+}
+
+#[test]
+fn test_size_range() {
+    let sizes = |list: SymbolList| -> Vec<SymbolSize> { list.iter().collect() };
+    assert_eq!(
+        sizes(SymbolList::default().enforce_size_in(SymbolSize::Square16..SymbolSize::Square20)),
+        vec![
+            SymbolSize::Square16,
+            SymbolSize::Rect12x26,
+            SymbolSize::Square18
+        ]
+    );
+    assert_eq!(
+        sizes(SymbolList::all().enforce_size_in(..=SymbolSize::Square10)),
+        vec![SymbolSize::Square10]
+    );
+    let large = sizes(SymbolList::default().enforce_size_in(SymbolSize::Square120..));
+    assert_eq!(
+        large,
+        vec![
+            SymbolSize::Square120,
+            SymbolSize::Square132,
+            SymbolSize::Square144
+        ]
+    );
+    // the range of a rectangle includes squares of similar capacity
+    let rectangles = SymbolSize::Rect16x36..=SymbolSize::Rect16x48;
+    let list = SymbolList::default().enforce_size_in(rectangles);
+    assert!(list.contains(&SymbolSize::Square26));
+    assert!(!list.contains(&SymbolSize::Square32));
+}
+
 #[test]
 fn test_iter_all_symbols() {
     let mut all: Vec<SymbolSize> = all::<SymbolSize>().collect();
@@ -959,6 +1495,43 @@ fn test_iter_all_symbols() {
     assert_eq!(&all, SYMBOL_SIZES,);
 }
 
+#[test]
+fn test_symbol_sizes_iter() {
+    let list = SymbolList::default().enforce_rectangular();
+    let forward: Vec<SymbolSize> = list.iter().collect();
+    assert_eq!(
+        forward,
+        [
+            SymbolSize::Rect8x18,
+            SymbolSize::Rect8x32,
+            SymbolSize::Rect12x26,
+            SymbolSize::Rect12x36,
+            SymbolSize::Rect16x36,
+            SymbolSize::Rect16x48,
+        ]
+    );
+    let mut backward: Vec<SymbolSize> = list.iter().rev().collect();
+    backward.reverse();
+    assert_eq!(backward, forward);
+
+    let mut sizes = list.iter();
+    assert_eq!(sizes.len(), 6);
+    assert_eq!(sizes.next(), Some(SymbolSize::Rect8x18));
+    assert_eq!(sizes.next_back(), Some(SymbolSize::Rect16x48));
+    assert_eq!(sizes.len(), 4);
+    assert_eq!(sizes.by_ref().count(), 4);
+    assert_eq!((sizes.next(), sizes.next_back()), (None, None));
+
+    for list in [
+        SymbolList::all(),
+        SymbolList::default(),
+        SymbolList::from([]),
+    ] {
+        assert_eq!(list.iter().len(), list.iter().count());
+        assert_eq!((&list).into_iter().count(), list.iter().len());
+    }
+}
+
 #[test]
 fn test_size_candidates_for_non_auto() {
     let list: SymbolList = SymbolSize::Square10.into();
@@ -1084,7 +1657,7 @@ fn test_list_all() {
     assert_eq!(SymbolList::all().iter().count(), SYMBOL_SIZES.len());
 
     for size in SymbolList::all() {
-        assert!(SYMBOL_SIZES.iter().any(|s| *s == size));
+        assert!(SYMBOL_SIZES.contains(&size));
     }
 }
 
@@ -1100,3 +1673,254 @@ fn test_content_sizes_consistency() {
         assert_eq!(len, setup.content_width() * setup.content_height());
     }
 }
+
+#[test]
+fn test_large_square_regions() {
+    // (size, data regions per side, region size, data codewords, blocks, ecc per block)
+    let table = [
+        (SymbolSize::Square104, 4, 24, 816, 6, 56),
+        (SymbolSize::Square120, 6, 18, 1050, 6, 68),
+        (SymbolSize::Square132, 6, 20, 1304, 8, 62),
+        (SymbolSize::Square144, 6, 22, 1558, 10, 62),
+    ];
+    for (size, regions, region_size, data, blocks, ecc) in table {
+        let setup = size.block_setup();
+        assert_eq!(setup.extra_horizontal_alignments + 1, regions, "{:?}", size);
+        assert_eq!(setup.extra_vertical_alignments + 1, regions, "{:?}", size);
+        assert_eq!(setup.content_width(), regions * region_size, "{:?}", size);
+        assert_eq!(setup.content_height(), regions * region_size, "{:?}", size);
+        assert_eq!(size.num_data_codewords(), data, "{:?}", size);
+        assert_eq!(setup.num_ecc_blocks, blocks, "{:?}", size);
+        assert_eq!(setup.num_ecc_per_block, ecc, "{:?}", size);
+
+        // check the alignment pattern of every data region in a rendered symbol
+        let code = crate::DataMatrix::from_codewords(&vec![129; data], size).unwrap();
+        let bitmap = code.bitmap();
+        assert_eq!(bitmap.width(), setup.width);
+        assert_eq!(bitmap.height(), setup.height);
+        let bits = bitmap.bits();
+        let bit = |x: usize, y: usize| bits[y * setup.width + x];
+        let step = region_size + 2;
+        for r in 0..regions {
+            let top = r * step;
+            let bottom = top + step - 1;
+            let left = r * step;
+            let right = left + step - 1;
+            for i in 0..setup.width {
+                assert!(bit(i, bottom), "{:?} solid row {}", size, bottom);
+                assert!(bit(left, i), "{:?} solid column {}", size, left);
+                assert_eq!(bit(i, top), i % 2 == 0, "{:?} dotted row {}", size, top);
+                assert_eq!(
+                    bit(right, i),
+                    i % 2 == 1,
+                    "{:?} dotted column {}",
+                    size,
+                    right
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_codeword_counts() {
+    use crate::DataMatrix;
+
+    for size in SymbolList::all() {
+        let code = DataMatrix::encode(b"OK", size).unwrap();
+        assert_eq!(code.data_codewords().len(), size.num_data_codewords());
+        assert_eq!(code.codewords().len(), size.num_codewords());
+        assert_eq!(
+            size.num_data_codewords() + size.num_error_codewords(),
+            size.num_codewords()
+        );
+    }
+    assert_eq!(SymbolSize::Square10.num_error_codewords(), 5);
+    assert_eq!(SymbolSize::Square144.num_error_codewords(), 620);
+    assert_eq!(SymbolSize::Rect8x18.num_error_codewords(), 7);
+}
+
+#[test]
+fn test_error_blocks() {
+    // from the table in the specification
+    assert_eq!(SymbolSize::Square10.error_blocks(), 1);
+    assert_eq!(SymbolSize::Square44.error_blocks(), 1);
+    assert_eq!(SymbolSize::Square48.error_blocks(), 1);
+    assert_eq!(SymbolSize::Square52.error_blocks(), 2);
+    assert_eq!(SymbolSize::Square64.error_blocks(), 2);
+    assert_eq!(SymbolSize::Square72.error_blocks(), 4);
+    assert_eq!(SymbolSize::Square104.error_blocks(), 6);
+    assert_eq!(SymbolSize::Square132.error_blocks(), 8);
+    assert_eq!(SymbolSize::Square144.error_blocks(), 10);
+    assert_eq!(SymbolSize::Rect16x48.error_blocks(), 1);
+    for size in SymbolList::all() {
+        assert_eq!(size.num_error_codewords() % size.error_blocks(), 0);
+    }
+}
+
+#[test]
+fn test_correctable_codewords() {
+    // from the table in the specification
+    assert_eq!(SymbolSize::Square12.correctable_codewords(), 3);
+    assert_eq!(SymbolSize::Square14.correctable_codewords(), 5);
+    assert_eq!(SymbolSize::Square52.correctable_codewords(), 42);
+    assert_eq!(SymbolSize::Square104.correctable_codewords(), 168);
+    assert_eq!(SymbolSize::Rect8x18.correctable_codewords(), 3);
+    assert_eq!(SymbolSize::Rect16x48.correctable_codewords(), 14);
+}
+
+#[test]
+fn test_max_chars() {
+    use crate::DataMatrixBuilder;
+    use SymbolSize::*;
+
+    // published capacity tables: numeric, alphanumeric, binary
+    #[rustfmt::skip]
+    let table = [
+        (Square10, 6, 3, 1), (Square12, 10, 6, 3), (Square14, 16, 10, 6),
+        (Square16, 24, 16, 10), (Square18, 36, 25, 16), (Square20, 44, 31, 20),
+        (Square22, 60, 43, 28), (Square24, 72, 52, 34), (Square26, 88, 64, 42),
+        (Square32, 124, 91, 60), (Square36, 172, 127, 84), (Square40, 228, 169, 112),
+        (Square44, 288, 214, 142), (Square48, 348, 259, 172), (Square52, 408, 304, 202),
+        (Square64, 560, 418, 278), (Square72, 736, 550, 366), (Square80, 912, 682, 454),
+        (Square88, 1152, 862, 574), (Square96, 1392, 1042, 694), (Square104, 1632, 1222, 814),
+        (Square120, 2100, 1573, 1048), (Square132, 2608, 1954, 1302), (Square144, 3116, 2335, 1556),
+        (Rect8x18, 10, 6, 3), (Rect8x32, 20, 13, 8), (Rect12x26, 32, 22, 14),
+        (Rect12x36, 44, 31, 20), (Rect16x36, 64, 46, 30), (Rect16x48, 98, 72, 47),
+    ];
+    for (size, numeric, alphanumeric, binary) in table {
+        assert_eq!(size.max_chars(DataKind::Numeric), numeric, "{:?}", size);
+        assert_eq!(
+            size.max_chars(DataKind::Alphanumeric),
+            alphanumeric,
+            "{:?}",
+            size
+        );
+        // the length of Base256 is limited to 1555 bytes
+        assert_eq!(
+            size.max_chars(DataKind::Binary),
+            binary.min(1555),
+            "{:?}",
+            size
+        );
+    }
+
+    // the encoder reaches the capacity
+    for size in SymbolList::all() {
+        let builder = DataMatrixBuilder::new().with_symbol_list(size);
+        for (kind, ch) in [
+            (DataKind::Numeric, b'7'),
+            (DataKind::Alphanumeric, b'A'),
+            (DataKind::Binary, 0xab),
+        ] {
+            let mut data = vec![ch; size.max_chars(kind)];
+            assert!(
+                builder.clone().encode(&data).is_ok(),
+                "{:?} {:?}",
+                size,
+                kind
+            );
+            data.push(ch);
+            assert!(
+                builder.clone().encode(&data).is_err(),
+                "{:?} {:?}",
+                size,
+                kind
+            );
+        }
+    }
+}
+
+#[test]
+fn test_largest_within() {
+    let list = SymbolList::default();
+    // 10 mm are 33.3 modules of 0.3 mm
+    assert_eq!(
+        list.largest_within(10.0, 10.0, 0.3, 1),
+        Some(SymbolSize::Square26)
+    );
+    assert_eq!(
+        list.largest_within(10.0, 10.0, 0.3, 0),
+        Some(SymbolSize::Square32)
+    );
+    // a wide label
+    assert_eq!(
+        list.largest_within(25.0, 9.0, 0.5, 1),
+        Some(SymbolSize::Rect16x48)
+    );
+    assert_eq!(
+        list.clone()
+            .enforce_square()
+            .largest_within(25.0, 9.0, 0.5, 1),
+        Some(SymbolSize::Square16)
+    );
+    // too small for 10x10 with the quiet zone
+    assert_eq!(list.largest_within(5.9, 5.9, 0.5, 1), None);
+    assert_eq!(
+        list.largest_within(6.0, 6.0, 0.5, 1),
+        Some(SymbolSize::Square10)
+    );
+    assert_eq!(
+        SymbolList::from([]).largest_within(100.0, 100.0, 0.1, 1),
+        None
+    );
+
+    for size in list.iter() {
+        let (w, h) = size.physical_dimensions(1.0, 1);
+        assert_eq!(
+            list.largest_within(w, h, 1.0, 1).map(|s| s >= size),
+            Some(true)
+        );
+    }
+}
+
+#[test]
+fn test_module_dimensions() {
+    assert_eq!(SymbolSize::Square10.module_dimensions(), ((10, 10), (8, 8)));
+    assert_eq!(
+        SymbolSize::Square144.module_dimensions(),
+        ((144, 144), (132, 132))
+    );
+    assert_eq!(SymbolSize::Rect8x18.module_dimensions(), ((18, 8), (16, 6)));
+    assert_eq!(
+        SymbolSize::Rect12x64.module_dimensions(),
+        ((64, 12), (56, 10))
+    );
+}
+
+#[test]
+fn test_dmre_parameters() {
+    use SymbolSize::*;
+
+    // rows, columns, data codewords, error codewords from ISO/IEC 21471
+    let table = [
+        (Rect8x48, 8, 48, 18, 15),
+        (Rect8x64, 8, 64, 24, 18),
+        (Rect8x80, 8, 80, 32, 22),
+        (Rect8x96, 8, 96, 38, 28),
+        (Rect8x120, 8, 120, 49, 32),
+        (Rect8x144, 8, 144, 63, 36),
+        (Rect12x64, 12, 64, 43, 27),
+        (Rect12x88, 12, 88, 64, 36),
+        (Rect16x64, 16, 64, 62, 36),
+        (Rect20x36, 20, 36, 44, 28),
+        (Rect20x44, 20, 44, 56, 34),
+        (Rect20x64, 20, 64, 84, 42),
+        (Rect22x48, 22, 48, 72, 38),
+        (Rect24x48, 24, 48, 80, 41),
+        (Rect24x64, 24, 64, 108, 46),
+        (Rect26x40, 26, 40, 70, 38),
+        (Rect26x48, 26, 48, 90, 42),
+        (Rect26x64, 26, 64, 118, 50),
+    ];
+    let dmre: Vec<SymbolSize> = SymbolList::all().iter().filter(|s| s.is_dmre()).collect();
+    assert_eq!(dmre.len(), table.len());
+    for (size, rows, cols, data, ecc) in table {
+        assert!(size.is_dmre() && !size.is_square());
+        let setup = size.block_setup();
+        assert_eq!((setup.height, setup.width), (rows, cols), "{:?}", size);
+        assert_eq!(size.num_data_codewords(), data, "{:?}", size);
+        assert_eq!(size.num_error_codewords(), ecc, "{:?}", size);
+    }
+    assert!(!SymbolList::default().iter().any(|s| s.is_dmre()));
+}
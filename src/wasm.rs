@@ -0,0 +1,221 @@
+//! Bindings for JavaScript with `wasm-bindgen`, requires the feature `wasm`.
+//!
+//! Build a package for the browser with, e.g., `wasm-pack build --features wasm`
+//! and use it like this:
+//!
+//! ```js
+//! import { encodeSvg, WasmOptions } from "datamatrix";
+//!
+//! const options = new WasmOptions();
+//! options.square = true;
+//! options.quietZone = 2;
+//! const svg = encodeSvg("Hello, browser!", options);
+//! ```
+use alloc::{string::String, vec::Vec};
+use wasm_bindgen::prelude::*;
+
+use crate::encodation::DataEncodingError;
+use crate::placement::SvgOptions;
+use crate::{DataMatrix, DataMatrixBuilder, SymbolList};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Options for [encode_svg] and [encode_modules].
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct WasmOptions {
+    square: bool,
+    gs1: bool,
+    use_macros: bool,
+    module_size: f32,
+    quiet_zone: usize,
+}
+
+#[wasm_bindgen]
+impl WasmOptions {
+    /// Any symbol size, macros enabled, and modules of size 1 with a quiet zone of one module.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            square: false,
+            gs1: false,
+            use_macros: true,
+            module_size: 1.0,
+            quiet_zone: 1,
+        }
+    }
+
+    /// Only use square symbols.
+    #[wasm_bindgen(getter)]
+    pub fn square(&self) -> bool {
+        self.square
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_square(&mut self, square: bool) {
+        self.square = square;
+    }
+
+    /// Encode GS1 data, see [DataMatrixBuilder::with_gs1].
+    #[wasm_bindgen(getter)]
+    pub fn gs1(&self) -> bool {
+        self.gs1
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_gs1(&mut self, gs1: bool) {
+        self.gs1 = gs1;
+    }
+
+    /// Use the macro codewords where possible, see [DataMatrixBuilder::with_macros].
+    #[wasm_bindgen(getter, js_name = useMacros)]
+    pub fn use_macros(&self) -> bool {
+        self.use_macros
+    }
+
+    #[wasm_bindgen(setter, js_name = useMacros)]
+    pub fn set_use_macros(&mut self, use_macros: bool) {
+        self.use_macros = use_macros;
+    }
+
+    /// Size of a module in the SVG, see [SvgOptions::with_module_size].
+    #[wasm_bindgen(getter, js_name = moduleSize)]
+    pub fn module_size(&self) -> f32 {
+        self.module_size
+    }
+
+    #[wasm_bindgen(setter, js_name = moduleSize)]
+    pub fn set_module_size(&mut self, module_size: f32) {
+        self.module_size = module_size;
+    }
+
+    /// Number of light modules on each side in the SVG.
+    #[wasm_bindgen(getter, js_name = quietZone)]
+    pub fn quiet_zone(&self) -> usize {
+        self.quiet_zone
+    }
+
+    #[wasm_bindgen(setter, js_name = quietZone)]
+    pub fn set_quiet_zone(&mut self, quiet_zone: usize) {
+        self.quiet_zone = quiet_zone;
+    }
+}
+
+impl Default for WasmOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WasmOptions {
+    fn encode(&self, text: &str) -> Result<DataMatrix, DataEncodingError> {
+        let mut symbol_list = SymbolList::default();
+        if self.square {
+            symbol_list = symbol_list.enforce_square();
+        }
+        DataMatrixBuilder::new()
+            .with_symbol_list(symbol_list)
+            .with_gs1(self.gs1)
+            .with_macros(self.use_macros)
+            .encode_str(text)
+    }
+
+    fn svg(&self, text: &str) -> Result<String, DataEncodingError> {
+        if self.module_size.is_nan() || self.module_size <= 0.0 {
+            return Err(DataEncodingError::InvalidOptions(
+                "the module size must be positive",
+            ));
+        }
+        let options = SvgOptions::new()
+            .with_module_size(self.module_size)
+            .with_quiet_zone(self.quiet_zone);
+        Ok(self.encode(text)?.bitmap().to_svg(&options))
+    }
+
+    fn symbol(&self, text: &str) -> Result<WasmSymbol, DataEncodingError> {
+        let bitmap = self.encode(text)?.bitmap();
+        Ok(WasmSymbol {
+            width: bitmap.width(),
+            modules: bitmap.bits().iter().map(|dark| *dark as u8).collect(),
+        })
+    }
+}
+
+/// Modules of an encoded symbol, see [encode_modules].
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmSymbol {
+    width: usize,
+    modules: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl WasmSymbol {
+    /// Number of modules per row.
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of rows.
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> usize {
+        self.modules.len() / self.width
+    }
+
+    /// The modules row by row as `Uint8Array`, `1` for dark and `0` for light.
+    #[wasm_bindgen(getter)]
+    pub fn modules(&self) -> Vec<u8> {
+        self.modules.clone()
+    }
+}
+
+/// Encode `text` and render it as an SVG document, see [Bitmap::to_svg](crate::placement::Bitmap::to_svg).
+///
+/// Throws an error if the text does not fit into a symbol.
+#[wasm_bindgen(js_name = encodeSvg)]
+pub fn encode_svg(text: &str, options: &WasmOptions) -> Result<String, JsError> {
+    Ok(options.svg(text)?)
+}
+
+/// Encode `text` and get the modules, e.g., to draw them on a canvas.
+///
+/// Throws an error if the text does not fit into a symbol.
+#[wasm_bindgen(js_name = encodeModules)]
+pub fn encode_modules(text: &str, options: &WasmOptions) -> Result<WasmSymbol, JsError> {
+    Ok(options.symbol(text)?)
+}
+
+#[test]
+fn test_wasm_options() {
+    let mut options = WasmOptions::new();
+    let symbol = options.symbol("Hello, web!").unwrap();
+    let code = DataMatrix::encode_str("Hello, web!", SymbolList::default()).unwrap();
+    assert_eq!((symbol.width(), symbol.height()), (32, 8));
+    assert_eq!(symbol.height(), code.bitmap().height());
+
+    options.set_square(true);
+    options.set_quiet_zone(2);
+    options.set_module_size(3.0);
+    let symbol = options.symbol("Hello, web!").unwrap();
+    assert_eq!((symbol.width(), symbol.height()), (16, 16));
+    let svg = options.svg("Hello, web!").unwrap();
+    assert!(svg.contains("width=\"60\" height=\"60\" viewBox=\"0 0 20 20\""));
+
+    options.set_module_size(0.0);
+    assert!(matches!(
+        options.svg("Hello, web!"),
+        Err(DataEncodingError::InvalidOptions(_))
+    ));
+
+    options.set_module_size(3.0);
+    let long = "x".repeat(5000);
+    assert!(matches!(
+        options.svg(&long),
+        Err(DataEncodingError::TooMuchData {
+            available: 1558,
+            ..
+        })
+    ));
+}
@@ -0,0 +1,162 @@
+//! Compare the codewords of some encodings against the files in `tests/golden/`.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the files again after an intended change
+//! of the encoder, and review the diff before committing it.
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use datamatrix::{DataMatrix, DataMatrixBuilder, EncodationType, SymbolSize};
+use pretty_assertions::assert_eq;
+
+/// Format the size and all codewords, including the error correction, of each symbol.
+fn format(symbols: &[DataMatrix]) -> String {
+    let mut out = String::new();
+    for symbol in symbols {
+        writeln!(out, "{:?}", symbol.size).unwrap();
+        for line in symbol.codewords().chunks(16) {
+            let line: Vec<String> = line.iter().map(|cw| cw.to_string()).collect();
+            writeln!(out, "{}", line.join(" ")).unwrap();
+        }
+    }
+    out
+}
+
+fn check(name: &str, symbols: &[DataMatrix]) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", name]
+        .iter()
+        .collect();
+    let path = path.with_extension("golden");
+    let actual = format(symbols);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("{}: {}, run with UPDATE_GOLDEN=1", path.display(), err));
+    assert_eq!(expected, actual, "{}", name);
+}
+
+fn check_single(name: &str, builder: DataMatrixBuilder, data: &[u8]) {
+    check(name, &[builder.encode(data).unwrap()]);
+}
+
+/// Check an encoding which uses `mode` besides ASCII.
+fn check_mode(name: &str, mode: EncodationType, data: &[u8]) {
+    let builder = DataMatrixBuilder::new().with_encodation_types(EncodationType::Ascii | mode);
+    let (symbol, segments) = builder.encode_with_segments(data).unwrap();
+    assert!(segments.iter().any(|s| s.1 == mode), "{:?}", segments);
+    check(name, &[symbol]);
+}
+
+#[test]
+fn golden_ascii() {
+    let builder = DataMatrixBuilder::new().with_encodation_types(EncodationType::Ascii);
+    check_single("ascii", builder, b"Hello, World! 1234567890 \xe4");
+}
+
+#[test]
+fn golden_c40() {
+    check_mode(
+        "c40",
+        EncodationType::C40,
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZ 0123",
+    );
+}
+
+#[test]
+fn golden_text() {
+    check_mode(
+        "text",
+        EncodationType::Text,
+        b"the quick brown fox jumps over the lazy dog",
+    );
+}
+
+#[test]
+fn golden_x12() {
+    check_mode("x12", EncodationType::X12, b"ABC>ABC123>ABCDE*XYZ 0987");
+}
+
+#[test]
+fn golden_edifact() {
+    check_mode(
+        "edifact",
+        EncodationType::Edifact,
+        b".-/+?:'=,;.-/+?:'=,;ABC",
+    );
+}
+
+#[test]
+fn golden_base256() {
+    let data: Vec<u8> = (0..40).map(|i| 128 + i * 3).collect();
+    check_mode("base256", EncodationType::Base256, &data);
+}
+
+#[test]
+fn golden_mixed() {
+    check_single(
+        "mixed",
+        DataMatrixBuilder::new(),
+        b"Label 2024: ORDER-4711 abcdefgh \xe4\xf6\xfc 0123456789",
+    );
+}
+
+#[test]
+fn golden_macro() {
+    check_single(
+        "macro",
+        DataMatrixBuilder::new(),
+        b"[)>\x1e05\x1d10ABC123\x1e\x04",
+    );
+}
+
+#[test]
+fn golden_gs1() {
+    check_single(
+        "gs1",
+        DataMatrixBuilder::new().with_gs1(true),
+        b"0109501101530003\x1d17140704\x1d10AB-123",
+    );
+}
+
+#[test]
+fn golden_structured_append() {
+    let data: Vec<u8> = (0..100).map(|i| b'A' + (i % 26) as u8).collect();
+    let symbols = DataMatrixBuilder::new()
+        .with_symbol_list(SymbolSize::Square16)
+        .with_file_id((42, 7))
+        .encode_structured(&data)
+        .unwrap();
+    check("structured_append", &symbols);
+}
+
+#[test]
+fn golden_files_covered() {
+    // every file belongs to a test, the files may still be written in the update mode
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        return;
+    }
+    let dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden"]
+        .iter()
+        .collect();
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "ascii.golden",
+            "base256.golden",
+            "c40.golden",
+            "edifact.golden",
+            "gs1.golden",
+            "macro.golden",
+            "mixed.golden",
+            "structured_append.golden",
+            "text.golden",
+            "x12.golden",
+        ]
+    );
+}